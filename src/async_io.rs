@@ -0,0 +1,387 @@
+//! Asynchronous readers and writers transcoding data as it passes through.
+//!
+//! [`TranscodeReader`] and [`TranscodeWriter`] wrap an asynchronous reader or
+//! writer and convert the data incrementally with a [`Converter`], so the
+//! semantics are the same as feeding the chunks to the converter by hand:
+//! sequences split across reads or writes are kept until the rest arrives,
+//! and strict converters fail on invalid input while lossy ones replace it.
+//!
+//! With feature `futures-io`, they implement `futures_io::AsyncRead` and
+//! `futures_io::AsyncWrite`. With feature `tokio`, they implement
+//! `tokio::io::AsyncRead` and `tokio::io::AsyncWrite`. Invalid input and
+//! characters that cannot be represented are reported as
+//! [`io::ErrorKind::InvalidData`] errors.
+//!
+//! The wrapped reader or writer must be [`Unpin`]; pin it with [`Box::pin`]
+//! otherwise.
+//!
+//! [`Box::pin`]: alloc::boxed::Box::pin
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "futures-io")]
+//! # futures::executor::block_on(async {
+//! use futures::io::AsyncReadExt;
+//! use iconv_native::async_io::TranscodeReader;
+//!
+//! let input = &b"\x82\xb3\x83\x86\x82\xe8"[..];
+//! let mut reader = TranscodeReader::new(input, "shift_jis", "utf-8").unwrap();
+//! let mut output = String::new();
+//! reader.read_to_string(&mut output).await?;
+//! assert_eq!(output, "さユり");
+//! # Ok::<(), std::io::Error>(())
+//! # }).unwrap();
+//! ```
+
+use core::pin::Pin;
+use core::task::{ready, Context, Poll};
+
+use std::io;
+
+use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
+
+use crate::{ConvertError, ConvertLossyError, Converter};
+
+/// Size of the buffer reading from the inner reader.
+const BUF_LEN: usize = 8 * 1024;
+
+fn invalid_data() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        ConvertError::InvalidInput.to_string(),
+    )
+}
+
+/// An asynchronous reader converting the data read from an inner reader.
+#[derive(Debug)]
+pub struct TranscodeReader<R> {
+    inner: R,
+    converter: Converter,
+    input: Box<[u8]>,
+    /// Converted data not read yet, starting at `pos`.
+    output: Vec<u8>,
+    pos: usize,
+    is_eof: bool,
+    is_failed: bool,
+}
+
+impl<R> TranscodeReader<R> {
+    fn with_converter(inner: R, converter: Converter) -> Self {
+        Self {
+            inner,
+            converter,
+            input: vec![0; BUF_LEN].into_boxed_slice(),
+            output: Vec::new(),
+            pos: 0,
+            is_eof: false,
+            is_failed: false,
+        }
+    }
+
+    /// Creates a reader converting data read from `inner` from `from_encoding`
+    /// to `to_encoding`, which fails on invalid input and characters that
+    /// cannot be represented.
+    ///
+    /// Returns [`ConvertError::UnknownConversion`] if either encoding is not
+    /// supported.
+    pub fn new(inner: R, from_encoding: &str, to_encoding: &str) -> Result<Self, ConvertError> {
+        let converter = Converter::new(from_encoding, to_encoding)?;
+        Ok(Self::with_converter(inner, converter))
+    }
+
+    /// Creates a reader converting data read from `inner` from `from_encoding`
+    /// to `to_encoding`, which replaces invalid input and characters that
+    /// cannot be represented.
+    ///
+    /// Returns [`ConvertLossyError::UnknownConversion`] if either encoding is
+    /// not supported.
+    pub fn new_lossy(
+        inner: R,
+        from_encoding: &str,
+        to_encoding: &str,
+    ) -> Result<Self, ConvertLossyError> {
+        let converter = Converter::new_lossy(from_encoding, to_encoding)?;
+        Ok(Self::with_converter(inner, converter))
+    }
+
+    /// Returns a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner reader. Reading from it
+    /// directly corrupts the converted data.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the inner reader. Converted data not read yet is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Fills the output until there is converted data to return, or the end
+    /// of the input is reached.
+    fn poll_output(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut read: impl FnMut(Pin<&mut R>, &mut Context<'_>, &mut [u8]) -> Poll<io::Result<usize>>,
+    ) -> Poll<io::Result<()>>
+    where
+        R: Unpin,
+    {
+        while self.pos == self.output.len() {
+            // Return the data before the invalid sequence first.
+            if self.is_failed {
+                return Poll::Ready(Err(invalid_data()));
+            }
+            if self.is_eof {
+                break;
+            }
+            self.output.clear();
+            self.pos = 0;
+            let len = ready!(read(Pin::new(&mut self.inner), cx, &mut self.input))?;
+            let result = if len == 0 {
+                self.is_eof = true;
+                self.converter.finish(&mut self.output)
+            } else {
+                self.converter.convert(&self.input[..len], &mut self.output)
+            };
+            self.is_failed = result.is_err();
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Copies converted data to `buf`, returning the number of bytes copied.
+    fn consume(&mut self, buf: &mut [u8]) -> usize {
+        let available = &self.output[self.pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.pos += len;
+        len
+    }
+}
+
+/// An asynchronous writer converting the data written to it, and writing the
+/// result to an inner writer.
+///
+/// Close (or shut down) the writer at the end of the data, so that an
+/// incomplete sequence at the end is reported, and pending output such as the
+/// last septets of packed GSM 03.38 is written.
+#[derive(Debug)]
+pub struct TranscodeWriter<W> {
+    inner: W,
+    converter: Converter,
+    /// Converted data not written yet, starting at `pos`.
+    output: Vec<u8>,
+    pos: usize,
+    is_finished: bool,
+}
+
+impl<W> TranscodeWriter<W> {
+    fn with_converter(inner: W, converter: Converter) -> Self {
+        Self {
+            inner,
+            converter,
+            output: Vec::new(),
+            pos: 0,
+            is_finished: false,
+        }
+    }
+
+    /// Creates a writer converting data from `from_encoding` to `to_encoding`
+    /// before writing it to `inner`, which fails on invalid input and
+    /// characters that cannot be represented.
+    ///
+    /// Returns [`ConvertError::UnknownConversion`] if either encoding is not
+    /// supported.
+    pub fn new(inner: W, from_encoding: &str, to_encoding: &str) -> Result<Self, ConvertError> {
+        let converter = Converter::new(from_encoding, to_encoding)?;
+        Ok(Self::with_converter(inner, converter))
+    }
+
+    /// Creates a writer converting data from `from_encoding` to `to_encoding`
+    /// before writing it to `inner`, which replaces invalid input and
+    /// characters that cannot be represented.
+    ///
+    /// Returns [`ConvertLossyError::UnknownConversion`] if either encoding is
+    /// not supported.
+    pub fn new_lossy(
+        inner: W,
+        from_encoding: &str,
+        to_encoding: &str,
+    ) -> Result<Self, ConvertLossyError> {
+        let converter = Converter::new_lossy(from_encoding, to_encoding)?;
+        Ok(Self::with_converter(inner, converter))
+    }
+
+    /// Returns a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner writer. Writing to it
+    /// directly corrupts the converted data.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the inner writer. Converted data not written yet is lost.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Writes all converted data to the inner writer.
+    fn poll_drain(
+        &mut self,
+        cx: &mut Context<'_>,
+        write: &mut impl FnMut(Pin<&mut W>, &mut Context<'_>, &[u8]) -> Poll<io::Result<usize>>,
+    ) -> Poll<io::Result<()>>
+    where
+        W: Unpin,
+    {
+        while self.pos < self.output.len() {
+            let len = ready!(write(
+                Pin::new(&mut self.inner),
+                cx,
+                &self.output[self.pos..]
+            ))?;
+            if len == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pos += len;
+        }
+        self.output.clear();
+        self.pos = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_write_with(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+        mut write: impl FnMut(Pin<&mut W>, &mut Context<'_>, &[u8]) -> Poll<io::Result<usize>>,
+    ) -> Poll<io::Result<usize>>
+    where
+        W: Unpin,
+    {
+        // Bound the buffered output by converting only after the previous
+        // output has been written.
+        ready!(self.poll_drain(cx, &mut write))?;
+        match self.converter.convert(buf, &mut self.output) {
+            Ok(()) => Poll::Ready(Ok(buf.len())),
+            Err(_) => Poll::Ready(Err(invalid_data())),
+        }
+    }
+
+    /// Finishes the conversion and writes the rest of the output.
+    fn poll_finish(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut write: impl FnMut(Pin<&mut W>, &mut Context<'_>, &[u8]) -> Poll<io::Result<usize>>,
+    ) -> Poll<io::Result<()>>
+    where
+        W: Unpin,
+    {
+        ready!(self.poll_drain(cx, &mut write))?;
+        if !self.is_finished {
+            self.is_finished = true;
+            if self.converter.finish(&mut self.output).is_err() {
+                return Poll::Ready(Err(invalid_data()));
+            }
+            ready!(self.poll_drain(cx, &mut write))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "futures-io")]
+mod futures_impl {
+    use super::*;
+
+    use futures_io::{AsyncRead, AsyncWrite};
+
+    impl<R: AsyncRead + Unpin> AsyncRead for TranscodeReader<R> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let this = self.get_mut();
+            ready!(this.poll_output(cx, |inner, cx, input| inner.poll_read(cx, input)))?;
+            Poll::Ready(Ok(this.consume(buf)))
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin> AsyncWrite for TranscodeWriter<W> {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.get_mut()
+                .poll_write_with(cx, buf, |inner, cx, output| inner.poll_write(cx, output))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            ready!(this.poll_drain(cx, &mut |inner, cx, output| inner.poll_write(cx, output)))?;
+            Pin::new(&mut this.inner).poll_flush(cx)
+        }
+
+        fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            ready!(this.poll_finish(cx, |inner, cx, output| inner.poll_write(cx, output)))?;
+            Pin::new(&mut this.inner).poll_close(cx)
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+mod tokio_impl {
+    use super::*;
+
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    impl<R: AsyncRead + Unpin> AsyncRead for TranscodeReader<R> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            ready!(this.poll_output(cx, |inner, cx, input| {
+                let mut input = ReadBuf::new(input);
+                ready!(inner.poll_read(cx, &mut input))?;
+                Poll::Ready(Ok(input.filled().len()))
+            }))?;
+            let len = this.consume(buf.initialize_unfilled());
+            buf.advance(len);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin> AsyncWrite for TranscodeWriter<W> {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.get_mut()
+                .poll_write_with(cx, buf, |inner, cx, output| inner.poll_write(cx, output))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            ready!(this.poll_drain(cx, &mut |inner, cx, output| inner.poll_write(cx, output)))?;
+            Pin::new(&mut this.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            ready!(this.poll_finish(cx, |inner, cx, output| inner.poll_write(cx, output)))?;
+            Pin::new(&mut this.inner).poll_shutdown(cx)
+        }
+    }
+}
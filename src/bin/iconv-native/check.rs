@@ -0,0 +1,193 @@
+//! The `check` subcommand, validating files in a declared encoding.
+
+use std::ffi::OsString;
+use std::io::{self, Read};
+use std::path::Path;
+
+use iconv_native::Decoder;
+
+use crate::convert::{open_input, read_chunk, CHUNK_LEN};
+use crate::{report, Failure, NAME};
+
+const USAGE: &str = "\
+Usage: iconv-native check -f NAME [OPTION...] [FILE...]
+Check that the files are valid in the given encoding, and print the position of
+the first invalid sequence in each file that is not.
+
+  -f, --from-code=NAME   encoding of the files
+  -q, --quiet            do not print anything for valid files
+  -?, --help             print this help
+
+FILE may be - for stdin, which is also the default.
+
+Exit status:
+  0  all files are valid
+  1  some files are invalid
+  2  invalid arguments
+  3  unknown encoding
+  4  I/O error
+";
+
+#[derive(Debug)]
+struct Args {
+    from: String,
+    quiet: bool,
+    inputs: Vec<OsString>,
+}
+
+fn parse_args(mut parser: lexopt::Parser) -> Result<Option<Args>, lexopt::Error> {
+    use lexopt::prelude::*;
+
+    let mut from = None;
+    let mut quiet = false;
+    let mut inputs = vec![];
+
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Short('f') | Long("from-code") => from = Some(parser.value()?.string()?),
+            Short('q') | Long("quiet") => quiet = true,
+            Short('?') | Short('h') | Long("help") | Long("usage") => {
+                print!("{USAGE}");
+                return Ok(None);
+            }
+            Value(input) => inputs.push(input),
+            _ => return Err(arg.unexpected()),
+        }
+    }
+
+    let Some(from) = from else {
+        return Err("missing encoding, specify it with -f".into());
+    };
+    if inputs.is_empty() {
+        inputs.push("-".into());
+    }
+    Ok(Some(Args {
+        from,
+        quiet,
+        inputs,
+    }))
+}
+
+/// Position of the first invalid sequence in the input.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct InvalidPosition {
+    /// Byte offset from the start of the input.
+    pub(crate) offset: u64,
+    /// 1-based line number.
+    pub(crate) line: usize,
+    /// 1-based column, counted in characters.
+    pub(crate) column: usize,
+}
+
+/// Decodes `input` a chunk at a time, and returns the position of the first
+/// invalid sequence if there is one.
+pub(crate) fn find_invalid(
+    input: &mut dyn Read,
+    mut decoder: Decoder,
+) -> io::Result<Option<InvalidPosition>> {
+    let mut buf = vec![0; CHUNK_LEN];
+    let mut decoded = String::new();
+    let (mut line, mut column) = (1, 1);
+    loop {
+        let len = read_chunk(input, &mut buf)?;
+        decoded.clear();
+        let result = match len {
+            0 => decoder.finish(&mut decoded),
+            len => decoder.decode(&buf[..len], &mut decoded),
+        };
+        for ch in decoded.chars() {
+            if ch == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        if result.is_err() {
+            return Ok(Some(InvalidPosition {
+                offset: decoder.position(),
+                line,
+                column,
+            }));
+        }
+        if len == 0 {
+            return Ok(None);
+        }
+    }
+}
+
+pub(crate) fn run(parser: lexopt::Parser) -> Result<(), Failure> {
+    let args = match parse_args(parser) {
+        Ok(Some(args)) => args,
+        Ok(None) => return Ok(()),
+        Err(err) => {
+            report(err);
+            eprintln!("Try '{NAME} check --help' for more information.");
+            return Err(Failure::Usage);
+        }
+    };
+
+    let Ok(decoder) = Decoder::new(&args.from) else {
+        report(format_args!("unknown encoding `{}'", args.from));
+        return Err(Failure::UnknownEncoding);
+    };
+    let mut result = Ok(());
+    for input in &args.inputs {
+        let name = Path::new(input).display();
+        let invalid =
+            open_input(input).and_then(|mut reader| find_invalid(&mut reader, decoder.clone()));
+        match invalid {
+            Ok(None) => {
+                if !args.quiet {
+                    println!("{name}: ok");
+                }
+            }
+            Ok(Some(InvalidPosition {
+                offset,
+                line,
+                column,
+            })) => {
+                println!(
+                    "{name}:{line}:{column}: invalid {} sequence at byte offset {offset}",
+                    args.from
+                );
+                result = result.and(Err(Failure::InvalidInput));
+            }
+            Err(err) => {
+                report(format_args!("cannot read input file `{name}': {err}"));
+                result = result.and(Err(Failure::Io));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_invalid() {
+        let testcases = [
+            (&b"\xff"[..], "utf-8", 0, 1, 1),
+            (b"ab\ncd\xffe", "utf-8", 5, 2, 3),
+            ("芙宁\n娜".as_bytes(), "ascii", 0, 1, 1),
+            (b"\xe8\x8a\x99\xe5\xae\x81\xe5\xa8", "utf-8", 6, 1, 3),
+            (b"\xdc\xbd\n\xc4\xfe\xc4", "gbk", 5, 2, 2),
+            (b"a\0b\0\0\xd8c\0", "utf-16le", 4, 1, 3),
+        ];
+        for (input, encoding, offset, line, column) in testcases {
+            let decoder = Decoder::new(encoding).unwrap();
+            let result = find_invalid(&mut &input[..], decoder).unwrap();
+            let expected = InvalidPosition {
+                offset,
+                line,
+                column,
+            };
+            assert_eq!(result, Some(expected), "{input:?} {encoding}");
+        }
+        let decoder = Decoder::new("utf-8").unwrap();
+        let result = find_invalid(&mut &b"valid\n"[..], decoder).unwrap();
+        assert_eq!(result, None);
+    }
+}
@@ -0,0 +1,494 @@
+//! The default mode, converting files like GNU `iconv`.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+use iconv_native::{encode, ConvertError, Decoder, Encoder};
+
+use crate::{report, Failure, NAME};
+
+const USAGE: &str = "\
+Usage: iconv-native [OPTION...] [FILE...]
+   or: iconv-native recode -f NAME [OPTION...] PATTERN...
+   or: iconv-native check -f NAME [OPTION...] [FILE...]
+   or: iconv-native detect [OPTION...] [FILE...]
+Convert encoding of the given files from one encoding to another.
+
+  -f, --from-code=NAME   encoding of the input (default: UTF-8)
+  -t, --to-code=NAME     encoding of the output (default: UTF-8)
+  -c                     omit invalid characters from the output
+  -s, --silent           suppress warnings about invalid input
+  -o, --output=FILE      write the output to FILE instead of stdout
+  -l, --list             list known encodings
+  -?, --help             print this help
+
+Append //TRANSLIT to the target encoding to replace characters that cannot be
+represented, or //IGNORE to omit them. Without -c or either suffix, the
+conversion is strict. FILE may be - for stdin, which is also the default.
+Run 'iconv-native SUBCOMMAND --help' for the usage of a subcommand.
+
+Exit status:
+  0  success
+  1  invalid input or unrepresentable characters
+  2  invalid arguments
+  3  unknown encoding or conversion
+  4  I/O error
+";
+
+/// Size of the chunks read from the input, so that input of any size is
+/// converted in bounded memory.
+pub(crate) const CHUNK_LEN: usize = 64 * 1024;
+
+const BUILTIN_ENCODINGS: &[&str] = &[
+    "UTF-8",
+    "UTF-16",
+    "UTF-16LE",
+    "UTF-16BE",
+    "UTF-32",
+    "UTF-32LE",
+    "UTF-32BE",
+    "WTF-8",
+    "CESU-8",
+    "MUTF-8",
+    "UCS-2",
+    "UCS-2LE",
+    "UCS-2BE",
+    "UTF-7",
+    "UTF-7-IMAP",
+    "GSM0338",
+    "GSM0338-PACKED",
+    "IBM037",
+    "IBM273",
+    "IBM277",
+    "IBM278",
+    "IBM280",
+    "IBM284",
+    "IBM285",
+    "IBM297",
+    "IBM500",
+    "IBM1047",
+    "IBM01140",
+    "IBM01141",
+    "IBM01142",
+    "IBM01143",
+    "IBM01144",
+    "IBM01145",
+    "IBM01146",
+    "IBM01147",
+    "IBM01148",
+    "IBM01149",
+];
+
+const PLATFORM_ENCODINGS: &[&str] = &[
+    "US-ASCII",
+    "ISO-8859-1",
+    "ISO-8859-2",
+    "ISO-8859-5",
+    "ISO-8859-7",
+    "ISO-8859-15",
+    "WINDOWS-1250",
+    "WINDOWS-1251",
+    "WINDOWS-1252",
+    "WINDOWS-1253",
+    "WINDOWS-1254",
+    "WINDOWS-1255",
+    "WINDOWS-1256",
+    "WINDOWS-1257",
+    "WINDOWS-1258",
+    "KOI8-R",
+    "KOI8-U",
+    "MACINTOSH",
+    "GBK",
+    "GB18030",
+    "BIG5",
+    "SHIFT_JIS",
+    "EUC-JP",
+    "ISO-2022-JP",
+    "EUC-KR",
+];
+
+/// How characters that cannot be converted are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Fallback {
+    /// Omit invalid input and unrepresentable characters.
+    pub(crate) omit: bool,
+    /// Replace invalid input and unrepresentable characters.
+    pub(crate) replace: bool,
+}
+
+impl Fallback {
+    /// Strips `//TRANSLIT` and `//IGNORE` suffixes from `encoding`, in any
+    /// order and case.
+    pub(crate) fn strip_suffixes(mut self, mut encoding: &str) -> (Self, &str) {
+        while let Some(pos) = encoding.rfind("//") {
+            let suffix = &encoding[pos + 2..];
+            if suffix.eq_ignore_ascii_case("translit") {
+                self.replace = true;
+            } else if suffix.eq_ignore_ascii_case("ignore") {
+                self.omit = true;
+            } else if !suffix.is_empty() {
+                break;
+            }
+            encoding = &encoding[..pos];
+        }
+        (self, encoding)
+    }
+
+    pub(crate) fn transcoder(
+        self,
+        from_encoding: &str,
+        to_encoding: &str,
+    ) -> Result<Transcoder, ConvertError> {
+        // Invalid input is omitted by skipping it after a strict decoder stopped there, since a
+        // lossy one cannot tell replaced input from U+FFFD in the input.
+        let decoder = if self.replace && !self.omit {
+            Decoder::new_lossy(from_encoding)?
+        } else {
+            Decoder::new(from_encoding)?
+        };
+        let encoder = if self.replace {
+            Encoder::new_lossy(to_encoding)?
+        } else {
+            Encoder::new(to_encoding)?
+        };
+        Ok(Transcoder {
+            fallback: self,
+            from_encoding: from_encoding.into(),
+            to_encoding: to_encoding.into(),
+            decoder,
+            undecoded: Vec::new(),
+            encoder,
+            decoded: String::new(),
+        })
+    }
+}
+
+/// Converts input in chunks, applying a [`Fallback`].
+#[derive(Debug, Clone)]
+pub(crate) struct Transcoder {
+    fallback: Fallback,
+    from_encoding: String,
+    to_encoding: String,
+    decoder: Decoder,
+    /// Input passed to `decoder` and not decoded yet, from which decoding restarts after an
+    /// invalid sequence is omitted.
+    undecoded: Vec<u8>,
+    encoder: Encoder,
+    decoded: String,
+}
+
+impl Transcoder {
+    /// Converts a chunk of input, or the rest of it if `input` is `None`,
+    /// appending the result to `output`.
+    pub(crate) fn convert(
+        &mut self,
+        input: Option<&[u8]>,
+        output: &mut Vec<u8>,
+    ) -> Result<(), ConvertError> {
+        self.decoded.clear();
+        let decoded = match input {
+            _ if self.fallback.omit => self.decode_omitting(input),
+            Some(input) => self.decoder.decode(input, &mut self.decoded),
+            None => self.decoder.finish(&mut self.decoded),
+        };
+        let encoded = match self.encoder.encode(&self.decoded, output) {
+            Err(ConvertError::InvalidInput) if self.fallback.omit => {
+                // Encode the chunk as a whole afterwards, since stateful encodings and BOMs must
+                // not be repeated for every character.
+                let mut buf = [0; 4];
+                let representable: String = self
+                    .decoded
+                    .chars()
+                    .filter(|ch| encode(ch.encode_utf8(&mut buf), &self.to_encoding).is_ok())
+                    .collect();
+                self.encoder.encode(&representable, output)
+            }
+            encoded => encoded,
+        };
+        if input.is_none() {
+            self.encoder.finish(output)?;
+        }
+        decoded.and(encoded)
+    }
+
+    /// Decodes a chunk of input, or the rest of it if `input` is `None`, skipping invalid
+    /// sequences.
+    fn decode_omitting(&mut self, input: Option<&[u8]>) -> Result<(), ConvertError> {
+        let last = input.is_none();
+        let mut input = input.unwrap_or_default().to_vec();
+        loop {
+            let position = self.decoder.position();
+            self.undecoded.extend_from_slice(&input);
+            let mut result = self.decoder.decode(&input, &mut self.decoded);
+            if result.is_ok() && last {
+                result = self.decoder.finish(&mut self.decoded);
+            }
+            match result {
+                Ok(()) if last => {
+                    self.undecoded.clear();
+                    return Ok(());
+                }
+                Ok(()) => {
+                    let decoded_len = self.decoder.position() - position;
+                    self.undecoded.drain(..decoded_len as usize);
+                    return Ok(());
+                }
+                Err(ConvertError::InvalidInput) => {
+                    // Restart after the first byte of the invalid sequence, so that the rest of
+                    // it is skipped as well unless a valid sequence starts there.
+                    let decoded_len = self.decoder.position() - position;
+                    self.undecoded.drain(..decoded_len as usize + 1);
+                    input = std::mem::take(&mut self.undecoded);
+                    self.decoder = Decoder::new(&self.from_encoding)?;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Converts everything read from `input` to `output`, a chunk at a time.
+    pub(crate) fn convert_stream(
+        &mut self,
+        input: &mut dyn Read,
+        output: &mut dyn Write,
+    ) -> Result<(), StreamError> {
+        let mut buf = vec![0; CHUNK_LEN];
+        let mut converted = vec![];
+        loop {
+            let len = read_chunk(input, &mut buf).map_err(StreamError::Read)?;
+            converted.clear();
+            let result = self.convert((len > 0).then(|| &buf[..len]), &mut converted);
+            output.write_all(&converted).map_err(StreamError::Write)?;
+            result.map_err(|_| StreamError::InvalidInput)?;
+            if len == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Reasons why [`Transcoder::convert_stream`] stopped.
+#[derive(Debug)]
+pub(crate) enum StreamError {
+    Read(io::Error),
+    Write(io::Error),
+    /// Invalid input or a character that cannot be represented.
+    InvalidInput,
+}
+
+#[derive(Debug)]
+struct Args {
+    from: String,
+    to: String,
+    fallback: Fallback,
+    silent: bool,
+    output: Option<OsString>,
+    inputs: Vec<OsString>,
+}
+
+fn parse_args(mut parser: lexopt::Parser) -> Result<Option<Args>, lexopt::Error> {
+    use lexopt::prelude::*;
+
+    let mut from = None;
+    let mut to = None;
+    let mut fallback = Fallback::default();
+    let mut silent = false;
+    let mut output = None;
+    let mut inputs = vec![];
+
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Short('f') | Long("from-code") => from = Some(parser.value()?.string()?),
+            Short('t') | Long("to-code") => to = Some(parser.value()?.string()?),
+            Short('c') => fallback.omit = true,
+            Short('s') | Long("silent") => silent = true,
+            Short('o') | Long("output") => output = Some(parser.value()?),
+            Short('l') | Long("list") => {
+                list_encodings();
+                return Ok(None);
+            }
+            Short('?') | Short('h') | Long("help") | Long("usage") => {
+                print!("{USAGE}");
+                return Ok(None);
+            }
+            Value(input) => inputs.push(input),
+            _ => return Err(arg.unexpected()),
+        }
+    }
+
+    let (fallback, to) = match &to {
+        Some(to) => fallback.strip_suffixes(to),
+        None => (fallback, "UTF-8"),
+    };
+    let to = to.to_owned();
+    if inputs.is_empty() {
+        inputs.push("-".into());
+    }
+    Ok(Some(Args {
+        from: from.unwrap_or_else(|| "UTF-8".into()),
+        to,
+        fallback,
+        silent,
+        output,
+        inputs,
+    }))
+}
+
+fn list_encodings() {
+    println!("Built-in encodings, available on every platform:");
+    for encoding in BUILTIN_ENCODINGS {
+        println!("  {encoding}");
+    }
+    println!("Common encodings provided by the platform (availability varies):");
+    for encoding in PLATFORM_ENCODINGS {
+        println!("  {encoding}");
+    }
+}
+
+pub(crate) fn open_input(path: &OsString) -> io::Result<Box<dyn Read>> {
+    Ok(match path {
+        path if path == "-" => Box::new(io::stdin().lock()),
+        path => Box::new(File::open(path)?),
+    })
+}
+
+/// Reads the next chunk of `input` into `buf`, returning 0 at the end of the
+/// input.
+pub(crate) fn read_chunk(input: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match input.read(buf) {
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+pub(crate) fn read_input(path: &OsString) -> io::Result<Vec<u8>> {
+    let mut buf = vec![];
+    open_input(path)?.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+pub(crate) fn open_output(path: Option<&OsString>) -> io::Result<Box<dyn Write>> {
+    Ok(match path {
+        Some(path) if path != "-" => Box::new(BufWriter::new(File::create(path)?)),
+        _ => Box::new(BufWriter::new(io::stdout().lock())),
+    })
+}
+
+pub(crate) fn run(parser: lexopt::Parser) -> Result<(), Failure> {
+    let args = match parse_args(parser) {
+        Ok(Some(args)) => args,
+        Ok(None) => return Ok(()),
+        Err(err) => {
+            report(err);
+            eprintln!("Try '{NAME} --help' for more information.");
+            return Err(Failure::Usage);
+        }
+    };
+
+    let mut output = open_output(args.output.as_ref()).map_err(|err| {
+        report(format_args!("cannot open output file: {err}"));
+        Failure::Io
+    })?;
+    let transcoder = match args.fallback.transcoder(&args.from, &args.to) {
+        Ok(transcoder) => transcoder,
+        Err(_) => {
+            report(format_args!(
+                "conversion from `{}' to `{}' is not supported",
+                args.from, args.to
+            ));
+            return Err(Failure::UnknownEncoding);
+        }
+    };
+    let mut result = Ok(());
+    for input in &args.inputs {
+        let name = Path::new(input).display();
+        let mut reader = match open_input(input) {
+            Ok(reader) => reader,
+            Err(err) => {
+                report(format_args!("cannot open input file `{name}': {err}"));
+                result = result.and(Err(Failure::Io));
+                continue;
+            }
+        };
+        // Start over for each input, since a failed one may leave an incomplete sequence behind.
+        match transcoder.clone().convert_stream(&mut reader, &mut output) {
+            Ok(()) => {}
+            Err(StreamError::Read(err)) => {
+                report(format_args!("cannot read input file `{name}': {err}"));
+                result = result.and(Err(Failure::Io));
+            }
+            Err(StreamError::Write(err)) => {
+                report(format_args!("cannot write output: {err}"));
+                return Err(Failure::Io);
+            }
+            Err(StreamError::InvalidInput) => {
+                if !args.silent {
+                    report(format_args!("{name}: cannot convert invalid input"));
+                }
+                result = result.and(Err(Failure::InvalidInput));
+            }
+        }
+    }
+    output.flush().map_err(|err| {
+        report(format_args!("cannot write output: {err}"));
+        Failure::Io
+    })?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_suffixes() {
+        let testcases = [
+            ("UTF-8", false, false, "UTF-8"),
+            ("ascii//TRANSLIT", false, true, "ascii"),
+            ("ascii//ignore", true, false, "ascii"),
+            ("ascii//TRANSLIT//IGNORE", true, true, "ascii"),
+            ("ascii//", false, false, "ascii"),
+            ("ascii//other", false, false, "ascii//other"),
+        ];
+        for (input, omit, replace, encoding) in testcases {
+            let (fallback, result) = Fallback::default().strip_suffixes(input);
+            assert_eq!(fallback, Fallback { omit, replace }, "{input}");
+            assert_eq!(result, encoding, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_transcoder() {
+        let convert = |fallback: Fallback, input: &[u8], from: &str, to: &str| {
+            let mut transcoder = fallback.transcoder(from, to)?;
+            let mut output = vec![];
+            for byte in input {
+                transcoder.convert(Some(&[*byte]), &mut output)?;
+            }
+            transcoder.convert(None, &mut output).map(|()| output)
+        };
+        let strict = Fallback::default();
+        let omit = Fallback {
+            omit: true,
+            replace: false,
+        };
+        let result = convert(strict, "a€b".as_bytes(), "utf-8", "iso-8859-1");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = convert(omit, "a€b".as_bytes(), "utf-8", "iso-8859-1");
+        assert_eq!(result.unwrap(), b"ab");
+        let result = convert(omit, b"a\xffb", "utf-8", "utf-16le");
+        assert_eq!(result.unwrap(), b"a\0b\0");
+        let result = convert(omit, b"a\xef\xbf\xbd\xe3\x81b\xe3", "utf-8", "utf-8");
+        assert_eq!(result.unwrap(), "a\u{fffd}b".as_bytes());
+        let result = convert(omit, b"\x82\xa0\x82\xff\x82", "shift_jis", "utf-8");
+        assert_eq!(result.unwrap(), "\u{3042}".as_bytes());
+        let result = convert(strict, b"a\x1b$B$\"\x1b(B", "iso-2022-jp", "utf-8");
+        assert_eq!(result.unwrap(), "aあ".as_bytes());
+        let result = convert(strict, b"a", "utf-8", "invalid_encoding");
+        assert_eq!(result, Err(ConvertError::UnknownConversion));
+    }
+}
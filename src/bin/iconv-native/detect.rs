@@ -0,0 +1,302 @@
+//! The `detect` subcommand, guessing the encoding of files.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+use iconv_native::decode;
+
+use crate::convert::read_input;
+use crate::{report, Failure, NAME};
+
+const USAGE: &str = "\
+Usage: iconv-native detect [OPTION...] [FILE...]
+Print the likely encodings of the files, most likely first, each followed by a
+confidence between 0 and 1.
+
+  -c, --candidates=LIST  comma separated encodings to consider
+  -?, --help             print this help
+
+FILE may be - for stdin, which is also the default. Detection is heuristic: a
+candidate is considered only if the file decodes strictly, and is ranked by how
+plausible the decoded text looks.
+
+Exit status:
+  0  success
+  1  no candidate matches some files
+  2  invalid arguments
+  4  I/O error
+";
+
+/// Candidates for inputs without a BOM, in order of preference among equally
+/// plausible results. Some texts decode to plausible characters in several of
+/// them, e.g. Japanese kana share their code points in GBK and EUC-JP.
+const DEFAULT_CANDIDATES: &[&str] = &[
+    "UTF-8",
+    "GBK",
+    "SHIFT_JIS",
+    "EUC-JP",
+    "EUC-KR",
+    "BIG5",
+    "WINDOWS-1252",
+    "ISO-2022-JP",
+    "ISO-2022-KR",
+    "HZ-GB-2312",
+    "UTF-7",
+];
+
+const BOMS: &[(&[u8], &str)] = &[
+    (b"\xef\xbb\xbf", "UTF-8"),
+    (b"\xff\xfe\0\0", "UTF-32LE"),
+    (b"\0\0\xfe\xff", "UTF-32BE"),
+    (b"\xff\xfe", "UTF-16LE"),
+    (b"\xfe\xff", "UTF-16BE"),
+];
+
+#[derive(Debug)]
+struct Args {
+    candidates: Option<Vec<String>>,
+    inputs: Vec<OsString>,
+}
+
+fn parse_args(mut parser: lexopt::Parser) -> Result<Option<Args>, lexopt::Error> {
+    use lexopt::prelude::*;
+
+    let mut candidates = None;
+    let mut inputs = vec![];
+
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Short('c') | Long("candidates") => {
+                let list = parser.value()?.string()?;
+                candidates = Some(list.split(',').map(|s| s.trim().to_owned()).collect());
+            }
+            Short('?') | Short('h') | Long("help") | Long("usage") => {
+                print!("{USAGE}");
+                return Ok(None);
+            }
+            Value(input) => inputs.push(input),
+            _ => return Err(arg.unexpected()),
+        }
+    }
+
+    if inputs.is_empty() {
+        inputs.push("-".into());
+    }
+    Ok(Some(Args { candidates, inputs }))
+}
+
+fn is_hangul(ch: char) -> bool {
+    matches!(ch, '\u{ac00}'..='\u{d7a3}')
+}
+
+fn is_latin_letter(ch: char) -> bool {
+    matches!(ch, '\u{c0}'..='\u{24f}') && ch.is_alphabetic()
+}
+
+/// How plausible the non-ASCII `ch` is as part of a text, between 0 and 1.
+///
+/// `has_hangul` tells whether the text contains Hangul, in which case kana and
+/// ideographs are unlikely. `next_to_ascii_letter` tells whether either
+/// neighbour is an ASCII letter, as accented letters rarely stand alone.
+fn char_weight(ch: char, has_hangul: bool, next_to_ascii_letter: bool) -> f64 {
+    match ch {
+        '\u{80}'..='\u{9f}' | '\u{e000}'..='\u{f8ff}' | char::REPLACEMENT_CHARACTER => 0.0,
+        '\u{3040}'..='\u{30ff}' | '\u{4e00}'..='\u{9fff}' if has_hangul => 0.2,
+        // Kana and Hangul only appear in Japanese and Korean text, so they
+        // win over a reading made only of ideographs.
+        '\u{3040}'..='\u{30ff}' | '\u{ac00}'..='\u{d7a3}' => 1.0,
+        '\u{4e00}'..='\u{9fff}' => 0.9,
+        _ if is_latin_letter(ch) => {
+            if next_to_ascii_letter {
+                1.0
+            } else {
+                0.4
+            }
+        }
+        // Greek and Cyrillic
+        '\u{370}'..='\u{4ff}' => 1.0,
+        // General punctuation, CJK punctuation and fullwidth ASCII
+        '\u{2010}'..='\u{206f}' | '\u{3000}'..='\u{303f}' | '\u{ff01}'..='\u{ff5e}' => 1.0,
+        // Halfwidth katakana
+        '\u{ff61}'..='\u{ff9f}' => 0.3,
+        _ => 0.5,
+    }
+}
+
+/// Scores the decoded text by the mean weight of its non-ASCII characters.
+/// Texts with control characters other than whitespace are implausible.
+fn score(text: &str) -> f64 {
+    let chars: Vec<char> = text.chars().collect();
+    let has_hangul = chars.iter().copied().any(is_hangul);
+    let is_ascii_letter = |idx: Option<usize>| {
+        idx.and_then(|idx| chars.get(idx))
+            .is_some_and(char::is_ascii_alphabetic)
+    };
+    let (mut sum, mut count) = (0.0, 0);
+    for (idx, &ch) in chars.iter().enumerate() {
+        if ch.is_ascii_control() && !matches!(ch, '\t' | '\n' | '\x0c' | '\r') {
+            return 0.0;
+        }
+        if ch.is_ascii() {
+            continue;
+        }
+        let next_to_ascii_letter =
+            is_ascii_letter(idx.checked_sub(1)) || is_ascii_letter(Some(idx + 1));
+        sum += char_weight(ch, has_hangul, next_to_ascii_letter);
+        count += 1;
+    }
+    if count > 0 {
+        sum / f64::from(count)
+    } else {
+        1.0
+    }
+}
+
+/// Whether `input` contains the escape sequences or shifts of a 7-bit
+/// stateful encoding: ESC for ISO-2022, `~{` for HZ, or a base64 run between
+/// `+` and `-` for UTF-7.
+fn has_escapes(input: &[u8]) -> bool {
+    let is_base64 = |b: &u8| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/');
+    let has_utf7_run = input.iter().enumerate().any(|(idx, &b)| {
+        let rest = &input[idx + 1..];
+        let run = rest.iter().take_while(|b| is_base64(b)).count();
+        b == b'+' && run >= 2 && rest.get(run) == Some(&b'-')
+    });
+    input.contains(&0x1b) || input.windows(2).any(|w| w == b"~{") || has_utf7_run
+}
+
+/// Candidates for UTF-16 and UTF-32 without a BOM, guessed from the positions
+/// of NUL bytes, which ASCII compatible encodings never contain.
+fn wide_candidates(input: &[u8]) -> Vec<&'static str> {
+    let (even, odd) =
+        input
+            .iter()
+            .enumerate()
+            .filter(|(_, &b)| b == 0)
+            .fold((0, 0), |(even, odd), (idx, _)| {
+                if idx % 2 == 0 {
+                    (even + 1, odd)
+                } else {
+                    (even, odd + 1)
+                }
+            });
+    if even + odd == 0 {
+        return vec![];
+    }
+    let utf16 = if odd >= even { "UTF-16LE" } else { "UTF-16BE" };
+    vec![utf16, "UTF-32LE", "UTF-32BE"]
+}
+
+/// Returns the candidates `input` decodes strictly in, with their confidence,
+/// most likely first.
+pub(crate) fn detect(input: &[u8], candidates: Option<&[String]>) -> Vec<(String, f64)> {
+    if candidates.is_none() {
+        if let Some((_, encoding)) = BOMS.iter().find(|(bom, _)| input.starts_with(bom)) {
+            if decode(input, encoding).is_ok() {
+                return vec![(encoding.to_string(), 1.0)];
+            }
+        }
+        if input.is_ascii() && !input.contains(&0) && !has_escapes(input) {
+            return vec![("US-ASCII".into(), 1.0)];
+        }
+    }
+    let candidates: Vec<&str> = match candidates {
+        Some(candidates) => candidates.iter().map(String::as_str).collect(),
+        None => wide_candidates(input)
+            .into_iter()
+            .chain(DEFAULT_CANDIDATES.iter().copied())
+            .collect(),
+    };
+    let mut ranked: Vec<_> = candidates
+        .into_iter()
+        .filter_map(|encoding| {
+            let text = decode(input, encoding).ok()?;
+            // A reading that leaves escape sequences in the text is less
+            // plausible than one that decodes them.
+            let penalty = if has_escapes(text.as_bytes()) {
+                0.5
+            } else {
+                1.0
+            };
+            Some((encoding.to_string(), score(&text) * penalty))
+        })
+        .collect();
+    // Stable, so that candidates listed first win ties
+    ranked.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    ranked
+}
+
+pub(crate) fn run(parser: lexopt::Parser) -> Result<(), Failure> {
+    let args = match parse_args(parser) {
+        Ok(Some(args)) => args,
+        Ok(None) => return Ok(()),
+        Err(err) => {
+            report(err);
+            eprintln!("Try '{NAME} detect --help' for more information.");
+            return Err(Failure::Usage);
+        }
+    };
+
+    let mut result = Ok(());
+    for input in &args.inputs {
+        let name = Path::new(input).display();
+        let buf = match read_input(input) {
+            Ok(buf) => buf,
+            Err(err) => {
+                report(format_args!("cannot open input file `{name}': {err}"));
+                result = result.and(Err(Failure::Io));
+                continue;
+            }
+        };
+        let ranked = detect(&buf, args.candidates.as_deref());
+        if ranked.is_empty() {
+            println!("{name}: unknown");
+            result = result.and(Err(Failure::InvalidInput));
+            continue;
+        }
+        let ranked: Vec<_> = ranked
+            .iter()
+            .map(|(encoding, confidence)| format!("{encoding} ({confidence:.2})"))
+            .collect();
+        println!("{name}: {}", ranked.join(", "));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let testcases = [
+            ("ascii only".as_bytes(), "US-ASCII"),
+            ("芙宁娜".as_bytes(), "UTF-8"),
+            (b"\xef\xbb\xbfabc", "UTF-8"),
+            (b"\xff\xfea\0", "UTF-16LE"),
+            (b"a\0b\0c\0", "UTF-16LE"),
+            (b"\0a\0b\0c", "UTF-16BE"),
+            (b"\xdc\xbd\xc4\xfe\xc4\xc8", "GBK"),
+            (b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd", "SHIFT_JIS"),
+            (b"\xbe\xc8\xb3\xe7\xc7\xcf\xbc\xbc\xbf\xe4", "EUC-KR"),
+            (b"caf\xe9", "WINDOWS-1252"),
+            (b"\x1b$BF|K\\8l\x1b(B", "ISO-2022-JP"),
+            (b"\x1b$)C\x0e>H3gGO<<?d\x0f", "ISO-2022-KR"),
+            (b"+ZeVnLIqe-", "UTF-7"),
+            (b"C++ and a+b-c", "US-ASCII"),
+        ];
+        for (input, expected) in testcases {
+            let ranked = detect(input, None);
+            assert_eq!(ranked[0].0, expected, "{input:?} {ranked:?}");
+        }
+    }
+
+    #[test]
+    fn test_detect_candidates() {
+        let candidates = ["utf-8".to_string(), "iso-8859-1".to_string()];
+        let ranked = detect(b"caf\xe9", Some(&candidates));
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0, "iso-8859-1");
+        assert!(detect(b"\xff", Some(&candidates[..1])).is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+//! A command line text encoding converter, compatible with the options of GNU
+//! `iconv`.
+
+use std::fmt::Display;
+use std::process::ExitCode;
+
+use iconv_native::ConvertError;
+
+mod check;
+mod convert;
+mod detect;
+mod recode;
+
+const NAME: &str = "iconv-native";
+
+/// Reasons for a non-zero exit status. Each has a distinct exit code so that
+/// scripts can tell them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Failure {
+    /// The input contains invalid data, or a character not representable in
+    /// the target encoding.
+    InvalidInput = 1,
+    /// The command line arguments are invalid.
+    Usage = 2,
+    /// An encoding or the conversion pair is not supported.
+    UnknownEncoding = 3,
+    /// Reading or writing a file failed.
+    Io = 4,
+}
+
+impl From<ConvertError> for Failure {
+    fn from(err: ConvertError) -> Self {
+        match err {
+            ConvertError::UnknownConversion => Failure::UnknownEncoding,
+            ConvertError::InvalidInput => Failure::InvalidInput,
+        }
+    }
+}
+
+impl From<Failure> for ExitCode {
+    fn from(failure: Failure) -> Self {
+        ExitCode::from(failure as u8)
+    }
+}
+
+fn report(message: impl Display) {
+    eprintln!("{NAME}: {message}");
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args_os().skip(1).peekable();
+    let result = match args.peek().and_then(|arg| arg.to_str()) {
+        Some(subcommand @ ("recode" | "check" | "detect")) => {
+            let run = match subcommand {
+                "recode" => recode::run,
+                "check" => check::run,
+                _ => detect::run,
+            };
+            args.next();
+            run(lexopt::Parser::from_args(args))
+        }
+        _ => convert::run(lexopt::Parser::from_args(args)),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => failure.into(),
+    }
+}
@@ -0,0 +1,264 @@
+//! The `recode` subcommand, converting files matching glob patterns in place.
+
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::fs::{self, File, FileTimes, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use iconv_native::{convert, ConvertError};
+
+use crate::{report, Failure, NAME};
+
+const USAGE: &str = "\
+Usage: iconv-native recode -f NAME [OPTION...] PATTERN...
+Convert files matching the glob patterns in place. Use ** to match any number
+of directories, e.g. 'src/**/*.c'.
+
+  -f, --from-code=NAME   encoding of the files
+  -t, --to-code=NAME     encoding to convert the files to (default: UTF-8)
+  -n, --dry-run          report what would be converted without writing
+  -q, --quiet            only print the summary
+  -?, --help             print this help
+
+Each file is converted strictly and replaced through a temporary file in the
+same directory, keeping its permissions and modification time. Files that
+cannot be converted are skipped and left untouched. Symbolic links are not
+followed, so that they are not replaced by regular files.
+
+Exit status:
+  0  success
+  1  some files were skipped because of invalid input
+  2  invalid arguments
+  3  unknown encoding or conversion
+  4  I/O error on some files
+";
+
+#[derive(Debug)]
+struct Args {
+    from: String,
+    to: String,
+    dry_run: bool,
+    quiet: bool,
+    patterns: Vec<String>,
+}
+
+fn parse_args(mut parser: lexopt::Parser) -> Result<Option<Args>, lexopt::Error> {
+    use lexopt::prelude::*;
+
+    let mut from = None;
+    let mut to = None;
+    let mut dry_run = false;
+    let mut quiet = false;
+    let mut patterns = vec![];
+
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Short('f') | Long("from-code") => from = Some(parser.value()?.string()?),
+            Short('t') | Long("to-code") => to = Some(parser.value()?.string()?),
+            Short('n') | Long("dry-run") => dry_run = true,
+            Short('q') | Long("quiet") => quiet = true,
+            Short('?') | Short('h') | Long("help") | Long("usage") => {
+                print!("{USAGE}");
+                return Ok(None);
+            }
+            Value(pattern) => patterns.push(pattern.string()?),
+            _ => return Err(arg.unexpected()),
+        }
+    }
+
+    let Some(from) = from else {
+        return Err("missing source encoding, specify it with -f".into());
+    };
+    if patterns.is_empty() {
+        return Err("missing file patterns".into());
+    }
+    Ok(Some(Args {
+        from,
+        to: to.unwrap_or_else(|| "UTF-8".into()),
+        dry_run,
+        quiet,
+        patterns,
+    }))
+}
+
+/// Number of files in each outcome, printed as the summary.
+#[derive(Debug, Default)]
+struct Summary {
+    converted: usize,
+    unchanged: usize,
+    skipped: usize,
+    failed: usize,
+}
+
+impl Summary {
+    fn failure(&self) -> Option<Failure> {
+        if self.failed > 0 {
+            Some(Failure::Io)
+        } else if self.skipped > 0 {
+            Some(Failure::InvalidInput)
+        } else {
+            None
+        }
+    }
+}
+
+/// Expands the glob patterns into a sorted list of regular files, without
+/// duplicates. Symbolic links are left out, since replacing the file would
+/// replace the link itself.
+fn expand_patterns(patterns: &[String], summary: &mut Summary) -> Result<Vec<PathBuf>, Failure> {
+    let mut paths = BTreeSet::new();
+    for pattern in patterns {
+        let entries = glob::glob(pattern).map_err(|err| {
+            report(format_args!("invalid pattern `{pattern}': {err}"));
+            Failure::Usage
+        })?;
+        let mut matched = false;
+        for entry in entries {
+            match entry {
+                Ok(path) if fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_file()) => {
+                    matched = true;
+                    paths.insert(path);
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    report(err);
+                    summary.failed += 1;
+                }
+            }
+        }
+        if !matched {
+            report(format_args!("no files match `{pattern}'"));
+        }
+    }
+    Ok(paths.into_iter().collect())
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{NAME}-{}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+/// Replaces the content of `path` with `content` through a temporary file and
+/// an atomic rename, keeping the permissions and timestamps of the original.
+fn replace_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    let metadata = fs::metadata(path)?;
+    let temp_path = temp_path(path);
+    let write_temp = |file: &mut File| {
+        file.write_all(content)?;
+        file.set_permissions(metadata.permissions())?;
+        let mut times = FileTimes::new().set_modified(metadata.modified()?);
+        if let Ok(accessed) = metadata.accessed() {
+            times = times.set_accessed(accessed);
+        }
+        file.set_times(times)?;
+        file.sync_all()
+    };
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)?;
+    let result = write_temp(&mut file).and_then(|()| {
+        drop(file);
+        fs::rename(&temp_path, path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+pub(crate) fn run(parser: lexopt::Parser) -> Result<(), Failure> {
+    let args = match parse_args(parser) {
+        Ok(Some(args)) => args,
+        Ok(None) => return Ok(()),
+        Err(err) => {
+            report(err);
+            eprintln!("Try '{NAME} recode --help' for more information.");
+            return Err(Failure::Usage);
+        }
+    };
+
+    let mut summary = Summary::default();
+    let paths = expand_patterns(&args.patterns, &mut summary)?;
+    for path in &paths {
+        let name = path.display();
+        let buf = match fs::read(path) {
+            Ok(buf) => buf,
+            Err(err) => {
+                report(format_args!("cannot read `{name}': {err}"));
+                summary.failed += 1;
+                continue;
+            }
+        };
+        let converted = match convert(&buf, &args.from, &args.to) {
+            Ok(converted) => converted,
+            Err(ConvertError::UnknownConversion) => {
+                report(format_args!(
+                    "conversion from `{}' to `{}' is not supported",
+                    args.from, args.to
+                ));
+                return Err(Failure::UnknownEncoding);
+            }
+            Err(ConvertError::InvalidInput) => {
+                if !args.quiet {
+                    println!("skipped: {name}: invalid input");
+                }
+                summary.skipped += 1;
+                continue;
+            }
+        };
+        if converted == buf {
+            summary.unchanged += 1;
+            continue;
+        }
+        if !args.dry_run {
+            if let Err(err) = replace_file(path, &converted) {
+                report(format_args!("cannot write `{name}': {err}"));
+                summary.failed += 1;
+                continue;
+            }
+        }
+        if !args.quiet {
+            let action = if args.dry_run {
+                "would convert"
+            } else {
+                "converted"
+            };
+            println!("{action}: {name}");
+        }
+        summary.converted += 1;
+    }
+
+    let Summary {
+        converted,
+        unchanged,
+        skipped,
+        failed,
+    } = summary;
+    let converted_label = if args.dry_run {
+        "to convert"
+    } else {
+        "converted"
+    };
+    println!(
+        "{converted} {converted_label}, {unchanged} unchanged, {skipped} skipped, {failed} failed"
+    );
+    summary.failure().map_or(Ok(()), Err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_path() {
+        let path = temp_path(Path::new("dir/file.txt"));
+        assert_eq!(path.parent(), Some(Path::new("dir")));
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with(".file.txt."), "{name}");
+        assert!(name.ends_with(".tmp"), "{name}");
+    }
+}
@@ -33,6 +33,26 @@ pub(crate) const UTF16_BE_BOM: [u8; 2] = [0xFE, 0xFF];
 pub(crate) const UTF32_LE_BOM: [u8; 4] = [0xFF, 0xFE, 0, 0];
 pub(crate) const UTF32_BE_BOM: [u8; 4] = [0, 0, 0xFE, 0xFF];
 
+/// Guesses the byte order of BOM-less UTF-16 or UTF-32 text from the position of zero bytes,
+/// which are common in the most significant byte of a code unit but rare in the least significant
+/// one. Returns [`ByteOrderMark::NotPresent`] if the input gives no hint.
+pub(crate) fn guess_byte_order(input: &[u8], unit_len: usize) -> ByteOrderMark {
+    let (mut le, mut be) = (0usize, 0usize);
+    for unit in input.chunks_exact(unit_len) {
+        match (unit[0], unit[unit_len - 1]) {
+            (0, 0) => {}
+            (_, 0) => le += 1,
+            (0, _) => be += 1,
+            _ => {}
+        }
+    }
+    match le.cmp(&be) {
+        core::cmp::Ordering::Greater => ByteOrderMark::Le,
+        core::cmp::Ordering::Less => ByteOrderMark::Be,
+        core::cmp::Ordering::Equal => ByteOrderMark::NotPresent,
+    }
+}
+
 impl ByteOrderMarkExt for [u8] {
     fn get_utf8_bom(&self) -> ByteOrderMark {
         if self.get(0..3) == Some(&UTF8_BOM) {
@@ -62,3 +82,23 @@ impl ByteOrderMarkExt for [u8] {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guess_byte_order() {
+        let testcases = [
+            (&b"h\0e\0l\0l\0o\0"[..], 2, ByteOrderMark::Le),
+            (b"\0h\0e\0l\0l\0o", 2, ByteOrderMark::Be),
+            (b"\x99\x82\x81\x5b", 2, ByteOrderMark::NotPresent),
+            (b"\x99\x82\0\0h\0\0\0", 4, ByteOrderMark::Le),
+            (b"\0\0\x82\x99\0\0\0h", 4, ByteOrderMark::Be),
+            (b"", 4, ByteOrderMark::NotPresent),
+        ];
+        for (idx, (input, unit_len, expected)) in testcases.into_iter().enumerate() {
+            assert_eq!(guess_byte_order(input, unit_len), expected, "{idx}");
+        }
+    }
+}
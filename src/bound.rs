@@ -0,0 +1,229 @@
+use core::str::FromStr;
+
+use crate::codec::{ebcdic::Ebcdic, gsm0338};
+use crate::encoding::{match_encoding_parts, match_encoding_parts_exact, trim_encoding_prefix};
+use crate::utf::{UtfEncoding, UtfType};
+
+/// Number of bytes per character, indexed by the length of the character in UTF-8 minus one, i.e.
+/// ASCII, `U+0080..=U+07FF`, `U+0800..=U+FFFF` and supplementary characters.
+///
+/// For a source encoding, it is the minimum number of input bytes that decode to a character of
+/// the class, or 0 if the encoding never produces such characters. For a target encoding, it is
+/// the maximum number of bytes written for a character of the class, or 0 if such characters are
+/// not representable.
+type Widths = [usize; 4];
+
+const WIDTHS_UTF8: Widths = [1, 2, 3, 4];
+const WIDTHS_UTF16: Widths = [2, 2, 2, 4];
+const WIDTHS_UCS2: Widths = [2, 2, 2, 0];
+const WIDTHS_UTF32: Widths = [4, 4, 4, 4];
+const WIDTHS_CESU8: Widths = [1, 2, 3, 6];
+// NUL takes 2 bytes in Modified UTF-8.
+const WIDTHS_MUTF8_TO: Widths = [2, 2, 3, 6];
+// The extension table contains ASCII characters and `€`, which take an escape septet.
+const WIDTHS_GSM0338_FROM: Widths = [1, 1, 2, 0];
+const WIDTHS_GSM0338_TO: Widths = [2, 1, 2, 0];
+// The WHATWG decoder maps the single byte `0x80` to `€`.
+const WIDTHS_GB18030_FROM: Widths = [1, 2, 1, 4];
+const WIDTHS_GB18030_TO: Widths = [1, 4, 4, 4];
+const WIDTHS_SINGLE_BYTE: Widths = [1, 1, 1, 0];
+// glibc decomposes precomposed characters into a base letter and combining marks when encoding to
+// CP1255 and CP1258, e.g. `U+FB2C` takes 3 bytes in CP1255.
+const WIDTHS_DECOMPOSING_SINGLE_BYTE_TO: Widths = [1, 2, 3, 0];
+// Some double-byte encodings contain single-byte non-ASCII characters (e.g. halfwidth katakana in
+// Shift_JIS), or map a double-byte sequence to a supplementary character (e.g. Big5-HKSCS).
+const WIDTHS_DOUBLE_BYTE_FROM: Widths = [1, 1, 1, 2];
+const WIDTHS_DOUBLE_BYTE_TO: Widths = [1, 2, 2, 0];
+const WIDTHS_EUC_JP_TO: Widths = [1, 3, 3, 0];
+
+/// A conservative upper bound of output length for a conversion pair, expressed as
+/// `input_len * numerator / denominator + overhead`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OutputBound {
+    numerator: usize,
+    denominator: usize,
+    overhead: usize,
+}
+
+impl OutputBound {
+    pub(crate) fn new(from_encoding: &str, to_encoding: &str) -> Option<Self> {
+        let (from_widths, _, _) = encoding_widths(from_encoding)?;
+        let (_, to_widths, overhead) = encoding_widths(to_encoding)?;
+        let (numerator, denominator) = from_widths
+            .into_iter()
+            .zip(to_widths)
+            .filter(|&(from, _)| from != 0)
+            .map(|(from, to)| (to, from))
+            .max_by(|(lnum, lden), (rnum, rden)| (lnum * rden).cmp(&(rnum * lden)))?;
+        Some(Self {
+            numerator,
+            denominator,
+            overhead,
+        })
+    }
+
+    pub(crate) fn max_output_len(&self, input_len: usize) -> Option<usize> {
+        input_len
+            .checked_mul(self.numerator)?
+            .div_ceil(self.denominator)
+            .checked_add(self.overhead)
+    }
+}
+
+/// Returns the widths as a source encoding, as a target encoding and the maximum length of a BOM
+/// written to the output.
+fn encoding_widths(encoding: &str) -> Option<(Widths, Widths, usize)> {
+    if let Ok(utf) = UtfEncoding::from_str(encoding) {
+        let is_ambiguous = utf.is_ambiguous();
+        return Some(match utf.r#type() {
+            UtfType::Utf8 => (WIDTHS_UTF8, WIDTHS_UTF8, 0),
+            UtfType::Utf16 => (WIDTHS_UTF16, WIDTHS_UTF16, if is_ambiguous { 2 } else { 0 }),
+            UtfType::Ucs2 => (WIDTHS_UCS2, WIDTHS_UCS2, if is_ambiguous { 2 } else { 0 }),
+            UtfType::Utf32 => (WIDTHS_UTF32, WIDTHS_UTF32, if is_ambiguous { 4 } else { 0 }),
+            UtfType::Cesu8 => (WIDTHS_CESU8, WIDTHS_CESU8, 0),
+            UtfType::Mutf8 => (WIDTHS_CESU8, WIDTHS_MUTF8_TO, 0),
+        });
+    }
+    // Unpaired surrogates take 3 bytes in WTF-8 and 2 bytes in UTF-16, just like other characters
+    // in the BMP.
+    if match_encoding_parts_exact(encoding, &["wtf", "8"]) {
+        return Some((WIDTHS_UTF8, WIDTHS_UTF8, 0));
+    }
+    // Packed GSM 03.38 takes 7/8 bytes per septet, which cannot be expressed with `Widths`.
+    if gsm0338::parse(encoding) == Some(false) {
+        return Some((WIDTHS_GSM0338_FROM, WIDTHS_GSM0338_TO, 0));
+    }
+    if match_encoding_parts_exact(encoding, &["gb", "18030"]) {
+        return Some((WIDTHS_GB18030_FROM, WIDTHS_GB18030_TO, 0));
+    }
+    if is_decomposing_single_byte(encoding) {
+        return Some((WIDTHS_SINGLE_BYTE, WIDTHS_DECOMPOSING_SINGLE_BYTE_TO, 0));
+    }
+    if is_single_byte(encoding) {
+        return Some((WIDTHS_SINGLE_BYTE, WIDTHS_SINGLE_BYTE, 0));
+    }
+    if match_encoding_parts_exact(encoding, &["euc", "jp"]) {
+        return Some((WIDTHS_DOUBLE_BYTE_FROM, WIDTHS_EUC_JP_TO, 0));
+    }
+    if is_double_byte(encoding) {
+        return Some((WIDTHS_DOUBLE_BYTE_FROM, WIDTHS_DOUBLE_BYTE_TO, 0));
+    }
+    None
+}
+
+fn is_single_byte(encoding: &str) -> bool {
+    const SINGLE_BYTE_PARTS: [&[&str]; 5] = [
+        &["us", "ascii"],
+        &["koi8", "r"],
+        &["koi8", "u"],
+        &["macintosh"],
+        &["latin", "1"],
+    ];
+    if SINGLE_BYTE_PARTS
+        .iter()
+        .any(|parts| match_encoding_parts_exact(encoding, parts))
+        || Ebcdic::from_encoding(encoding).is_some()
+    {
+        return true;
+    }
+    const SINGLE_BYTE_CODEPAGES: [&str; 23] = [
+        "437", "850", "852", "855", "857", "860", "861", "862", "863", "864", "865", "866", "869",
+        "874", "1250", "1251", "1252", "1253", "1254", "1255", "1256", "1257", "1258",
+    ];
+    const SINGLE_BYTE_ISO_8859_PARTS: [&str; 15] = [
+        "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "13", "14", "15", "16",
+    ];
+    if let Some(code) = ["windows", "cp", "ibm"]
+        .into_iter()
+        .find_map(|prefix| trim_encoding_prefix(encoding, prefix))
+    {
+        return SINGLE_BYTE_CODEPAGES.contains(&code);
+    }
+    if let Some(part) = match_encoding_parts(encoding, &["iso", "8859"]) {
+        return SINGLE_BYTE_ISO_8859_PARTS.contains(&part);
+    }
+    false
+}
+
+fn is_decomposing_single_byte(encoding: &str) -> bool {
+    ["windows", "cp"]
+        .into_iter()
+        .find_map(|prefix| trim_encoding_prefix(encoding, prefix))
+        .is_some_and(|code| matches!(code, "1255" | "1258"))
+}
+
+fn is_double_byte(encoding: &str) -> bool {
+    const DOUBLE_BYTE_PARTS: [&[&str]; 13] = [
+        &["gbk"],
+        &["gb", "2312"],
+        &["euc", "cn"],
+        &["euc", "kr"],
+        &["big", "5"],
+        &["shift", "jis"],
+        &["sjis"],
+        &["windows", "31j"],
+        &["cp", "932"],
+        &["cp", "936"],
+        &["cp", "949"],
+        &["cp", "950"],
+        &["uhc"],
+    ];
+    DOUBLE_BYTE_PARTS
+        .iter()
+        .any(|parts| match_encoding_parts_exact(encoding, parts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_bound() {
+        let testcases = [
+            ("utf-8", "utf-16le", 10, Some(20)),
+            ("utf-8", "utf-16", 10, Some(22)),
+            ("utf-8", "utf-32", 10, Some(44)),
+            ("utf-8", "utf-8", 10, Some(10)),
+            ("utf-16le", "utf-8", 10, Some(15)),
+            ("utf-32be", "utf-8", 10, Some(10)),
+            ("utf-32be", "utf-16le", 12, Some(12)),
+            ("gb18030", "utf-8", 10, Some(30)),
+            ("gb18030", "utf-16le", 10, Some(20)),
+            ("utf-8", "gb18030", 10, Some(20)),
+            ("shift_jis", "utf-8", 10, Some(30)),
+            ("iso-8859-1", "utf-8", 10, Some(30)),
+            ("utf-8", "windows-1252", 10, Some(10)),
+            ("utf-16le", "windows-1255", 10, Some(15)),
+            ("utf-8", "cp1258", 10, Some(10)),
+            ("windows-1258", "utf-16le", 10, Some(20)),
+            ("utf-16le", "euc-kr", 10, Some(10)),
+            ("utf-8", "euc-jp", 10, Some(15)),
+            ("utf-16le", "wtf-8", 10, Some(15)),
+            ("gsm0338", "utf-8", 10, Some(20)),
+            ("ibm1047", "utf-8", 10, Some(30)),
+            ("utf-8", "gsm-03.38", 10, Some(20)),
+            ("gsm0338-packed", "utf-8", 10, None),
+            ("utf-8", "cesu-8", 10, Some(15)),
+            ("utf-8", "mutf-8", 10, Some(20)),
+            ("cesu-8", "utf-32le", 10, Some(40)),
+            ("utf-8", "ucs-2", 10, Some(22)),
+            ("ucs-2be", "utf-8", 10, Some(15)),
+            ("utf-8", "iso-2022-jp", 10, None),
+            ("invalid_encoding", "utf-8", 10, None),
+        ];
+        for (from_encoding, to_encoding, input_len, expected) in testcases {
+            assert_eq!(
+                OutputBound::new(from_encoding, to_encoding)
+                    .and_then(|bound| bound.max_output_len(input_len)),
+                expected,
+                "{from_encoding} => {to_encoding}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_output_bound_overflow() {
+        let bound = OutputBound::new("utf-8", "utf-32").unwrap();
+        assert_eq!(bound.max_output_len(usize::MAX), None);
+    }
+}
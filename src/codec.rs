@@ -0,0 +1,236 @@
+//! Encodings implemented in Rust, available regardless of the underlying implementation.
+//!
+//! Functions in this module have the same signatures as those in [`crate::sys`]. Conversions that
+//! involve a built-in encoding are handled here, and everything else is passed through to the
+//! underlying implementation.
+
+use core::str::FromStr;
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+use crate::encoding::match_encoding_parts_exact;
+use crate::options::DefaultByteOrder;
+use crate::utf::{decode_cesu8, decode_ucs2, encode_cesu8, encode_ucs2, UtfEncoding, UtfType};
+use crate::{sys, ConvertError, ConvertLossyError};
+
+pub(crate) mod ebcdic;
+pub(crate) mod gsm0338;
+pub(crate) mod utf7;
+pub(crate) mod wtf8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Builtin {
+    Wtf8,
+    Cesu8,
+    Mutf8,
+    Ucs2(UtfEncoding),
+    Gsm0338 { packed: bool },
+    Utf7(utf7::Variant),
+    Ebcdic(ebcdic::Ebcdic),
+}
+
+impl Builtin {
+    fn from_encoding(encoding: &str) -> Option<Self> {
+        if match_encoding_parts_exact(encoding, &["wtf", "8"]) {
+            return Some(Self::Wtf8);
+        }
+        if let Some(variant) = utf7::Variant::from_encoding(encoding) {
+            return Some(Self::Utf7(variant));
+        }
+        if let Some(ebcdic) = ebcdic::Ebcdic::from_encoding(encoding) {
+            return Some(Self::Ebcdic(ebcdic));
+        }
+        if let Some(packed) = gsm0338::parse(encoding) {
+            return Some(Self::Gsm0338 { packed });
+        }
+        let utf = UtfEncoding::from_str(encoding).ok()?;
+        match utf.r#type() {
+            UtfType::Cesu8 => Some(Self::Cesu8),
+            UtfType::Mutf8 => Some(Self::Mutf8),
+            UtfType::Ucs2 => Some(Self::Ucs2(utf)),
+            _ => None,
+        }
+    }
+
+    fn decode(self, input: &[u8], lossy: bool) -> Result<String, ConvertError> {
+        match self {
+            Self::Wtf8 => wtf8::decode(input, lossy),
+            Self::Cesu8 => decode_cesu8(input, false, lossy).map(Cow::into_owned),
+            Self::Mutf8 => decode_cesu8(input, true, lossy).map(Cow::into_owned),
+            Self::Ucs2(utf) => {
+                let mut input = input;
+                let byte_order = utf.consume_input_bom(&mut input, DefaultByteOrder::default());
+                decode_ucs2(input, byte_order.is_le(true), lossy)
+            }
+            Self::Gsm0338 { packed } => gsm0338::decode(input, packed, lossy),
+            Self::Utf7(variant) => utf7::decode(input, variant, lossy),
+            Self::Ebcdic(ebcdic) => Ok(ebcdic.decode(input)),
+        }
+    }
+
+    fn encode(self, input: &str, lossy: bool) -> Result<Vec<u8>, ConvertError> {
+        match self {
+            // Any UTF-8 is valid WTF-8.
+            Self::Wtf8 => Ok(input.as_bytes().to_vec()),
+            Self::Cesu8 => Ok(encode_cesu8(input, false)),
+            Self::Mutf8 => Ok(encode_cesu8(input, true)),
+            Self::Ucs2(utf) => {
+                let is_le = utf.byte_order().is_le(true);
+                encode_ucs2(input, is_le, utf.is_ambiguous(), lossy)
+            }
+            Self::Gsm0338 { packed } => gsm0338::encode(input, packed, lossy),
+            Self::Utf7(variant) => Ok(utf7::encode(input, variant)),
+            Self::Ebcdic(ebcdic) => ebcdic.encode(input, lossy),
+        }
+    }
+}
+
+/// Converts `input` if either encoding is built-in. Returns `None` if neither is.
+fn convert_builtin(
+    input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+    lossy: bool,
+) -> Option<Result<Vec<u8>, ConvertError>> {
+    let from_builtin = Builtin::from_encoding(from_encoding);
+    let to_builtin = Builtin::from_encoding(to_encoding);
+    if from_builtin.is_none() && to_builtin.is_none() {
+        return None;
+    }
+    if let Some(result) = wtf8::convert_utf16(input, from_encoding, to_encoding, lossy) {
+        return Some(result);
+    }
+    Some((|| {
+        let decoded = match from_builtin {
+            Some(builtin) => Cow::Owned(builtin.decode(input, lossy)?),
+            None => decode_external(input, from_encoding, lossy)?,
+        };
+        match to_builtin {
+            Some(builtin) => builtin.encode(&decoded, lossy),
+            None if lossy => Ok(encode_lossy(&decoded, to_encoding)?),
+            None => encode(&decoded, to_encoding),
+        }
+    })())
+}
+
+fn decode_external<'i>(
+    input: &'i [u8],
+    encoding: &str,
+    lossy: bool,
+) -> Result<Cow<'i, str>, ConvertError> {
+    if let Some(str) = crate::try_borrow_str(input, encoding) {
+        return Ok(Cow::Borrowed(str));
+    }
+    Ok(Cow::Owned(if lossy {
+        sys::decode_lossy(input, encoding)?
+    } else {
+        sys::decode(input, encoding)?
+    }))
+}
+
+pub(crate) fn into_lossy(err: ConvertError) -> ConvertLossyError {
+    match err {
+        ConvertError::UnknownConversion => ConvertLossyError::UnknownConversion,
+        ConvertError::InvalidInput => unreachable!("lossy conversion reported invalid input"),
+    }
+}
+
+pub(crate) fn convert(
+    input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+) -> Result<Vec<u8>, ConvertError> {
+    convert_builtin(input, from_encoding, to_encoding, false)
+        .unwrap_or_else(|| sys::convert(input, from_encoding, to_encoding))
+}
+
+pub(crate) fn convert_into(
+    input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+    output: &mut [u8],
+) -> Result<(usize, usize), ConvertError> {
+    match convert_builtin(input, from_encoding, to_encoding, false) {
+        Some(converted) => Ok(sys::copy_converted(input, &converted?, output)),
+        None => sys::convert_into(input, from_encoding, to_encoding, output),
+    }
+}
+
+pub(crate) fn convert_append(
+    input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+    output: &mut Vec<u8>,
+) -> Result<(), ConvertError> {
+    match convert_builtin(input, from_encoding, to_encoding, false) {
+        Some(converted) => {
+            output.extend_from_slice(&converted?);
+            Ok(())
+        }
+        None => sys::convert_append(input, from_encoding, to_encoding, output),
+    }
+}
+
+pub(crate) fn convert_lossy(
+    input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+) -> Result<Vec<u8>, ConvertLossyError> {
+    match convert_builtin(input, from_encoding, to_encoding, true) {
+        Some(converted) => converted.map_err(into_lossy),
+        None => sys::convert_lossy(input, from_encoding, to_encoding),
+    }
+}
+
+pub(crate) fn decode(input: &[u8], encoding: &str) -> Result<String, ConvertError> {
+    match Builtin::from_encoding(encoding) {
+        Some(builtin) => builtin.decode(input, false),
+        None => sys::decode(input, encoding),
+    }
+}
+
+/// Decodes the longest prefix of `input` that is valid on its own, returning it with its length,
+/// if the underlying implementation reports where decoding stopped. Returns `None` otherwise, and
+/// for built-in encodings.
+pub(crate) fn decode_prefix(input: &[u8], encoding: &str) -> Option<(String, usize)> {
+    match Builtin::from_encoding(encoding) {
+        Some(_) => None,
+        None => sys::decode_prefix(input, encoding),
+    }
+}
+
+/// Opens a conversion decoding one sequence at a time, if the underlying implementation can keep
+/// one open. Returns `None` otherwise, and for built-in encodings.
+pub(crate) fn sequence_decoder(encoding: &str) -> Option<sys::SequenceDecoder> {
+    match Builtin::from_encoding(encoding) {
+        Some(_) => None,
+        None => sys::SequenceDecoder::new(encoding),
+    }
+}
+
+pub(crate) fn decode_lossy(input: &[u8], encoding: &str) -> Result<String, ConvertLossyError> {
+    match Builtin::from_encoding(encoding) {
+        Some(builtin) => builtin.decode(input, true).map_err(into_lossy),
+        None => sys::decode_lossy(input, encoding),
+    }
+}
+
+pub(crate) fn encode(input: &str, encoding: &str) -> Result<Vec<u8>, ConvertError> {
+    if crate::is_utf8(encoding) {
+        return Ok(input.as_bytes().to_vec());
+    }
+    match Builtin::from_encoding(encoding) {
+        Some(builtin) => builtin.encode(input, false),
+        None => sys::encode(input, encoding),
+    }
+}
+
+pub(crate) fn encode_lossy(input: &str, encoding: &str) -> Result<Vec<u8>, ConvertLossyError> {
+    if crate::is_utf8(encoding) {
+        return Ok(input.as_bytes().to_vec());
+    }
+    match Builtin::from_encoding(encoding) {
+        Some(builtin) => builtin.encode(input, true).map_err(into_lossy),
+        None => sys::encode_lossy(input, encoding),
+    }
+}
@@ -0,0 +1,436 @@
+//! EBCDIC code pages used by IBM mainframes, with the euro variants (1140-1149) of the national
+//! code pages.
+//!
+//! Every code page maps all 256 bytes, so decoding never fails. NL (`0x15`) and LF (`0x25`) map to
+//! `U+0085` and `U+000A` respectively as defined by IBM, unless the encoding name ends with
+//! `,swaplfnl` (the option of ICU) or its shorthand `,swaps`, where they are swapped.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::encoding::trim_encoding_prefix;
+use crate::ConvertError;
+
+const NL: usize = 0x15;
+const LF: usize = 0x25;
+// SUB, the substitute character of EBCDIC, written for characters that are not representable by
+// the lossy functions.
+const REPLACEMENT: u8 = 0x3F;
+const SWAP_SUFFIXES: [&str; 2] = [",swaplfnl", ",swaps"];
+
+#[rustfmt::skip]
+const CP037: [char; 256] = [
+    '\u{00}', '\u{01}', '\u{02}', '\u{03}', '\u{9C}', '\u{09}', '\u{86}', '\u{7F}', '\u{97}', '\u{8D}', '\u{8E}', '\u{0B}', '\u{0C}', '\u{0D}', '\u{0E}', '\u{0F}',
+    '\u{10}', '\u{11}', '\u{12}', '\u{13}', '\u{9D}', '\u{85}', '\u{08}', '\u{87}', '\u{18}', '\u{19}', '\u{92}', '\u{8F}', '\u{1C}', '\u{1D}', '\u{1E}', '\u{1F}',
+    '\u{80}', '\u{81}', '\u{82}', '\u{83}', '\u{84}', '\u{0A}', '\u{17}', '\u{1B}', '\u{88}', '\u{89}', '\u{8A}', '\u{8B}', '\u{8C}', '\u{05}', '\u{06}', '\u{07}',
+    '\u{90}', '\u{91}', '\u{16}', '\u{93}', '\u{94}', '\u{95}', '\u{96}', '\u{04}', '\u{98}', '\u{99}', '\u{9A}', '\u{9B}', '\u{14}', '\u{15}', '\u{9E}', '\u{1A}',
+    ' ', '\u{A0}', 'â', 'ä', 'à', 'á', 'ã', 'å', 'ç', 'ñ', '¢', '.', '<', '(', '+', '|',
+    '&', 'é', 'ê', 'ë', 'è', 'í', 'î', 'ï', 'ì', 'ß', '!', '$', '*', ')', ';', '¬',
+    '-', '/', 'Â', 'Ä', 'À', 'Á', 'Ã', 'Å', 'Ç', 'Ñ', '¦', ',', '%', '_', '>', '?',
+    'ø', 'É', 'Ê', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', '`', ':', '#', '@', '\'', '=', '"',
+    'Ø', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', '«', '»', 'ð', 'ý', 'þ', '±',
+    '°', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 'ª', 'º', 'æ', '¸', 'Æ', '¤',
+    'µ', '~', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '¡', '¿', 'Ð', 'Ý', 'Þ', '®',
+    '^', '£', '¥', '·', '©', '§', '¶', '¼', '½', '¾', '[', ']', '¯', '¨', '´', '×',
+    '{', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', '\u{AD}', 'ô', 'ö', 'ò', 'ó', 'õ',
+    '}', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', '¹', 'û', 'ü', 'ù', 'ú', 'ÿ',
+    '\\', '÷', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '²', 'Ô', 'Ö', 'Ò', 'Ó', 'Õ',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '³', 'Û', 'Ü', 'Ù', 'Ú', '\u{9F}',
+];
+
+// Differences from code page 037. 871 is only supported as the base of 1149.
+const CP273: &[(u8, char)] = &[
+    (0x43, '{'),
+    (0x4A, 'Ä'),
+    (0x4F, '!'),
+    (0x59, '~'),
+    (0x5A, 'Ü'),
+    (0x5F, '^'),
+    (0x63, '['),
+    (0x6A, 'ö'),
+    (0x7C, '§'),
+    (0xA1, 'ß'),
+    (0xB0, '¢'),
+    (0xB5, '@'),
+    (0xBA, '¬'),
+    (0xBB, '|'),
+    (0xC0, 'ä'),
+    (0xCC, '¦'),
+    (0xD0, 'ü'),
+    (0xDC, '}'),
+    (0xE0, 'Ö'),
+    (0xEC, '\\'),
+    (0xFC, ']'),
+];
+const CP277: &[(u8, char)] = &[
+    (0x47, '}'),
+    (0x4A, '#'),
+    (0x4F, '!'),
+    (0x5A, '¤'),
+    (0x5B, 'Å'),
+    (0x5F, '^'),
+    (0x67, '$'),
+    (0x6A, 'ø'),
+    (0x70, '¦'),
+    (0x7B, 'Æ'),
+    (0x7C, 'Ø'),
+    (0x80, '@'),
+    (0x9C, '{'),
+    (0x9E, '['),
+    (0x9F, ']'),
+    (0xA1, 'ü'),
+    (0xB0, '¢'),
+    (0xBA, '¬'),
+    (0xBB, '|'),
+    (0xC0, 'æ'),
+    (0xD0, 'å'),
+    (0xDC, '~'),
+];
+const CP278: &[(u8, char)] = &[
+    (0x43, '{'),
+    (0x47, '}'),
+    (0x4A, '§'),
+    (0x4F, '!'),
+    (0x51, '`'),
+    (0x5A, '¤'),
+    (0x5B, 'Å'),
+    (0x5F, '^'),
+    (0x63, '#'),
+    (0x67, '$'),
+    (0x6A, 'ö'),
+    (0x71, '\\'),
+    (0x79, 'é'),
+    (0x7B, 'Ä'),
+    (0x7C, 'Ö'),
+    (0x9F, ']'),
+    (0xA1, 'ü'),
+    (0xB0, '¢'),
+    (0xB5, '['),
+    (0xBA, '¬'),
+    (0xBB, '|'),
+    (0xC0, 'ä'),
+    (0xCC, '¦'),
+    (0xD0, 'å'),
+    (0xDC, '~'),
+    (0xE0, 'É'),
+    (0xEC, '@'),
+];
+const CP280: &[(u8, char)] = &[
+    (0x44, '{'),
+    (0x48, '\\'),
+    (0x4A, '°'),
+    (0x4F, '!'),
+    (0x51, ']'),
+    (0x54, '}'),
+    (0x58, '~'),
+    (0x5A, 'é'),
+    (0x5F, '^'),
+    (0x6A, 'ò'),
+    (0x79, 'ù'),
+    (0x7B, '£'),
+    (0x7C, '§'),
+    (0x90, '['),
+    (0xA1, 'ì'),
+    (0xB0, '¢'),
+    (0xB1, '#'),
+    (0xB5, '@'),
+    (0xBA, '¬'),
+    (0xBB, '|'),
+    (0xC0, 'à'),
+    (0xCD, '¦'),
+    (0xD0, 'è'),
+    (0xDD, '`'),
+    (0xE0, 'ç'),
+];
+const CP284: &[(u8, char)] = &[
+    (0x49, '¦'),
+    (0x4A, '['),
+    (0x5A, ']'),
+    (0x69, '#'),
+    (0x6A, 'ñ'),
+    (0x7B, 'Ñ'),
+    (0xA1, '¨'),
+    (0xB0, '¢'),
+    (0xBA, '^'),
+    (0xBB, '!'),
+    (0xBD, '~'),
+];
+const CP285: &[(u8, char)] = &[
+    (0x4A, '$'),
+    (0x5B, '£'),
+    (0xA1, '‾'),
+    (0xB0, '¢'),
+    (0xB1, '['),
+    (0xBA, '^'),
+    (0xBC, '~'),
+];
+const CP297: &[(u8, char)] = &[
+    (0x44, '@'),
+    (0x48, '\\'),
+    (0x4A, '°'),
+    (0x4F, '!'),
+    (0x51, '{'),
+    (0x54, '}'),
+    (0x5A, '§'),
+    (0x5F, '^'),
+    (0x6A, 'ù'),
+    (0x79, 'µ'),
+    (0x7B, '£'),
+    (0x7C, 'à'),
+    (0x90, '['),
+    (0xA0, '`'),
+    (0xA1, '¨'),
+    (0xB0, '¢'),
+    (0xB1, '#'),
+    (0xB5, ']'),
+    (0xBA, '¬'),
+    (0xBB, '|'),
+    (0xBD, '~'),
+    (0xC0, 'é'),
+    (0xD0, 'è'),
+    (0xDD, '¦'),
+    (0xE0, 'ç'),
+];
+const CP500: &[(u8, char)] = &[
+    (0x4A, '['),
+    (0x4F, '!'),
+    (0x5A, ']'),
+    (0x5F, '^'),
+    (0xB0, '¢'),
+    (0xBA, '¬'),
+    (0xBB, '|'),
+];
+const CP1047: &[(u8, char)] = &[
+    (0x5F, '^'),
+    (0xAD, '['),
+    (0xB0, '¬'),
+    (0xBA, 'Ý'),
+    (0xBB, '¨'),
+    (0xBD, ']'),
+];
+const CP871: &[(u8, char)] = &[
+    (0x4A, 'Þ'),
+    (0x4F, '!'),
+    (0x5A, 'Æ'),
+    (0x5F, 'Ö'),
+    (0x79, 'ð'),
+    (0x7C, 'Ð'),
+    (0x8C, '`'),
+    (0x8E, '{'),
+    (0x9C, '}'),
+    (0x9E, ']'),
+    (0xA1, 'ö'),
+    (0xAC, '@'),
+    (0xAE, '['),
+    (0xB0, '¢'),
+    (0xBA, '¬'),
+    (0xBB, '|'),
+    (0xBE, '\\'),
+    (0xC0, 'þ'),
+    (0xCC, '~'),
+    (0xD0, 'æ'),
+    (0xE0, '´'),
+    (0xEC, '^'),
+];
+
+const EURO_9F: &[(u8, char)] = &[(0x9F, '€')];
+const EURO_5A: &[(u8, char)] = &[(0x5A, '€')];
+// Besides the euro sign, 1146 replaces the overline of 285 with the macron.
+const EURO_1146: &[(u8, char)] = &[(0x9F, '€'), (0xA1, '¯')];
+
+#[derive(Debug, PartialEq, Eq)]
+struct CodePage {
+    number: u16,
+    /// Labels with the standard and swapped newline mappings.
+    labels: [&'static str; 2],
+    /// Differences from code page 037, applied in order.
+    changes: &'static [&'static [(u8, char)]],
+}
+
+macro_rules! code_page {
+    ($number:literal, $label:literal, [$($changes:expr),*]) => {
+        CodePage {
+            number: $number,
+            labels: [$label, concat!($label, ",swaps")],
+            changes: &[$($changes),*],
+        }
+    };
+}
+
+static CODE_PAGES: [CodePage; 20] = [
+    code_page!(37, "IBM037", []),
+    code_page!(273, "IBM273", [CP273]),
+    code_page!(277, "IBM277", [CP277]),
+    code_page!(278, "IBM278", [CP278]),
+    code_page!(280, "IBM280", [CP280]),
+    code_page!(284, "IBM284", [CP284]),
+    code_page!(285, "IBM285", [CP285]),
+    code_page!(297, "IBM297", [CP297]),
+    code_page!(500, "IBM500", [CP500]),
+    code_page!(1047, "IBM1047", [CP1047]),
+    code_page!(1140, "IBM01140", [EURO_9F]),
+    code_page!(1141, "IBM01141", [CP273, EURO_9F]),
+    code_page!(1142, "IBM01142", [CP277, EURO_5A]),
+    code_page!(1143, "IBM01143", [CP278, EURO_5A]),
+    code_page!(1144, "IBM01144", [CP280, EURO_9F]),
+    code_page!(1145, "IBM01145", [CP284, EURO_9F]),
+    code_page!(1146, "IBM01146", [CP285, EURO_1146]),
+    code_page!(1147, "IBM01147", [CP297, EURO_9F]),
+    code_page!(1148, "IBM01148", [CP500, EURO_9F]),
+    code_page!(1149, "IBM01149", [CP871, EURO_9F]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Ebcdic {
+    code_page: &'static CodePage,
+    swap_newlines: bool,
+}
+
+impl Ebcdic {
+    /// Parses names like `IBM037`, `CP1047`, `IBM-1140` or `IBM01140`, optionally followed by
+    /// `,swaplfnl` or `,swaps`.
+    pub(crate) fn from_encoding(encoding: &str) -> Option<Self> {
+        let (encoding, swap_newlines) = SWAP_SUFFIXES
+            .into_iter()
+            .find_map(|suffix| {
+                let (name, rest) =
+                    encoding.split_at_checked(encoding.len().checked_sub(suffix.len())?)?;
+                rest.eq_ignore_ascii_case(suffix).then_some(name)
+            })
+            .map_or((encoding, false), |name| (name, true));
+        let number = ["ibm", "cp"]
+            .into_iter()
+            .find_map(|prefix| trim_encoding_prefix(encoding, prefix))?;
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let number: u16 = number.parse().ok()?;
+        let code_page = CODE_PAGES
+            .iter()
+            .find(|code_page| code_page.number == number)?;
+        Some(Self {
+            code_page,
+            swap_newlines,
+        })
+    }
+
+    pub(crate) fn with_swapped_newlines(self, swap_newlines: bool) -> Self {
+        Self {
+            swap_newlines,
+            ..self
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        self.code_page.labels[usize::from(self.swap_newlines)]
+    }
+
+    fn table(self) -> [char; 256] {
+        let mut table = CP037;
+        for &(byte, ch) in self.code_page.changes.iter().copied().flatten() {
+            table[usize::from(byte)] = ch;
+        }
+        if self.swap_newlines {
+            table.swap(NL, LF);
+        }
+        table
+    }
+
+    pub(crate) fn decode(self, input: &[u8]) -> String {
+        let table = self.table();
+        input.iter().map(|&byte| table[usize::from(byte)]).collect()
+    }
+
+    pub(crate) fn encode(self, input: &str, lossy: bool) -> Result<Vec<u8>, ConvertError> {
+        let table = self.table();
+        let mut latin1 = [None; 256];
+        for (byte, &ch) in table.iter().enumerate() {
+            if let Ok(code) = u8::try_from(u32::from(ch)) {
+                latin1[usize::from(code)] = Some(byte as u8);
+            }
+        }
+        let mut output = Vec::with_capacity(input.len());
+        for ch in input.chars() {
+            let byte = match u8::try_from(u32::from(ch)) {
+                Ok(code) => latin1[usize::from(code)],
+                Err(_) => table.iter().position(|&c| c == ch).map(|byte| byte as u8),
+            };
+            match byte {
+                Some(byte) => output.push(byte),
+                None if lossy => output.push(REPLACEMENT),
+                None => return Err(ConvertError::InvalidInput),
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_encoding() {
+        let testcases = [
+            ("IBM037", Some("IBM037")),
+            ("cp37", Some("IBM037")),
+            ("IBM-1047", Some("IBM1047")),
+            ("ibm1047,SWAPS", Some("IBM1047,swaps")),
+            ("IBM037,swaplfnl", Some("IBM037,swaps")),
+            ("cp1140,SwapLfNl", Some("IBM01140,swaps")),
+            ("ibm1047,swap", None),
+            ("IBM01140", Some("IBM01140")),
+            ("cp1149", Some("IBM01149")),
+            ("ibm871", None),
+            ("cp1252", None),
+            ("ibm", None),
+            ("ibm-+37", None),
+        ];
+        for (input, expected) in testcases {
+            let result = Ebcdic::from_encoding(input).map(Ebcdic::label);
+            assert_eq!(result, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_tables_bijective() {
+        for code_page in &CODE_PAGES {
+            let ebcdic = Ebcdic {
+                code_page,
+                swap_newlines: false,
+            };
+            let all_bytes: Vec<u8> = (0..=255).collect();
+            let decoded = ebcdic.decode(&all_bytes);
+            assert_eq!(
+                ebcdic.encode(&decoded, false).unwrap(),
+                all_bytes,
+                "{}",
+                code_page.number
+            );
+        }
+    }
+
+    #[test]
+    fn test_newlines() {
+        let ebcdic = Ebcdic::from_encoding("ibm1047").unwrap();
+        assert_eq!(ebcdic.decode(b"\xc1\x15\xc2\x25"), "A\u{85}B\n");
+        let ebcdic = ebcdic.with_swapped_newlines(true);
+        assert_eq!(ebcdic.decode(b"\xc1\x15\xc2\x25"), "A\nB\u{85}");
+        assert_eq!(ebcdic.encode("A\n", false).unwrap(), b"\xc1\x15");
+    }
+
+    #[test]
+    fn test_euro() {
+        let testcases = [
+            ("ibm037", None),
+            ("ibm1140", Some(0x9F)),
+            ("ibm1142", Some(0x5A)),
+        ];
+        for (encoding, expected) in testcases {
+            let ebcdic = Ebcdic::from_encoding(encoding).unwrap();
+            let result = ebcdic.encode("€", false).ok();
+            assert_eq!(result, expected.map(|byte| vec![byte]), "{encoding}");
+        }
+        let ebcdic = Ebcdic::from_encoding("ibm037").unwrap();
+        assert_eq!(ebcdic.encode("a€", true).unwrap(), b"\x81\x3f");
+    }
+}
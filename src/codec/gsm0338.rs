@@ -0,0 +1,263 @@
+//! The GSM 03.38 (3GPP TS 23.038) 7-bit default alphabet used by SMS, including the extension
+//! table reached through the escape septet.
+//!
+//! Unpacked, each septet occupies one byte. Packed, septets are stored in the low bits first, so
+//! that 8 septets fit in 7 bytes.
+
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::encoding::{match_encoding_parts, trim_encoding_prefix};
+use crate::ConvertError;
+
+const ESCAPE: u8 = 0x1B;
+const CR: u8 = 0x0D;
+// Written for characters that are not representable by the lossy functions.
+const REPLACEMENT: u8 = b'?';
+
+#[rustfmt::skip]
+const DEFAULT_ALPHABET: [char; 128] = [
+    '@', '£', '$', '¥', 'è', 'é', 'ù', 'ì', 'ò', 'Ç', '\n', 'Ø', 'ø', '\r', 'Å', 'å',
+    'Δ', '_', 'Φ', 'Γ', 'Λ', 'Ω', 'Π', 'Ψ', 'Σ', 'Θ', 'Ξ', '\u{1B}', 'Æ', 'æ', 'ß', 'É',
+    ' ', '!', '"', '#', '¤', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?',
+    '¡', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O',
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'Ä', 'Ö', 'Ñ', 'Ü', '§',
+    '¿', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o',
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'ä', 'ö', 'ñ', 'ü', 'à',
+];
+
+const EXTENSION_TABLE: [(u8, char); 10] = [
+    (0x0A, '\u{0C}'),
+    (0x14, '^'),
+    (0x28, '{'),
+    (0x29, '}'),
+    (0x2F, '\\'),
+    (0x3C, '['),
+    (0x3D, '~'),
+    (0x3E, ']'),
+    (0x40, '|'),
+    (0x65, '€'),
+];
+
+/// Parses `GSM0338` or `GSM-03.38`, optionally followed by `-PACKED`. Returns whether the encoding
+/// is packed.
+pub(crate) fn parse(encoding: &str) -> Option<bool> {
+    let rest = match_encoding_parts(encoding, &["gsm"])?;
+    let rest = ["0338", "03.38"]
+        .into_iter()
+        .find_map(|number| trim_encoding_prefix(rest, number))?;
+    match rest {
+        "" => Some(false),
+        _ if rest.eq_ignore_ascii_case("packed") => Some(true),
+        _ => None,
+    }
+}
+
+/// Returns the septets representing `ch`, or `None` if it is not representable.
+fn encode_char(ch: char) -> Option<(u8, Option<u8>)> {
+    if ch == '\u{1B}' {
+        return None;
+    }
+    if let Some(septet) = DEFAULT_ALPHABET.iter().position(|&c| c == ch) {
+        return Some((septet as u8, None));
+    }
+    EXTENSION_TABLE
+        .iter()
+        .find(|&&(_, c)| c == ch)
+        .map(|&(septet, _)| (ESCAPE, Some(septet)))
+}
+
+fn decode_extension(septet: u8) -> char {
+    match EXTENSION_TABLE.iter().find(|&&(s, _)| s == septet) {
+        Some(&(_, ch)) => ch,
+        // Reserved for another extension table, to be displayed as a space.
+        None if septet == ESCAPE => ' ',
+        // Unknown extensions are displayed as the character in the default alphabet.
+        None => DEFAULT_ALPHABET[usize::from(septet)],
+    }
+}
+
+/// Returns the number of septets needed to encode `input`, or `None` if any character is not
+/// representable.
+pub(crate) fn count_septets(input: &str) -> Option<usize> {
+    input.chars().try_fold(0, |count, ch| {
+        let (_, extension) = encode_char(ch)?;
+        Some(count + 1 + usize::from(extension.is_some()))
+    })
+}
+
+fn decode_septets(
+    septets: impl IntoIterator<Item = u8>,
+    lossy: bool,
+) -> Result<String, ConvertError> {
+    let mut output = String::new();
+    let mut septets = septets.into_iter();
+    while let Some(septet) = septets.next() {
+        let ch = match septet {
+            0x80.. if lossy => char::REPLACEMENT_CHARACTER,
+            0x80.. => return Err(ConvertError::InvalidInput),
+            ESCAPE => match septets.next() {
+                Some(0x80..) | None if lossy => char::REPLACEMENT_CHARACTER,
+                Some(0x80..) | None => return Err(ConvertError::InvalidInput),
+                Some(septet) => decode_extension(septet),
+            },
+            _ => DEFAULT_ALPHABET[usize::from(septet)],
+        };
+        output.push(ch);
+    }
+    Ok(output)
+}
+
+fn encode_septets(input: &str, lossy: bool) -> Result<Vec<u8>, ConvertError> {
+    let mut output = Vec::with_capacity(input.len());
+    for ch in input.chars() {
+        match encode_char(ch) {
+            Some((septet, extension)) => {
+                output.push(septet);
+                output.extend(extension);
+            }
+            None if lossy => output.push(REPLACEMENT),
+            None => return Err(ConvertError::InvalidInput),
+        }
+    }
+    Ok(output)
+}
+
+/// Unpacks septets without removing the padding, for input in the middle of a message.
+pub(crate) fn unpack_septets(input: &[u8]) -> Vec<u8> {
+    let count = input.len() * 8 / 7;
+    (0..count)
+        .map(|idx| {
+            let bit = idx * 7;
+            let (byte, shift) = (bit / 8, bit % 8);
+            let mut septet = input[byte] >> shift;
+            if shift > 1 {
+                septet |= input[byte + 1] << (8 - shift);
+            }
+            septet & 0x7F
+        })
+        .collect()
+}
+
+pub(crate) fn unpack(input: &[u8]) -> Vec<u8> {
+    let mut septets = unpack_septets(input);
+    // 7 spare bits at the end are filled with CR, which is not part of the message.
+    if input.len() % 7 == 0 && septets.last() == Some(&CR) {
+        septets.pop();
+    }
+    septets
+}
+
+/// Packs septets without adding the padding, for input in the middle of a message.
+pub(crate) fn pack_septets(septets: &[u8]) -> Vec<u8> {
+    let mut output = vec![0u8; (septets.len() * 7).div_ceil(8)];
+    for (idx, &septet) in septets.iter().enumerate() {
+        let bit = idx * 7;
+        let (byte, shift) = (bit / 8, bit % 8);
+        output[byte] |= septet << shift;
+        if shift > 1 {
+            output[byte + 1] |= septet >> (8 - shift);
+        }
+    }
+    output
+}
+
+pub(crate) fn pack(mut septets: Vec<u8>) -> Vec<u8> {
+    match septets.len() % 8 {
+        // Otherwise the padding would be indistinguishable from the CR at the end of the message.
+        0 if septets.last() == Some(&CR) => septets.push(CR),
+        7 => septets.push(CR),
+        _ => {}
+    }
+    pack_septets(&septets)
+}
+
+pub(super) fn decode(input: &[u8], packed: bool, lossy: bool) -> Result<String, ConvertError> {
+    if packed {
+        decode_septets(unpack(input), lossy)
+    } else {
+        decode_septets(input.iter().copied(), lossy)
+    }
+}
+
+pub(super) fn encode(input: &str, packed: bool, lossy: bool) -> Result<Vec<u8>, ConvertError> {
+    let septets = encode_septets(input, lossy)?;
+    Ok(if packed { pack(septets) } else { septets })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let testcases = [
+            ("gsm0338", Some(false)),
+            ("GSM-03.38", Some(false)),
+            ("gsm_0338_packed", Some(true)),
+            ("GSM-03.38-PACKED", Some(true)),
+            ("gsm", None),
+            ("gsm-0338-unpacked", None),
+        ];
+        for (input, expected) in testcases {
+            assert_eq!(parse(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_pack() {
+        let testcases = [
+            ("", &b""[..]),
+            ("hello", b"\xe8\x32\x9b\xfd\x06"),
+            ("1234567", b"\x31\xd9\x8c\x56\xb3\xdd\x1a"),
+            ("12345678", b"\x31\xd9\x8c\x56\xb3\xdd\x70"),
+            ("1234567\r", b"\x31\xd9\x8c\x56\xb3\xdd\x1a\x0d"),
+        ];
+        for (idx, (str, bytes)) in testcases.into_iter().enumerate() {
+            assert_eq!(encode(str, true, false).unwrap(), bytes, "{idx}");
+        }
+        assert_eq!(
+            decode(b"\xe8\x32\x9b\xfd\x06", true, false).unwrap(),
+            "hello"
+        );
+        assert_eq!(
+            decode(b"\x31\xd9\x8c\x56\xb3\xdd\x1a", true, false).unwrap(),
+            "1234567"
+        );
+        assert_eq!(
+            decode(b"\x31\xd9\x8c\x56\xb3\xdd\x70", true, false).unwrap(),
+            "12345678"
+        );
+    }
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(
+            encode("{€}", false, false).unwrap(),
+            b"\x1b\x28\x1b\x65\x1b\x29"
+        );
+        assert_eq!(
+            decode(b"\x1b\x28\x1b\x65\x1b\x29", false, false).unwrap(),
+            "{€}"
+        );
+        assert_eq!(decode(b"\x1b\x41\x1b\x1b", false, false).unwrap(), "A ");
+        assert_eq!(
+            decode(b"a\x1b", false, false),
+            Err(ConvertError::InvalidInput)
+        );
+        assert_eq!(decode(b"a\x1b", false, true).unwrap(), "a\u{fffd}");
+    }
+
+    #[test]
+    fn test_count_septets() {
+        let testcases = [
+            ("", Some(0)),
+            ("Δ@£", Some(3)),
+            ("[1]€", Some(7)),
+            ("中", None),
+        ];
+        for (input, expected) in testcases {
+            assert_eq!(count_septets(input), expected, "{input}");
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! [UTF-7](https://www.rfc-editor.org/rfc/rfc2152) and the modified UTF-7 used by IMAP for
+//! mailbox names ([RFC 3501](https://www.rfc-editor.org/rfc/rfc3501#section-5.1.3)).
+//!
+//! Both encode characters outside a set of ASCII characters as base64 encoded UTF-16, enclosed by
+//! a shift character and `-`. Modified UTF-7 uses `&` as the shift character, `,` instead of `/`
+//! in base64, and always requires the terminating `-`.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::encoding::match_encoding_parts_exact;
+use crate::ConvertError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Variant {
+    Utf7,
+    Imap,
+}
+
+impl Variant {
+    pub(crate) fn from_encoding(encoding: &str) -> Option<Self> {
+        if match_encoding_parts_exact(encoding, &["utf", "7"]) {
+            return Some(Self::Utf7);
+        }
+        if match_encoding_parts_exact(encoding, &["utf", "7", "imap"])
+            || match_encoding_parts_exact(encoding, &["imap", "utf", "7"])
+        {
+            return Some(Self::Imap);
+        }
+        None
+    }
+
+    fn shift(self) -> u8 {
+        match self {
+            Self::Utf7 => b'+',
+            Self::Imap => b'&',
+        }
+    }
+
+    fn base64_alphabet(self) -> &'static [u8; 64] {
+        match self {
+            Self::Utf7 => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+            Self::Imap => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+,",
+        }
+    }
+
+    fn base64_value(self, byte: u8) -> Option<u32> {
+        self.base64_alphabet()
+            .iter()
+            .position(|&b| b == byte)
+            .map(|value| value as u32)
+    }
+
+    /// Whether `byte` is accepted as itself when decoding.
+    fn is_direct_input(self, byte: u8) -> bool {
+        match self {
+            // Decoders are expected to accept any ASCII character, even if an encoder would not
+            // write it directly.
+            Self::Utf7 => byte.is_ascii(),
+            Self::Imap => (0x20..=0x7E).contains(&byte),
+        }
+    }
+
+    /// Whether `ch` is written as itself when encoding.
+    fn is_direct_output(self, ch: char) -> bool {
+        match self {
+            // Set D of RFC 2152 and white space. Characters in the optional Set O are encoded,
+            // since some of them are unsafe in mail headers.
+            Self::Utf7 => ch.is_ascii_alphanumeric() || "'(),-./:? \t\r\n".contains(ch),
+            Self::Imap => (' '..='~').contains(&ch),
+        }
+    }
+}
+
+/// Decodes a base64 run after the shift character into `output`, consuming the terminating `-` if
+/// any. Returns whether the run is well-formed.
+fn decode_run(input: &mut &[u8], variant: Variant, output: &mut String) -> bool {
+    let mut units = Vec::new();
+    let (mut bits, mut bit_len) = (0u32, 0u32);
+    while let Some(value) = input.first().and_then(|&b| variant.base64_value(b)) {
+        *input = &input[1..];
+        bits = (bits << 6) | value;
+        bit_len += 6;
+        if bit_len >= 16 {
+            bit_len -= 16;
+            units.push((bits >> bit_len) as u16);
+            bits &= (1 << bit_len) - 1;
+        }
+    }
+    // Leftover bits are padding, which must be zero and shorter than a base64 character.
+    let mut is_valid = !units.is_empty() && bit_len < 6 && bits == 0;
+    match input.first() {
+        Some(b'-') => *input = &input[1..],
+        _ if variant == Variant::Imap => is_valid = false,
+        _ => {}
+    }
+    if !is_valid {
+        output.push(char::REPLACEMENT_CHARACTER);
+    }
+    for ch in char::decode_utf16(units) {
+        match ch {
+            Ok(ch) => output.push(ch),
+            Err(_) => {
+                output.push(char::REPLACEMENT_CHARACTER);
+                is_valid = false;
+            }
+        }
+    }
+    is_valid
+}
+
+pub(super) fn decode(
+    mut input: &[u8],
+    variant: Variant,
+    lossy: bool,
+) -> Result<String, ConvertError> {
+    let mut output = String::with_capacity(input.len());
+    while let Some((&byte, rest)) = input.split_first() {
+        input = rest;
+        let is_valid = if byte == variant.shift() {
+            if input.first() == Some(&b'-') {
+                input = &input[1..];
+                output.push(char::from(byte));
+                true
+            } else {
+                decode_run(&mut input, variant, &mut output)
+            }
+        } else if variant.is_direct_input(byte) {
+            output.push(char::from(byte));
+            true
+        } else {
+            output.push(char::REPLACEMENT_CHARACTER);
+            false
+        };
+        if !is_valid && !lossy {
+            return Err(ConvertError::InvalidInput);
+        }
+    }
+    Ok(output)
+}
+
+/// Splits `input` for incremental decoding, so that the prefix can be decoded on its own. Returns
+/// the length of the prefix, and whether the rest continues a base64 run. In that case, the prefix
+/// must be decoded with `-` appended, and the rest with the shift character prepended.
+pub(crate) fn split_incomplete(input: &[u8], variant: Variant) -> (usize, bool) {
+    let mut idx = 0;
+    while idx < input.len() {
+        if input[idx] != variant.shift() {
+            idx += 1;
+            continue;
+        }
+        let start = idx;
+        idx += 1;
+        let values: Vec<u32> = input[idx..]
+            .iter()
+            .map_while(|&b| variant.base64_value(b))
+            .collect();
+        idx += values.len();
+        if idx < input.len() {
+            // The run is terminated, possibly by `-` which is part of it.
+            idx += usize::from(input[idx] == b'-');
+            continue;
+        }
+        // Split after a multiple of 8 base64 characters, which hold exactly 3 UTF-16 units. At
+        // least one character is left, so that the rest is not mistaken for `+-`, and a pair of
+        // surrogates is never split.
+        let mut complete = values.len().saturating_sub(1) / 8 * 8;
+        while complete > 0 {
+            let group = values[complete - 8..complete]
+                .iter()
+                .fold(0u64, |bits, &value| (bits << 6) | u64::from(value));
+            if !(0xD800..=0xDBFF).contains(&(group & 0xFFFF)) {
+                break;
+            }
+            complete -= 8;
+        }
+        return match complete {
+            0 => (start, false),
+            _ => (start + 1 + complete, true),
+        };
+    }
+    (input.len(), false)
+}
+
+fn encode_run(units: &[u16], variant: Variant, output: &mut Vec<u8>) {
+    let alphabet = variant.base64_alphabet();
+    let (mut bits, mut bit_len) = (0u32, 0u32);
+    for &unit in units {
+        bits = (bits << 16) | u32::from(unit);
+        bit_len += 16;
+        while bit_len >= 6 {
+            bit_len -= 6;
+            output.push(alphabet[((bits >> bit_len) & 0x3F) as usize]);
+        }
+        bits &= (1 << bit_len) - 1;
+    }
+    if bit_len > 0 {
+        output.push(alphabet[((bits << (6 - bit_len)) & 0x3F) as usize]);
+    }
+}
+
+pub(super) fn encode(input: &str, variant: Variant) -> Vec<u8> {
+    let shift = variant.shift();
+    let mut output = Vec::with_capacity(input.len());
+    let mut units = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == char::from(shift) {
+            output.extend_from_slice(&[shift, b'-']);
+        } else if variant.is_direct_output(ch) {
+            output.push(ch as u8);
+        } else {
+            units.clear();
+            let mut buf = [0; 2];
+            units.extend_from_slice(ch.encode_utf16(&mut buf));
+            while let Some(&ch) = chars.peek() {
+                if ch == char::from(shift) || variant.is_direct_output(ch) {
+                    break;
+                }
+                units.extend_from_slice(ch.encode_utf16(&mut buf));
+                chars.next();
+            }
+            output.push(shift);
+            encode_run(&units, variant, &mut output);
+            output.push(b'-');
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_encoding() {
+        let testcases = [
+            ("utf-7", Some(Variant::Utf7)),
+            ("UTF7", Some(Variant::Utf7)),
+            ("utf-7-imap", Some(Variant::Imap)),
+            ("IMAP-UTF-7", Some(Variant::Imap)),
+            ("utf-8", None),
+        ];
+        for (input, expected) in testcases {
+            assert_eq!(Variant::from_encoding(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_utf7() {
+        let testcases = [
+            ("Hi Mom -☺-!", &b"Hi Mom -+Jjo--+ACE-"[..]),
+            ("日本語", b"+ZeVnLIqe-"),
+            ("A≢Α.", b"A+ImIDkQ-."),
+            ("1 + 1", b"1 +- 1"),
+            ("😀", b"+2D3eAA-"),
+        ];
+        for (idx, (str, bytes)) in testcases.into_iter().enumerate() {
+            assert_eq!(encode(str, Variant::Utf7), bytes, "{idx}");
+            assert_eq!(decode(bytes, Variant::Utf7, false).unwrap(), str, "{idx}");
+        }
+        // Direct characters and the implicit end of a run are accepted.
+        assert_eq!(
+            decode(b"1 +ACs- 1 = 2!", Variant::Utf7, false).unwrap(),
+            "1 + 1 = 2!"
+        );
+        assert_eq!(
+            decode(b"+ZeVnLIqe.", Variant::Utf7, false).unwrap(),
+            "日本語."
+        );
+    }
+
+    #[test]
+    fn test_imap() {
+        let testcases = [
+            (
+                "~peter/mail/台北/日本語",
+                &b"~peter/mail/&U,BTFw-/&ZeVnLIqe-"[..],
+            ),
+            ("Tom & Jerry", b"Tom &- Jerry"),
+            ("", b""),
+        ];
+        for (idx, (str, bytes)) in testcases.into_iter().enumerate() {
+            assert_eq!(encode(str, Variant::Imap), bytes, "{idx}");
+            assert_eq!(decode(bytes, Variant::Imap, false).unwrap(), str, "{idx}");
+        }
+    }
+
+    #[test]
+    fn test_split_incomplete() {
+        let testcases = [
+            (&b"abc"[..], Variant::Utf7, (3, false)),
+            (b"a+", Variant::Utf7, (1, false)),
+            (b"a+ZeVnLIqf-b", Variant::Utf7, (12, false)),
+            (b"a+ZeVnLIqf", Variant::Utf7, (1, false)),
+            (b"a+ZeVnLIqfZ", Variant::Utf7, (10, true)),
+            (b"a+ZeVnLIqfZeVn", Variant::Utf7, (10, true)),
+            (b"a&ZeVnLIqfZ", Variant::Imap, (10, true)),
+            // The third unit is a high surrogate
+            (b"+AGEAYdg93gA", Variant::Utf7, (0, false)),
+        ];
+        for (input, variant, expected) in testcases {
+            let result = split_incomplete(input, variant);
+            assert_eq!(result, expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let testcases = [
+            (&b"+ZeVnLIq-"[..], Variant::Utf7),
+            (b"+!", Variant::Utf7),
+            (b"+2D0-", Variant::Utf7),
+            (b"\xff", Variant::Utf7),
+            (b"&ZeVnLIqe", Variant::Imap),
+            (b"&ZeVnLIqe/-", Variant::Imap),
+            (b"a\tb", Variant::Imap),
+        ];
+        for (idx, (bytes, variant)) in testcases.into_iter().enumerate() {
+            assert_eq!(
+                decode(bytes, variant, false),
+                Err(ConvertError::InvalidInput),
+                "{idx}"
+            );
+            let lossy = decode(bytes, variant, true).unwrap();
+            assert!(lossy.contains('\u{fffd}'), "{idx}_lossy");
+        }
+    }
+}
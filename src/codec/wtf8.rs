@@ -0,0 +1,242 @@
+//! [WTF-8](https://simonsapin.github.io/wtf-8/), a superset of UTF-8 that is able to represent
+//! unpaired surrogates in potentially ill-formed UTF-16 losslessly.
+
+use core::str::FromStr;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::bom::{UTF16_BE_BOM, UTF16_LE_BOM};
+use crate::options::DefaultByteOrder;
+use crate::utf::{UtfEncoding, UtfType};
+use crate::ConvertError;
+
+use super::Builtin;
+
+pub(crate) enum Chunk<'i> {
+    Str(&'i str),
+    Surrogate(u16),
+    Invalid,
+}
+
+/// Splits WTF-8 input into runs of valid UTF-8, surrogates and invalid sequences.
+pub(crate) fn next_chunk<'i>(input: &mut &'i [u8]) -> Option<Chunk<'i>> {
+    if input.is_empty() {
+        return None;
+    }
+    let err = match core::str::from_utf8(input) {
+        Ok(str) => {
+            *input = &[];
+            return Some(Chunk::Str(str));
+        }
+        Err(err) => err,
+    };
+    if err.valid_up_to() > 0 {
+        let (valid, rest) = input.split_at(err.valid_up_to());
+        *input = rest;
+        // Safety: validated by `from_utf8` above.
+        return Some(Chunk::Str(unsafe { core::str::from_utf8_unchecked(valid) }));
+    }
+    if let [0xED, b1 @ 0xA0..=0xBF, b2 @ 0x80..=0xBF, ..] = **input {
+        *input = &input[3..];
+        return Some(Chunk::Surrogate(
+            0xD000 | (u16::from(b1 & 0x3F) << 6) | u16::from(b2 & 0x3F),
+        ));
+    }
+    *input = &input[err.error_len().unwrap_or(input.len())..];
+    Some(Chunk::Invalid)
+}
+
+fn is_high_surrogate(unit: u16) -> bool {
+    (0xD800..0xDC00).contains(&unit)
+}
+
+fn is_low_surrogate(unit: u16) -> bool {
+    (0xDC00..0xE000).contains(&unit)
+}
+
+/// Decodes WTF-8 into a [`String`]. Surrogates cannot be represented, hence are considered
+/// invalid.
+pub(super) fn decode(mut input: &[u8], lossy: bool) -> Result<String, ConvertError> {
+    let mut output = String::with_capacity(input.len());
+    while let Some(chunk) = next_chunk(&mut input) {
+        match chunk {
+            Chunk::Str(str) => output.push_str(str),
+            _ if lossy => output.push(char::REPLACEMENT_CHARACTER),
+            _ => return Err(ConvertError::InvalidInput),
+        }
+    }
+    Ok(output)
+}
+
+/// Decodes WTF-8 into potentially ill-formed UTF-16.
+pub(crate) fn to_utf16(mut input: &[u8], lossy: bool) -> Result<Vec<u16>, ConvertError> {
+    let mut output = Vec::with_capacity(input.len());
+    // A surrogate pair must be encoded as a supplementary character in WTF-8.
+    let mut follows_high_surrogate = false;
+    while let Some(chunk) = next_chunk(&mut input) {
+        let unit = match chunk {
+            Chunk::Str(str) => {
+                output.extend(str.encode_utf16());
+                follows_high_surrogate = false;
+                continue;
+            }
+            Chunk::Surrogate(unit) if !(follows_high_surrogate && is_low_surrogate(unit)) => unit,
+            _ if lossy => 0xFFFD,
+            _ => return Err(ConvertError::InvalidInput),
+        };
+        output.push(unit);
+        follows_high_surrogate = is_high_surrogate(unit);
+    }
+    Ok(output)
+}
+
+/// Encodes potentially ill-formed UTF-16 into WTF-8.
+pub(crate) fn from_utf16(input: impl IntoIterator<Item = u16>, output: &mut Vec<u8>) {
+    for result in char::decode_utf16(input) {
+        match result {
+            Ok(ch) => output.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes()),
+            Err(err) => {
+                let unit = err.unpaired_surrogate();
+                output.extend_from_slice(&[
+                    0xED,
+                    0x80 | ((unit >> 6) & 0x3F) as u8,
+                    0x80 | (unit & 0x3F) as u8,
+                ]);
+            }
+        }
+    }
+}
+
+/// Converts between WTF-8 and UTF-16 (or WTF-8 itself) without going through a [`str`], so that
+/// unpaired surrogates are preserved. Returns `None` for other conversions.
+pub(super) fn convert_utf16(
+    input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+    lossy: bool,
+) -> Option<Result<Vec<u8>, ConvertError>> {
+    let from_utf = UtfEncoding::from_str(from_encoding)
+        .ok()
+        .filter(UtfEncoding::is_utf16);
+    let to_utf = UtfEncoding::from_str(to_encoding)
+        .ok()
+        .filter(UtfEncoding::is_utf16);
+    let from_wtf8 = Builtin::from_encoding(from_encoding) == Some(Builtin::Wtf8);
+    let to_wtf8 = Builtin::from_encoding(to_encoding) == Some(Builtin::Wtf8);
+    let units = match (from_utf, from_wtf8) {
+        (Some(from_utf), _) if to_wtf8 => utf16_units(input, from_utf, lossy),
+        (None, true) if to_wtf8 || to_utf.is_some() => to_utf16(input, lossy),
+        _ => return None,
+    };
+    Some(units.map(|units| {
+        let mut output = Vec::with_capacity(units.len() * 2);
+        match to_utf {
+            Some(to_utf) => {
+                let is_le = to_utf.byte_order().is_le(true);
+                if to_utf.is_ambiguous() {
+                    output.extend_from_slice(if is_le { &UTF16_LE_BOM } else { &UTF16_BE_BOM });
+                }
+                for unit in units {
+                    output.extend_from_slice(&if is_le {
+                        unit.to_le_bytes()
+                    } else {
+                        unit.to_be_bytes()
+                    });
+                }
+            }
+            None => from_utf16(units, &mut output),
+        }
+        output
+    }))
+}
+
+fn utf16_units(
+    mut input: &[u8],
+    encoding: UtfEncoding,
+    lossy: bool,
+) -> Result<Vec<u16>, ConvertError> {
+    debug_assert_eq!(encoding.r#type(), UtfType::Utf16);
+    let is_le = encoding
+        .consume_input_bom(&mut input, DefaultByteOrder::default())
+        .is_le(true);
+    let chunks = input.chunks_exact(2);
+    if !chunks.remainder().is_empty() && !lossy {
+        return Err(ConvertError::InvalidInput);
+    }
+    let has_remainder = !chunks.remainder().is_empty();
+    let mut units: Vec<u16> = chunks
+        .map(|chunk| {
+            let bytes = [chunk[0], chunk[1]];
+            if is_le {
+                u16::from_le_bytes(bytes)
+            } else {
+                u16::from_be_bytes(bytes)
+            }
+        })
+        .collect();
+    if has_remainder {
+        units.push(0xFFFD);
+    }
+    Ok(units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_utf16() {
+        let testcases: [&[u16]; 5] = [
+            &[0x61, 0xD800, 0x62],
+            &[0xDC00, 0xD800],
+            &[0xD83D, 0xDE00, 0xDE00],
+            &[0x8299, 0x5B81, 0x5A1C],
+            &[],
+        ];
+        for (idx, units) in testcases.into_iter().enumerate() {
+            let mut wtf8 = Vec::new();
+            from_utf16(units.iter().copied(), &mut wtf8);
+            assert_eq!(to_utf16(&wtf8, false).unwrap(), units, "{idx}");
+        }
+    }
+
+    #[test]
+    fn test_from_utf16() {
+        let mut wtf8 = Vec::new();
+        from_utf16([0x61, 0xD800, 0xD83D, 0xDE00, 0xDFFF], &mut wtf8);
+        assert_eq!(wtf8, b"a\xed\xa0\x80\xf0\x9f\x98\x80\xed\xbf\xbf");
+    }
+
+    #[test]
+    fn test_to_utf16_invalid() {
+        let testcases: [(&[u8], &[u16]); 4] = [
+            // An encoded surrogate pair
+            (b"\xed\xa0\xbd\xed\xb8\x80", &[0xD83D, 0xFFFD]),
+            (b"a\xff", &[0x61, 0xFFFD]),
+            (b"\xed\xa0", &[0xFFFD, 0xFFFD]),
+            (b"\xc0\x80", &[0xFFFD, 0xFFFD]),
+        ];
+        for (idx, (input, expected_lossy)) in testcases.into_iter().enumerate() {
+            assert_eq!(
+                to_utf16(input, false),
+                Err(ConvertError::InvalidInput),
+                "{idx}"
+            );
+            assert_eq!(
+                to_utf16(input, true).unwrap(),
+                expected_lossy,
+                "{idx}_lossy"
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            decode(b"a\xed\xa0\x80b", false),
+            Err(ConvertError::InvalidInput)
+        );
+        assert_eq!(decode(b"a\xed\xa0\x80b", true).unwrap(), "a\u{fffd}b");
+        assert_eq!(decode("芙宁娜".as_bytes(), false).unwrap(), "芙宁娜");
+    }
+}
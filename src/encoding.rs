@@ -10,18 +10,61 @@ pub(crate) fn trim_encoding_prefix<'i>(ascii_input: &'i str, prefix: &str) -> Op
     )
 }
 
-#[allow(dead_code)]
 pub(crate) fn match_encoding_parts<'i>(input: &'i str, parts: &[&str]) -> Option<&'i str> {
     parts
         .iter()
         .try_fold(input, |input, &part| trim_encoding_prefix(input, part))
 }
 
-#[allow(dead_code)]
 pub(crate) fn match_encoding_parts_exact(input: &str, parts: &[&str]) -> bool {
     match_encoding_parts(input, parts) == Some("")
 }
 
+/// Returns whether `encoding` is known to map every byte in `0x00..=0x7F` to the same ASCII
+/// character when decoding and vice versa when encoding, on every supported implementation.
+///
+/// The list is intentionally conservative and only contains labels recognized by all
+/// implementations. Stateful encodings (ISO-2022-*, HZ, UTF-7) and encodings whose single-byte
+/// part deviates from ASCII in some implementations (e.g. `Shift_JIS` mapping `0x5C` to `¥` in
+/// libiconv) are excluded.
+pub(crate) fn is_ascii_compatible(encoding: &str) -> bool {
+    const ASCII_COMPATIBLE_PARTS: [&[&str]; 11] = [
+        &["utf", "8"],
+        &["cesu", "8"],
+        &["us", "ascii"],
+        &["gbk"],
+        &["gb", "2312"],
+        &["gb", "18030"],
+        &["big", "5"],
+        &["euc", "jp"],
+        &["euc", "kr"],
+        &["koi8", "r"],
+        &["koi8", "u"],
+    ];
+    if ASCII_COMPATIBLE_PARTS
+        .iter()
+        .any(|parts| match_encoding_parts_exact(encoding, parts))
+    {
+        return true;
+    }
+    if let Some(code) = ["windows", "cp"]
+        .into_iter()
+        .find_map(|prefix| trim_encoding_prefix(encoding, prefix))
+    {
+        return matches!(
+            code,
+            "1250" | "1251" | "1252" | "1253" | "1254" | "1255" | "1256" | "1257" | "1258"
+        );
+    }
+    if let Some(part) = match_encoding_parts(encoding, &["iso", "8859"]) {
+        return matches!(
+            part,
+            "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" | "13" | "15"
+        );
+    }
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,4 +132,32 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_is_ascii_compatible() {
+        let testcases = [
+            ("utf-8", true),
+            ("UTF8", true),
+            ("us-ascii", true),
+            ("gb18030", true),
+            ("GBK", true),
+            ("euc-jp", true),
+            ("big5", true),
+            ("windows-1252", true),
+            ("cp1251", true),
+            ("iso-8859-1", true),
+            ("ISO_8859-15", true),
+            ("shift_jis", false),
+            ("iso-2022-jp", false),
+            ("utf-7", false),
+            ("utf-16le", false),
+            ("windows-1200", false),
+            ("iso-8859-12", false),
+            ("ibm037", false),
+            ("invalid_encoding", false),
+        ];
+        for (input, expected) in testcases {
+            assert_eq!(is_ascii_compatible(input), expected, "{input}");
+        }
+    }
 }
@@ -1,7 +1,7 @@
 use core::fmt;
 
 #[cfg(doc)]
-use crate::{convert, convert_lossy, decode, decode_lossy};
+use crate::{convert, convert_lossy, decode, decode_lossy, idna};
 
 /// Error representation for [`decode`] and [`convert`].
 #[derive(Debug, PartialEq, Eq)]
@@ -23,6 +23,17 @@ pub enum ConvertLossyError {
     UnknownConversion,
 }
 
+/// Error representation for functions in [`idna`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum IdnaError {
+    /// The input could not be decoded, or the output could not be encoded.
+    Convert(ConvertError),
+    /// A label is not valid Punycode.
+    InvalidPunycode,
+    /// A label is empty or longer than 63 bytes in its ASCII form.
+    InvalidLabel,
+}
+
 impl fmt::Display for ConvertError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(match self {
@@ -40,6 +51,22 @@ impl fmt::Display for ConvertLossyError {
     }
 }
 
+impl fmt::Display for IdnaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdnaError::Convert(err) => err.fmt(f),
+            IdnaError::InvalidPunycode => f.write_str("label is not valid punycode"),
+            IdnaError::InvalidLabel => f.write_str("label is empty or too long"),
+        }
+    }
+}
+
+impl From<ConvertError> for IdnaError {
+    fn from(err: ConvertError) -> Self {
+        IdnaError::Convert(err)
+    }
+}
+
 impl From<ConvertLossyError> for ConvertError {
     fn from(err: ConvertLossyError) -> Self {
         match err {
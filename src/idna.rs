@@ -0,0 +1,267 @@
+//! Conversion of internationalized domain names between Unicode and the ASCII
+//! compatible encoding (ACE) used by DNS, e.g. `bücher.example` and
+//! `xn--bcher-kva.example`.
+//!
+//! Non-ASCII labels are lowercased and encoded with [Punycode] behind the
+//! `xn--` prefix. Other mappings and validations of [UTS #46], such as
+//! normalization and checks for disallowed characters, are not performed, so
+//! the input is expected to be a valid domain name already.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use iconv_native::idna;
+//!
+//! // "芙宁娜.example" in GB18030
+//! let input = b"\xdc\xbd\xc4\xfe\xc4\xc8.example";
+//! let ascii = idna::to_ascii(input, "gb18030")?;
+//! assert_eq!(ascii, "xn--pzs55a217f.example");
+//! assert_eq!(idna::from_ascii(&ascii, "gb18030")?, input);
+//! # Ok::<(), iconv_native::IdnaError>(())
+//! ```
+//!
+//! [Punycode]: https://www.rfc-editor.org/rfc/rfc3492
+//! [UTS #46]: https://www.unicode.org/reports/tr46/
+
+use alloc::{string::String, vec::Vec};
+
+use crate::IdnaError;
+
+const ACE_PREFIX: &str = "xn--";
+const MAX_LABEL_LEN: usize = 63;
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 0x80;
+
+/// Label separators recognized in Unicode input, as defined by IDNA2003.
+fn is_separator(ch: char) -> bool {
+    matches!(ch, '.' | '\u{3002}' | '\u{FF0E}' | '\u{FF61}')
+}
+
+fn adapt(mut delta: u32, num_points: u32, is_first: bool) -> u32 {
+    delta /= if is_first { DAMP } else { 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+    k.saturating_sub(bias).clamp(T_MIN, T_MAX)
+}
+
+fn encode_digit(digit: u32) -> char {
+    match digit {
+        0..=25 => char::from(b'a' + digit as u8),
+        _ => char::from(b'0' + (digit - 26) as u8),
+    }
+}
+
+fn decode_digit(byte: u8) -> Option<u32> {
+    match byte {
+        b'a'..=b'z' => Some(u32::from(byte - b'a')),
+        b'A'..=b'Z' => Some(u32::from(byte - b'A')),
+        b'0'..=b'9' => Some(u32::from(byte - b'0') + 26),
+        _ => None,
+    }
+}
+
+/// Encodes `input` with Punycode, without the `xn--` prefix.
+///
+/// Returns [`IdnaError::InvalidPunycode`] if the input is too long to be
+/// encoded.
+pub fn punycode_encode(input: &str) -> Result<String, IdnaError> {
+    let mut output: String = input.chars().filter(char::is_ascii).collect();
+    let basic_len = output.len() as u32;
+    if basic_len > 0 {
+        output.push('-');
+    }
+    let (mut n, mut delta, mut bias) = (INITIAL_N, 0u32, INITIAL_BIAS);
+    let mut handled = basic_len;
+    let total = input.chars().count() as u32;
+    while handled < total {
+        let Some(m) = input.chars().map(u32::from).filter(|&c| c >= n).min() else {
+            break;
+        };
+        delta = (m - n)
+            .checked_mul(handled + 1)
+            .and_then(|d| delta.checked_add(d))
+            .ok_or(IdnaError::InvalidPunycode)?;
+        n = m;
+        for c in input.chars().map(u32::from) {
+            if c < n {
+                delta = delta.checked_add(1).ok_or(IdnaError::InvalidPunycode)?;
+            }
+            if c != n {
+                continue;
+            }
+            let mut q = delta;
+            let mut k = BASE;
+            loop {
+                let t = threshold(k, bias);
+                if q < t {
+                    break;
+                }
+                output.push(encode_digit(t + (q - t) % (BASE - t)));
+                q = (q - t) / (BASE - t);
+                k += BASE;
+            }
+            output.push(encode_digit(q));
+            bias = adapt(delta, handled + 1, handled == basic_len);
+            delta = 0;
+            handled += 1;
+        }
+        delta += 1;
+        n += 1;
+    }
+    Ok(output)
+}
+
+/// Decodes Punycode `input`, without the `xn--` prefix.
+///
+/// Returns [`IdnaError::InvalidPunycode`] if the input is not valid Punycode.
+pub fn punycode_decode(input: &str) -> Result<String, IdnaError> {
+    let (basic, extended) = match input.rfind('-') {
+        Some(pos) => (&input[..pos], &input[pos + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return Err(IdnaError::InvalidPunycode);
+    }
+    let mut output: Vec<char> = basic.chars().collect();
+    let (mut n, mut i, mut bias) = (INITIAL_N, 0u32, INITIAL_BIAS);
+    let mut digits = extended.bytes();
+    while digits.len() > 0 {
+        let old_i = i;
+        let mut w = 1u32;
+        let mut k = BASE;
+        loop {
+            let digit = digits
+                .next()
+                .and_then(decode_digit)
+                .ok_or(IdnaError::InvalidPunycode)?;
+            i = digit
+                .checked_mul(w)
+                .and_then(|d| i.checked_add(d))
+                .ok_or(IdnaError::InvalidPunycode)?;
+            let t = threshold(k, bias);
+            if digit < t {
+                break;
+            }
+            w = w.checked_mul(BASE - t).ok_or(IdnaError::InvalidPunycode)?;
+            k += BASE;
+        }
+        let len = output.len() as u32 + 1;
+        bias = adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len).ok_or(IdnaError::InvalidPunycode)?;
+        i %= len;
+        let ch = char::from_u32(n).ok_or(IdnaError::InvalidPunycode)?;
+        output.insert(i as usize, ch);
+        i += 1;
+    }
+    Ok(output.into_iter().collect())
+}
+
+fn push_label(output: &mut String, label: &str, is_last: bool) -> Result<(), IdnaError> {
+    let start = output.len();
+    if label.is_ascii() {
+        output.extend(label.chars().map(|ch| ch.to_ascii_lowercase()));
+    } else {
+        let lowercase: String = label.chars().flat_map(char::to_lowercase).collect();
+        output.push_str(ACE_PREFIX);
+        output.push_str(&punycode_encode(&lowercase)?);
+    }
+    let len = output.len() - start;
+    // An empty last label stands for the root of a fully qualified domain name.
+    if len > MAX_LABEL_LEN || (len == 0 && !is_last) {
+        return Err(IdnaError::InvalidLabel);
+    }
+    Ok(())
+}
+
+/// Decodes `input` in `encoding` with [`decode`](crate::decode), and converts
+/// the domain name to its ASCII form.
+///
+/// Returns [`IdnaError::InvalidLabel`] if a label other than the last one is
+/// empty, or a label is longer than 63 bytes once converted.
+pub fn to_ascii(input: impl AsRef<[u8]>, encoding: &str) -> Result<String, IdnaError> {
+    let decoded = crate::decode(input, encoding)?;
+    let mut output = String::with_capacity(decoded.len());
+    let mut labels = decoded.split(is_separator).peekable();
+    while let Some(label) = labels.next() {
+        let is_last = labels.peek().is_none();
+        push_label(&mut output, label, is_last)?;
+        if !is_last {
+            output.push('.');
+        }
+    }
+    Ok(output)
+}
+
+/// Converts a domain name in ASCII form to Unicode, decoding labels starting
+/// with `xn--`. Other labels are kept as-is.
+///
+/// Returns [`IdnaError::InvalidPunycode`] if a label starting with `xn--` is
+/// not valid Punycode.
+pub fn to_unicode(domain: &str) -> Result<String, IdnaError> {
+    let mut output = String::with_capacity(domain.len());
+    for (idx, label) in domain.split('.').enumerate() {
+        if idx > 0 {
+            output.push('.');
+        }
+        match label.split_at_checked(ACE_PREFIX.len()) {
+            Some((prefix, encoded)) if prefix.eq_ignore_ascii_case(ACE_PREFIX) => {
+                output.push_str(&punycode_decode(encoded)?);
+            }
+            _ => output.push_str(label),
+        }
+    }
+    Ok(output)
+}
+
+/// Converts a domain name in ASCII form to Unicode with [`to_unicode`], and
+/// encodes it in `encoding` with [`encode`](crate::encode).
+pub fn from_ascii(domain: &str, encoding: &str) -> Result<Vec<u8>, IdnaError> {
+    Ok(crate::encode(to_unicode(domain)?, encoding)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_punycode() {
+        let testcases = [
+            ("", ""),
+            ("abc", "abc-"),
+            ("ü", "tda"),
+            ("bücher", "bcher-kva"),
+            ("他们为什么不说中文", "ihqwcrb4cv8a8dqg056pqjye"),
+            ("3年B組金八先生", "3B-ww4c5e180e575a65lsy2b"),
+        ];
+        for (decoded, encoded) in testcases {
+            assert_eq!(punycode_encode(decoded).unwrap(), encoded, "{decoded}");
+            assert_eq!(punycode_decode(encoded).unwrap(), decoded, "{encoded}");
+        }
+    }
+
+    #[test]
+    fn test_punycode_invalid() {
+        let testcases = ["a-!", "bcher-kv", "ü-tda", "99999999999"];
+        for input in testcases {
+            assert_eq!(
+                punycode_decode(input),
+                Err(IdnaError::InvalidPunycode),
+                "{input}"
+            );
+        }
+    }
+}
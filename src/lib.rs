@@ -7,18 +7,44 @@
 #![doc = include_str!("../docs/README.md")]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+#[cfg(any(feature = "futures-io", feature = "tokio"))]
+pub mod async_io;
 mod bom;
+mod bound;
+mod codec;
 mod encoding;
 mod error;
+pub mod idna;
+#[cfg(feature = "std")]
+mod lines;
+#[cfg(all(feature = "std", unix))]
+pub mod locale;
+mod newline;
+#[cfg(feature = "normalization")]
+mod normalization;
+mod options;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod stream;
 mod sys;
+#[cfg(feature = "tokio-codec")]
+pub mod tokio_codec;
 mod utf;
 
 use core::str::FromStr;
 
-use alloc::{string::String, vec::Vec};
+use alloc::{borrow::Cow, string::String, vec::Vec};
 
-pub use error::{ConvertError, ConvertLossyError};
+pub use error::{ConvertError, ConvertLossyError, IdnaError};
+#[cfg(feature = "std")]
+pub use lines::{DecodedLines, LinesError};
+#[cfg(feature = "normalization")]
+pub use options::NormalizationForm;
+pub use options::{BomPolicy, ConvertOptions, DefaultByteOrder, EbcdicNewline, NewlinePolicy};
+pub use stream::{Converter, DecodeChars, Decoder, Encoder};
 
 #[doc = include_str!("../docs/convert.md")]
 pub fn convert(
@@ -26,7 +52,7 @@ pub fn convert(
     from_encoding: &str,
     to_encoding: &str,
 ) -> Result<Vec<u8>, ConvertError> {
-    sys::convert(input.as_ref(), from_encoding, to_encoding)
+    codec::convert(input.as_ref(), from_encoding, to_encoding)
 }
 
 #[doc = include_str!("../docs/convert_lossy.md")]
@@ -35,21 +61,295 @@ pub fn convert_lossy(
     from_encoding: &str,
     to_encoding: &str,
 ) -> Result<Vec<u8>, ConvertLossyError> {
-    sys::convert_lossy(input.as_ref(), from_encoding, to_encoding)
+    codec::convert_lossy(input.as_ref(), from_encoding, to_encoding)
+}
+
+#[doc = include_str!("../docs/convert_with_options.md")]
+pub fn convert_with_options(
+    input: impl AsRef<[u8]>,
+    from_encoding: &str,
+    to_encoding: &str,
+    options: ConvertOptions,
+) -> Result<Vec<u8>, ConvertError> {
+    let prepared = options::Prepared::new(input.as_ref(), from_encoding, to_encoding, &options);
+    let from_encoding = prepared.source_encoding(from_encoding);
+    let to_encoding = prepared.target_encoding(to_encoding);
+    let mut output = prepared.output(prepared.input.len());
+    if !options.transforms_text() {
+        convert_append(prepared.input, from_encoding, to_encoding, &mut output)?;
+        return Ok(output);
+    }
+    let mut decoded = String::with_capacity(prepared.input.len());
+    options.decode_transformed(prepared.input, from_encoding, false, &mut decoded)?;
+    if is_utf8(to_encoding) {
+        output.extend_from_slice(decoded.as_bytes());
+    } else {
+        output.extend_from_slice(&codec::encode(&decoded, to_encoding)?);
+    }
+    Ok(output)
+}
+
+#[doc = include_str!("../docs/convert_lossy_with_options.md")]
+pub fn convert_lossy_with_options(
+    input: impl AsRef<[u8]>,
+    from_encoding: &str,
+    to_encoding: &str,
+    options: ConvertOptions,
+) -> Result<Vec<u8>, ConvertLossyError> {
+    let prepared = options::Prepared::new(input.as_ref(), from_encoding, to_encoding, &options);
+    let from_encoding = prepared.source_encoding(from_encoding);
+    let to_encoding = prepared.target_encoding(to_encoding);
+    let converted = if !options.transforms_text() {
+        codec::convert_lossy(prepared.input, from_encoding, to_encoding)?
+    } else {
+        let mut decoded = String::with_capacity(prepared.input.len());
+        options
+            .decode_transformed(prepared.input, from_encoding, true, &mut decoded)
+            .map_err(codec::into_lossy)?;
+        if is_utf8(to_encoding) {
+            decoded.into_bytes()
+        } else {
+            codec::encode_lossy(&decoded, to_encoding)?
+        }
+    };
+    if prepared.output_bom.is_empty() {
+        return Ok(converted);
+    }
+    let mut output = prepared.output(converted.len());
+    output.extend_from_slice(&converted);
+    Ok(output)
+}
+
+#[doc = include_str!("../docs/convert_cow.md")]
+pub fn convert_cow<'i>(
+    input: &'i (impl AsRef<[u8]> + ?Sized),
+    from_encoding: &str,
+    to_encoding: &str,
+) -> Result<Cow<'i, [u8]>, ConvertError> {
+    let input = input.as_ref();
+    if is_identity_conversion(input, from_encoding, to_encoding) {
+        return Ok(Cow::Borrowed(input));
+    }
+    codec::convert(input, from_encoding, to_encoding).map(Cow::Owned)
+}
+
+#[doc = include_str!("../docs/convert_into.md")]
+pub fn convert_into(
+    input: impl AsRef<[u8]>,
+    from_encoding: &str,
+    to_encoding: &str,
+    output: &mut [u8],
+) -> Result<(usize, usize), ConvertError> {
+    codec::convert_into(input.as_ref(), from_encoding, to_encoding, output)
+}
+
+#[doc = include_str!("../docs/convert_append.md")]
+pub fn convert_append(
+    input: impl AsRef<[u8]>,
+    from_encoding: &str,
+    to_encoding: &str,
+    output: &mut Vec<u8>,
+) -> Result<(), ConvertError> {
+    let input = input.as_ref();
+    if is_identity_conversion(input, from_encoding, to_encoding) {
+        output.extend_from_slice(input);
+        return Ok(());
+    }
+    codec::convert_append(input, from_encoding, to_encoding, output)
+}
+
+#[doc = include_str!("../docs/max_output_len.md")]
+pub fn max_output_len(from_encoding: &str, to_encoding: &str, input_len: usize) -> Option<usize> {
+    bound::OutputBound::new(from_encoding, to_encoding)?.max_output_len(input_len)
 }
 
 #[doc = include_str!("../docs/decode.md")]
 pub fn decode(input: impl AsRef<[u8]>, encoding: &str) -> Result<String, ConvertError> {
     let mut input = input.as_ref();
     try_strip_utf8_bom(&mut input, encoding);
-    sys::decode(input, encoding)
+    codec::decode(input, encoding)
 }
 
 #[doc = include_str!("../docs/decode_lossy.md")]
 pub fn decode_lossy(input: impl AsRef<[u8]>, encoding: &str) -> Result<String, ConvertLossyError> {
     let mut input = input.as_ref();
     try_strip_utf8_bom(&mut input, encoding);
-    sys::decode_lossy(input, encoding)
+    codec::decode_lossy(input, encoding)
+}
+
+#[doc = include_str!("../docs/decode_cow.md")]
+pub fn decode_cow<'i>(
+    input: &'i (impl AsRef<[u8]> + ?Sized),
+    encoding: &str,
+) -> Result<Cow<'i, str>, ConvertError> {
+    let mut input = input.as_ref();
+    try_strip_utf8_bom(&mut input, encoding);
+    if let Some(str) = try_borrow_str(input, encoding) {
+        return Ok(Cow::Borrowed(str));
+    }
+    codec::decode(input, encoding).map(Cow::Owned)
+}
+
+#[doc = include_str!("../docs/decode_chars.md")]
+pub fn decode_chars<'i>(
+    input: &'i (impl AsRef<[u8]> + ?Sized),
+    encoding: &str,
+) -> Result<DecodeChars<'i>, ConvertError> {
+    Ok(DecodeChars::new(input.as_ref(), Decoder::new(encoding)?))
+}
+
+#[doc = include_str!("../docs/decode_chars_lossy.md")]
+pub fn decode_chars_lossy<'i>(
+    input: &'i (impl AsRef<[u8]> + ?Sized),
+    encoding: &str,
+) -> Result<DecodeChars<'i>, ConvertLossyError> {
+    Ok(DecodeChars::new(
+        input.as_ref(),
+        Decoder::new_lossy(encoding)?,
+    ))
+}
+
+#[doc = include_str!("../docs/decode_with_options.md")]
+pub fn decode_with_options(
+    input: impl AsRef<[u8]>,
+    encoding: &str,
+    options: ConvertOptions,
+) -> Result<String, ConvertError> {
+    let prepared = options::Prepared::new(input.as_ref(), encoding, "UTF-8", &options);
+    let encoding = prepared.source_encoding(encoding);
+    if !options.transforms_text() {
+        let decoded = decode_unchecked_bom(prepared.input, encoding)?;
+        return Ok(with_bom(decoded, &prepared));
+    }
+    let mut output = output_string(&prepared);
+    options.decode_transformed(prepared.input, encoding, false, &mut output)?;
+    Ok(output)
+}
+
+#[doc = include_str!("../docs/decode_lossy_with_options.md")]
+pub fn decode_lossy_with_options(
+    input: impl AsRef<[u8]>,
+    encoding: &str,
+    options: ConvertOptions,
+) -> Result<String, ConvertLossyError> {
+    let prepared = options::Prepared::new(input.as_ref(), encoding, "UTF-8", &options);
+    let encoding = prepared.source_encoding(encoding);
+    if !options.transforms_text() {
+        let decoded = decode_lossy_unchecked_bom(prepared.input, encoding)?;
+        return Ok(with_bom(decoded, &prepared));
+    }
+    let mut output = output_string(&prepared);
+    options
+        .decode_transformed(prepared.input, encoding, true, &mut output)
+        .map_err(codec::into_lossy)?;
+    Ok(output)
+}
+
+#[doc = include_str!("../docs/encode.md")]
+pub fn encode(input: impl AsRef<str>, encoding: &str) -> Result<Vec<u8>, ConvertError> {
+    let input = input.as_ref();
+    if is_utf8(encoding) {
+        return Ok(input.as_bytes().to_vec());
+    }
+    codec::encode(input, encoding)
+}
+
+#[doc = include_str!("../docs/encode_lossy.md")]
+pub fn encode_lossy(input: impl AsRef<str>, encoding: &str) -> Result<Vec<u8>, ConvertLossyError> {
+    let input = input.as_ref();
+    if is_utf8(encoding) {
+        return Ok(input.as_bytes().to_vec());
+    }
+    codec::encode_lossy(input, encoding)
+}
+
+#[doc = include_str!("../docs/decode_utf16_to_wtf8.md")]
+pub fn decode_utf16_to_wtf8(input: impl AsRef<[u16]>) -> Vec<u8> {
+    let input = input.as_ref();
+    let mut output = Vec::with_capacity(input.len() * 3);
+    codec::wtf8::from_utf16(input.iter().copied(), &mut output);
+    output
+}
+
+#[doc = include_str!("../docs/encode_wtf8_to_utf16.md")]
+pub fn encode_wtf8_to_utf16(input: impl AsRef<[u8]>) -> Result<Vec<u16>, ConvertError> {
+    codec::wtf8::to_utf16(input.as_ref(), false)
+}
+
+#[doc = include_str!("../docs/count_gsm0338_septets.md")]
+pub fn count_gsm0338_septets(input: impl AsRef<str>) -> Option<usize> {
+    codec::gsm0338::count_septets(input.as_ref())
+}
+
+fn is_utf8(encoding: &str) -> bool {
+    utf::UtfEncoding::from_str(encoding).is_ok_and(|utf| utf.is_utf8())
+}
+
+fn try_borrow_str<'i>(input: &'i [u8], encoding: &str) -> Option<&'i str> {
+    if is_utf8(encoding) {
+        return core::str::from_utf8(input).ok();
+    }
+    if input.is_ascii() && encoding::is_ascii_compatible(encoding) {
+        // Safety: ASCII bytes are valid UTF-8.
+        return Some(unsafe { core::str::from_utf8_unchecked(input) });
+    }
+    None
+}
+
+fn is_identity_conversion(input: &[u8], from_encoding: &str, to_encoding: &str) -> bool {
+    if is_utf8(from_encoding) && is_utf8(to_encoding) {
+        return core::str::from_utf8(input).is_ok();
+    }
+    // A standard TextEncoder does not support legacy encodings.
+    #[cfg(all(
+        target_arch = "wasm32",
+        feature = "web-encoding",
+        not(feature = "wasm-nonstandard-allow-legacy-encoding")
+    ))]
+    if !is_utf8(to_encoding) {
+        return false;
+    }
+    input.is_ascii()
+        && encoding::is_ascii_compatible(from_encoding)
+        && encoding::is_ascii_compatible(to_encoding)
+}
+
+/// Decodes input whose BOM has been handled by [`options::Prepared`].
+fn decode_unchecked_bom<'i>(input: &'i [u8], encoding: &str) -> Result<Cow<'i, str>, ConvertError> {
+    match try_borrow_str(input, encoding) {
+        Some(str) => Ok(Cow::Borrowed(str)),
+        None => codec::decode(input, encoding).map(Cow::Owned),
+    }
+}
+
+/// Decodes input whose BOM has been handled by [`options::Prepared`], replacing invalid sequences.
+fn decode_lossy_unchecked_bom<'i>(
+    input: &'i [u8],
+    encoding: &str,
+) -> Result<Cow<'i, str>, ConvertLossyError> {
+    match try_borrow_str(input, encoding) {
+        Some(str) => Ok(Cow::Borrowed(str)),
+        None => codec::decode_lossy(input, encoding).map(Cow::Owned),
+    }
+}
+
+fn with_bom(decoded: Cow<'_, str>, prepared: &options::Prepared<'_>) -> String {
+    if prepared.output_bom.is_empty() {
+        return decoded.into_owned();
+    }
+    let mut output = String::with_capacity(decoded.len() + 3);
+    output.push('\u{feff}');
+    output.push_str(&decoded);
+    output
+}
+
+/// Creates the output of a decoding to [`String`], starting with the BOM if one is added.
+fn output_string(prepared: &options::Prepared<'_>) -> String {
+    let mut output = String::with_capacity(prepared.output_bom.len() + prepared.input.len());
+    if !prepared.output_bom.is_empty() {
+        output.push('\u{feff}');
+    }
+    output
 }
 
 fn try_strip_utf8_bom(input: &mut &[u8], encoding: &str) {
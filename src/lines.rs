@@ -0,0 +1,234 @@
+//! Iteration over the lines of decoded text.
+
+use std::io::{self, BufRead};
+use std::{error, fmt};
+
+use alloc::string::String;
+
+use crate::{ConvertError, ConvertLossyError, Decoder};
+
+/// Decoded text split into lines.
+///
+/// Lines are terminated by `\n` or NEL (`U+0085`), and a `\r` before `\n` is
+/// removed as well. NEL is the newline of EBCDIC text, decoded from `0x15`.
+/// Since the text is decoded before it is split, line terminators are found
+/// wherever the source encoding puts them, even if `\n` is not `0x0A` there.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LineBuffer {
+    /// Decoded text, whose lines before `start` have been returned already.
+    text: String,
+    start: usize,
+}
+
+const TERMINATORS: [char; 2] = ['\n', '\u{85}'];
+
+impl LineBuffer {
+    /// Returns the buffer to append decoded text to.
+    pub(crate) fn text_mut(&mut self) -> &mut String {
+        self.text.drain(..self.start);
+        self.start = 0;
+        &mut self.text
+    }
+
+    /// Returns the text after the last complete line.
+    pub(crate) fn pending(&self) -> &str {
+        let rest = &self.text[self.start..];
+        match rest.rmatch_indices(TERMINATORS).next() {
+            Some((pos, terminator)) => &rest[pos + terminator.len()..],
+            None => rest,
+        }
+    }
+
+    /// Drops the text after the last complete line.
+    pub(crate) fn discard_pending(&mut self) {
+        let len = self.text.len() - self.pending().len();
+        self.text.truncate(len);
+    }
+
+    /// Takes the next complete line, without its terminator.
+    pub(crate) fn next_line(&mut self) -> Option<String> {
+        let rest = &self.text[self.start..];
+        let (len, terminator) = rest.match_indices(TERMINATORS).next()?;
+        let line = match terminator {
+            "\n" => rest[..len].strip_suffix('\r').unwrap_or(&rest[..len]),
+            _ => &rest[..len],
+        };
+        let line = line.into();
+        self.start += len + terminator.len();
+        Some(line)
+    }
+
+    /// Takes the text after the last complete line, at the end of the input.
+    pub(crate) fn take_last_line(&mut self) -> Option<String> {
+        let rest = &self.text[self.start..];
+        let line = match rest {
+            "" => None,
+            rest => Some(rest.strip_suffix('\r').unwrap_or(rest).into()),
+        };
+        self.text.clear();
+        self.start = 0;
+        line
+    }
+}
+
+/// An iterator over the lines of a reader in any supported encoding, created
+/// with [`DecodedLines::new`] or [`DecodedLines::new_lossy`].
+///
+/// Like [`BufRead::lines`], each line is returned without the terminating
+/// `\n` or `\r\n`. NEL (`U+0085`) also ends a line, since EBCDIC encodes the
+/// newline as NL (`0x15`), which is decoded to NEL. The input is decoded
+/// incrementally with a [`Decoder`] before it is split, so lines are found
+/// correctly in encodings such as UTF-16 and EBCDIC, where a newline is not
+/// the byte `0x0A`, and the state of stateful encodings is carried over from
+/// one line to the next.
+///
+/// After an error, the iteration ends.
+///
+/// # Examples
+///
+/// ```rust
+/// use iconv_native::DecodedLines;
+///
+/// let input = &b"\xff\xfe\x99\x82\x0a\x00\x81\x5b\x0d\x00\x0a\x00\x1c\x5a"[..];
+/// let lines = DecodedLines::new(input, "utf-16")?;
+/// let lines: Result<Vec<_>, _> = lines.collect();
+/// assert_eq!(lines?, ["芙", "宁", "娜"]);
+/// # Ok::<(), iconv_native::LinesError>(())
+/// ```
+#[derive(Debug)]
+pub struct DecodedLines<R> {
+    reader: R,
+    decoder: Decoder,
+    lines: LineBuffer,
+    error: Option<LinesError>,
+    is_done: bool,
+}
+
+impl<R: BufRead> DecodedLines<R> {
+    fn with_decoder(reader: R, decoder: Decoder) -> Self {
+        Self {
+            reader,
+            decoder,
+            lines: LineBuffer::default(),
+            error: None,
+            is_done: false,
+        }
+    }
+
+    /// Creates an iterator over the lines of `reader` in `encoding`, which
+    /// returns [`ConvertError::InvalidInput`] on invalid input.
+    ///
+    /// Returns [`ConvertError::UnknownConversion`] if `encoding` is not
+    /// supported.
+    pub fn new(reader: R, encoding: &str) -> Result<Self, ConvertError> {
+        Ok(Self::with_decoder(reader, Decoder::new(encoding)?))
+    }
+
+    /// Creates an iterator over the lines of `reader` in `encoding`, which
+    /// replaces invalid input like [`decode_lossy`](crate::decode_lossy).
+    ///
+    /// Returns [`ConvertLossyError::UnknownConversion`] if `encoding` is not
+    /// supported.
+    pub fn new_lossy(reader: R, encoding: &str) -> Result<Self, ConvertLossyError> {
+        Ok(Self::with_decoder(reader, Decoder::new_lossy(encoding)?))
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the underlying reader. Input decoded but not returned as lines
+    /// yet is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Decodes the next chunk of input.
+    fn fill(&mut self) -> Result<(), LinesError> {
+        let buf = match self.reader.fill_buf() {
+            Ok(buf) => buf,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        if buf.is_empty() {
+            self.is_done = true;
+            self.decoder.finish(self.lines.text_mut())?;
+            return Ok(());
+        }
+        let len = buf.len();
+        let result = self.decoder.decode(buf, self.lines.text_mut());
+        self.reader.consume(len);
+        Ok(result?)
+    }
+}
+
+impl<R: BufRead> Iterator for DecodedLines<R> {
+    type Item = Result<String, LinesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(line) = self.lines.next_line() {
+                return Some(Ok(line));
+            }
+            // Return the lines before an invalid sequence first.
+            if let Some(err) = self.error.take() {
+                return Some(Err(err));
+            }
+            if self.is_done {
+                return self.lines.take_last_line().map(Ok);
+            }
+            if let Err(err) = self.fill() {
+                self.error = Some(err);
+                self.is_done = true;
+                // The line containing the invalid sequence is not returned.
+                self.lines.discard_pending();
+            }
+        }
+    }
+}
+
+/// Error representation for [`DecodedLines`].
+#[derive(Debug)]
+pub enum LinesError {
+    /// The input is invalid, or the encoding is not supported.
+    Convert(ConvertError),
+    /// Reading from the underlying reader failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for LinesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinesError::Convert(err) => err.fmt(f),
+            LinesError::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for LinesError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LinesError::Io(err) => Some(err),
+            LinesError::Convert(_) => None,
+        }
+    }
+}
+
+impl From<ConvertError> for LinesError {
+    fn from(err: ConvertError) -> Self {
+        LinesError::Convert(err)
+    }
+}
+
+impl From<ConvertLossyError> for LinesError {
+    fn from(err: ConvertLossyError) -> Self {
+        LinesError::Convert(err.into())
+    }
+}
+
+impl From<io::Error> for LinesError {
+    fn from(err: io::Error) -> Self {
+        LinesError::Io(err)
+    }
+}
@@ -0,0 +1,275 @@
+//! Conversion of [`OsStr`] and [`Path`] in the encoding of the current locale.
+//!
+//! On Unix, file names, command line arguments and environment variables are byte strings, which
+//! programs interpret in the character set of the `LC_CTYPE` locale category. While most systems
+//! use UTF-8 today, a locale such as `ja_JP.eucJP` makes them EUC-JP. The functions in this module
+//! look up the character set with [`codeset`] on each call and convert with
+//! [`decode`](crate::decode) and [`encode`](crate::encode).
+//!
+//! The locale is read from the `LC_ALL`, `LC_CTYPE` and `LANG` environment variables, without
+//! calling `setlocale`, so the process-wide locale is not changed.
+//!
+//! Byte strings that are not valid in the locale encoding can still be converted without loss
+//! with [`os_str_to_utf8`], which escapes each byte that cannot be decoded as a lone surrogate
+//! like [PEP 383](https://peps.python.org/pep-0383/) does, and converted back with
+//! [`os_str_from_utf8`]. The result is [WTF-8](https://simonsapin.github.io/wtf-8/), which is
+//! valid UTF-8 if nothing had to be escaped.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::ffi::OsStr;
+//! use std::os::unix::ffi::OsStrExt;
+//!
+//! use iconv_native::locale;
+//!
+//! assert_eq!(locale::decode_os_str(OsStr::new("notes.txt"))?, "notes.txt");
+//!
+//! // "さ.txt" in EUC-JP, which is invalid in most other encodings.
+//! let name = OsStr::from_bytes(b"\xa4\xb5.txt");
+//! let converted = locale::os_str_to_utf8(name);
+//! assert_eq!(locale::os_str_from_utf8(&converted)?, name);
+//! # Ok::<(), iconv_native::ConvertError>(())
+//! ```
+
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+use alloc::{string::String, vec::Vec};
+
+use crate::codec::wtf8::{self, Chunk};
+use crate::{ConvertError, Decoder};
+
+/// Bytes that cannot be decoded are escaped to this surrogate plus the byte value.
+const ESCAPE_BASE: u16 = 0xDC00;
+
+/// Returns the character set of the `LC_CTYPE` category of the locale configured by the
+/// environment, such as `UTF-8` or `EUC-JP`.
+///
+/// With glibc and on Apple platforms, the name is queried with `nl_langinfo(CODESET)`, and is
+/// `ANSI_X3.4-1968` or `US-ASCII` for the `C` locale. Elsewhere, it is taken from the part of the
+/// locale name after `.`, and defaults to `UTF-8`.
+pub fn codeset() -> String {
+    sys::codeset().unwrap_or_else(codeset_from_env)
+}
+
+/// Finds the locale name like `setlocale(LC_CTYPE, "")` does.
+fn locale_from_env() -> Option<String> {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+fn codeset_from_env() -> String {
+    let Some(locale) = locale_from_env() else {
+        return "UTF-8".into();
+    };
+    let locale = locale.split('@').next().unwrap_or_default();
+    match locale.split_once('.') {
+        Some((_, codeset)) => codeset.into(),
+        None if locale == "C" || locale == "POSIX" => "US-ASCII".into(),
+        None => "UTF-8".into(),
+    }
+}
+
+#[cfg(any(
+    all(target_env = "gnu", target_os = "linux"),
+    target_os = "hurd",
+    target_vendor = "apple"
+))]
+mod sys {
+    use core::ffi::{c_char, c_int, c_void, CStr};
+    use core::ptr::null_mut;
+
+    use alloc::string::String;
+
+    #[allow(non_camel_case_types)]
+    type locale_t = *mut c_void;
+
+    #[cfg(target_vendor = "apple")]
+    const CODESET: c_int = 0;
+    #[cfg(target_vendor = "apple")]
+    const LC_CTYPE_MASK: c_int = 1 << 1;
+    #[cfg(not(target_vendor = "apple"))]
+    const CODESET: c_int = 14;
+    #[cfg(not(target_vendor = "apple"))]
+    const LC_CTYPE_MASK: c_int = 1 << 0;
+
+    extern "C" {
+        fn newlocale(category_mask: c_int, locale: *const c_char, base: locale_t) -> locale_t;
+        fn freelocale(locale: locale_t);
+        fn nl_langinfo_l(item: c_int, locale: locale_t) -> *const c_char;
+    }
+
+    /// Queries the codeset of a locale object created from the environment, which unlike
+    /// `setlocale` does not affect other threads.
+    pub(super) fn codeset() -> Option<String> {
+        let locale = unsafe { newlocale(LC_CTYPE_MASK, c"".as_ptr(), null_mut()) };
+        if locale.is_null() {
+            return None;
+        }
+        let codeset = unsafe { nl_langinfo_l(CODESET, locale) };
+        let codeset = match codeset.is_null() {
+            true => None,
+            false => unsafe { CStr::from_ptr(codeset) }
+                .to_str()
+                .ok()
+                .filter(|codeset| !codeset.is_empty())
+                .map(String::from),
+        };
+        unsafe { freelocale(locale) };
+        codeset
+    }
+}
+
+#[cfg(not(any(
+    all(target_env = "gnu", target_os = "linux"),
+    target_os = "hurd",
+    target_vendor = "apple"
+)))]
+mod sys {
+    use alloc::string::String;
+
+    pub(super) fn codeset() -> Option<String> {
+        None
+    }
+}
+
+/// Decodes `input` from the locale encoding.
+///
+/// Returns [`ConvertError::InvalidInput`] if `input` is not valid in the locale encoding, and
+/// [`ConvertError::UnknownConversion`] if the locale encoding is not supported.
+pub fn decode_os_str(input: &OsStr) -> Result<String, ConvertError> {
+    crate::decode(input.as_bytes(), &codeset())
+}
+
+/// Encodes `input` to the locale encoding.
+///
+/// Returns [`ConvertError::InvalidInput`] if `input` contains characters that cannot be
+/// represented in the locale encoding, and [`ConvertError::UnknownConversion`] if the locale
+/// encoding is not supported.
+pub fn encode_os_str(input: &str) -> Result<OsString, ConvertError> {
+    crate::encode(input, &codeset()).map(OsString::from_vec)
+}
+
+/// Decodes a path from the locale encoding, like [`decode_os_str`].
+pub fn decode_path(input: &Path) -> Result<String, ConvertError> {
+    decode_os_str(input.as_os_str())
+}
+
+/// Encodes a path to the locale encoding, like [`encode_os_str`].
+pub fn encode_path(input: &str) -> Result<PathBuf, ConvertError> {
+    encode_os_str(input).map(PathBuf::from)
+}
+
+/// Converts `input` from the locale encoding to WTF-8, escaping each byte that cannot be decoded
+/// as the lone surrogate U+DC00 plus the byte value, such as U+DCFF for `0xFF`. The result can be
+/// converted back with [`os_str_from_utf8`].
+///
+/// If the whole input is valid, [`OsStr::to_str`] succeeds on the result. If the locale encoding
+/// is UTF-8 or not supported, `input` is returned unchanged.
+pub fn os_str_to_utf8(input: &OsStr) -> Cow<'_, OsStr> {
+    match to_utf8(input.as_bytes(), &codeset()) {
+        Some(converted) => Cow::Owned(OsString::from_vec(converted)),
+        None => Cow::Borrowed(input),
+    }
+}
+
+/// Converts `input` from WTF-8 to the locale encoding, turning the surrogates escaped by
+/// [`os_str_to_utf8`] back into the bytes they stand for. This reverses [`os_str_to_utf8`].
+///
+/// Returns [`ConvertError::InvalidInput`] if `input` is not valid WTF-8, contains other surrogates,
+/// or contains characters that cannot be represented in the locale encoding. If the locale
+/// encoding is UTF-8 or not supported, `input` is returned unchanged.
+pub fn os_str_from_utf8(input: &OsStr) -> Result<Cow<'_, OsStr>, ConvertError> {
+    Ok(match from_utf8(input.as_bytes(), &codeset())? {
+        Some(converted) => Cow::Owned(OsString::from_vec(converted)),
+        None => Cow::Borrowed(input),
+    })
+}
+
+/// Converts `input` in `encoding` to WTF-8, escaping invalid bytes. Returns `None` if no
+/// conversion is needed or possible.
+fn to_utf8(input: &[u8], encoding: &str) -> Option<Vec<u8>> {
+    if crate::is_utf8(encoding) {
+        return None;
+    }
+    let mut decoder = Decoder::new(encoding).ok()?;
+    let mut converted = Vec::with_capacity(input.len());
+    decoder.decode_all(input, |chunk| match chunk {
+        Ok(decoded) => converted.extend_from_slice(decoded.as_bytes()),
+        Err(invalid) => wtf8::from_utf16(
+            invalid.iter().map(|&byte| ESCAPE_BASE | u16::from(byte)),
+            &mut converted,
+        ),
+    });
+    Some(converted)
+}
+
+/// Converts WTF-8 `input` to `encoding`, unescaping invalid bytes. Returns `None` if no conversion
+/// is needed or possible.
+fn from_utf8(mut input: &[u8], encoding: &str) -> Result<Option<Vec<u8>>, ConvertError> {
+    if crate::is_utf8(encoding) || crate::encode("", encoding).is_err() {
+        return Ok(None);
+    }
+    let mut output = Vec::with_capacity(input.len());
+    while let Some(chunk) = wtf8::next_chunk(&mut input) {
+        match chunk {
+            Chunk::Str(str) => output.extend_from_slice(&crate::encode(str, encoding)?),
+            Chunk::Surrogate(unit) if unit & 0xFF00 == ESCAPE_BASE => output.push(unit as u8),
+            _ => return Err(ConvertError::InvalidInput),
+        }
+    }
+    Ok(Some(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_utf8() {
+        let input = b"\xa4\xb5\xff\xa5\xe6\xa4\xea.txt";
+        let converted = to_utf8(input, "EUC-JP").unwrap();
+        assert_eq!(
+            converted,
+            b"\xe3\x81\x95\xed\xb3\xbf\xe3\x83\xa6\xe3\x82\x8a.txt"
+        );
+        assert_eq!(from_utf8(&converted, "EUC-JP").unwrap().unwrap(), input);
+
+        assert_eq!(to_utf8(b"\xa4", "EUC-JP").unwrap(), b"\xed\xb2\xa4");
+        assert_eq!(to_utf8(b"a.txt", "UTF-8"), None);
+        assert_eq!(to_utf8(b"a.txt", "invalid_encoding"), None);
+    }
+
+    #[test]
+    fn test_to_utf8_roundtrip() {
+        // Invalid in EUC-JP, but "さ" in UTF-8 if kept as they are.
+        let input = b"\xe3\x81\x95\xa4\xb5";
+        let converted = to_utf8(input, "EUC-JP").unwrap();
+        assert!(converted.starts_with(b"\xed\xb3\xa3"));
+        assert_eq!(from_utf8(&converted, "EUC-JP").unwrap().unwrap(), input);
+
+        let input = b"\x82\xa0\xff\x82";
+        let converted = to_utf8(input, "SHIFT_JIS").unwrap();
+        assert_eq!(from_utf8(&converted, "SHIFT_JIS").unwrap().unwrap(), input);
+    }
+
+    #[test]
+    fn test_from_utf8() {
+        let converted = from_utf8("さ.txt".as_bytes(), "EUC-JP").unwrap().unwrap();
+        assert_eq!(converted, b"\xa4\xb5.txt");
+        let result = from_utf8("😀".as_bytes(), "EUC-JP");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = from_utf8(b"\xed\xa0\x80", "EUC-JP");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        assert_eq!(
+            from_utf8(b"\xff", "EUC-JP"),
+            Err(ConvertError::InvalidInput)
+        );
+        assert_eq!(from_utf8(b"a.txt", "UTF-8"), Ok(None));
+    }
+}
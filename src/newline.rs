@@ -0,0 +1,74 @@
+use alloc::string::String;
+
+use crate::options::NewlinePolicy;
+
+fn newline_str(policy: NewlinePolicy) -> Option<&'static str> {
+    match policy {
+        NewlinePolicy::Keep => None,
+        NewlinePolicy::Lf => Some("\n"),
+        NewlinePolicy::CrLf => Some("\r\n"),
+    }
+}
+
+/// Pushes `chars` to `output`, replacing every CRLF, CR, LF and NEL (`U+0085`) with the newline
+/// of `policy`.
+pub(crate) fn push_normalized(
+    output: &mut String,
+    chars: impl Iterator<Item = char>,
+    policy: NewlinePolicy,
+) {
+    let Some(newline) = newline_str(policy) else {
+        output.extend(chars);
+        return;
+    };
+    let mut chars = chars.peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                output.push_str(newline);
+            }
+            '\n' | '\u{85}' => output.push_str(newline),
+            ch => output.push(ch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_normalized_policies() {
+        let testcases = [
+            ("a\r\nb\rc\nd\u{85}e", NewlinePolicy::Lf, "a\nb\nc\nd\ne"),
+            (
+                "a\r\nb\rc\nd\u{85}e",
+                NewlinePolicy::CrLf,
+                "a\r\nb\r\nc\r\nd\r\ne",
+            ),
+            (
+                "a\r\nb\rc\nd\u{85}e",
+                NewlinePolicy::Keep,
+                "a\r\nb\rc\nd\u{85}e",
+            ),
+            ("\r\r\n\n\r", NewlinePolicy::Lf, "\n\n\n\n"),
+            ("\n\r\n", NewlinePolicy::CrLf, "\r\n\r\n"),
+            ("a\nb\n", NewlinePolicy::Lf, "a\nb\n"),
+            ("", NewlinePolicy::CrLf, ""),
+            ("芙宁娜\r", NewlinePolicy::Lf, "芙宁娜\n"),
+        ];
+        for (idx, (input, policy, expected)) in testcases.into_iter().enumerate() {
+            let mut output = String::new();
+            push_normalized(&mut output, input.chars(), policy);
+            assert_eq!(output, expected, "{idx}");
+        }
+    }
+
+    #[test]
+    fn test_push_normalized() {
+        let mut output = String::from(">");
+        push_normalized(&mut output, "a\r\nb\r\rc\u{85}".chars(), NewlinePolicy::Lf);
+        assert_eq!(output, ">a\nb\n\nc\n");
+    }
+}
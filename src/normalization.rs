@@ -0,0 +1,75 @@
+use alloc::string::String;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::newline;
+use crate::options::{NewlinePolicy, NormalizationForm};
+
+/// Pushes `chars` to `output`, normalized to `form` and with line endings replaced according to
+/// `newline_policy`, in a single pass.
+pub(crate) fn push_normalized(
+    output: &mut String,
+    chars: impl Iterator<Item = char>,
+    form: NormalizationForm,
+    newline_policy: NewlinePolicy,
+) {
+    match form {
+        NormalizationForm::Nfc => newline::push_normalized(output, chars.nfc(), newline_policy),
+        NormalizationForm::Nfd => newline::push_normalized(output, chars.nfd(), newline_policy),
+        NormalizationForm::Nfkc => newline::push_normalized(output, chars.nfkc(), newline_policy),
+        NormalizationForm::Nfkd => newline::push_normalized(output, chars.nfkd(), newline_policy),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_normalized() {
+        let testcases = [
+            (
+                "e\u{301}\r\n",
+                NormalizationForm::Nfc,
+                NewlinePolicy::Lf,
+                "\u{e9}\n",
+            ),
+            (
+                "\u{e9}\r",
+                NormalizationForm::Nfd,
+                NewlinePolicy::CrLf,
+                "e\u{301}\r\n",
+            ),
+            (
+                "\u{d55c}",
+                NormalizationForm::Nfd,
+                NewlinePolicy::Keep,
+                "\u{1112}\u{1161}\u{11ab}",
+            ),
+            (
+                "\u{1112}\u{1161}\u{11ab}",
+                NormalizationForm::Nfc,
+                NewlinePolicy::Keep,
+                "\u{d55c}",
+            ),
+            (
+                "\u{ff21}\u{2460}",
+                NormalizationForm::Nfkc,
+                NewlinePolicy::Keep,
+                "A1",
+            ),
+            (
+                "\u{fb01}",
+                NormalizationForm::Nfkd,
+                NewlinePolicy::Keep,
+                "fi",
+            ),
+            ("abc\r", NormalizationForm::Nfc, NewlinePolicy::Lf, "abc\n"),
+        ];
+        for (idx, (input, form, newline_policy, expected)) in testcases.into_iter().enumerate() {
+            let mut output = String::new();
+            push_normalized(&mut output, input.chars(), form, newline_policy);
+            assert_eq!(output, expected, "{idx}");
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use core::str::FromStr;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::codec::ebcdic::Ebcdic;
+use crate::stream::{ChunkedChars, Decoder};
+use crate::utf::UtfEncoding;
+use crate::ConvertError;
+
+/// Controls whether a BOM (Byte Order Mark) is written to the output of
+/// [`convert_with_options`](crate::convert_with_options).
+///
+/// Regardless of the policy, a BOM at the beginning of a UTF input is always
+/// consumed and never converted as `U+FEFF` (ZERO WIDTH NO-BREAK SPACE). A BOM
+/// is only ever written when the target encoding is a UTF encoding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BomPolicy {
+    /// Never write a BOM.
+    Strip,
+    /// Write a BOM if and only if the input starts with one.
+    Preserve,
+    /// Always write a BOM, including for `UTF-8`, `UTF-16LE`, `UTF-16BE`,
+    /// `UTF-32LE` and `UTF-32BE`.
+    AlwaysAdd,
+    /// Write a BOM only if the target encoding is `UTF-16` or `UTF-32`, where
+    /// the byte order would be unknown otherwise.
+    #[default]
+    AddIfAmbiguous,
+}
+
+/// The byte order assumed for `UTF-16` and `UTF-32` input without a BOM.
+///
+/// Implementations disagree on this: glibc and libiconv assume big-endian,
+/// while the Windows and Web implementations assume little-endian. This crate
+/// assumes little-endian everywhere unless told otherwise.
+///
+/// This is a breaking change on iconv platforms: previously, [`decode`] and
+/// [`convert`] treated BOM-less `UTF-16` and `UTF-32` input as big-endian there.
+/// Pass [`DefaultByteOrder::BigEndian`] to
+/// [`decode_with_options`](crate::decode_with_options) or
+/// [`convert_with_options`](crate::convert_with_options) to keep the old
+/// behavior.
+///
+/// [`decode`]: crate::decode
+/// [`convert`]: crate::convert
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DefaultByteOrder {
+    /// Assume little-endian.
+    #[default]
+    LittleEndian,
+    /// Assume big-endian.
+    BigEndian,
+    /// Guess from the position of zero bytes in the input, which works well for
+    /// text mostly consisting of ASCII or Latin characters. Falls back to
+    /// little-endian if the input gives no hint.
+    Guess,
+}
+
+/// Controls how line endings are written to the output of
+/// [`convert_with_options`](crate::convert_with_options).
+///
+/// CRLF, CR, LF and NEL (`U+0085`, `0x15` in most EBCDIC code pages) are
+/// recognized as line endings. Since they are recognized after decoding, line
+/// endings are handled correctly for multi-byte encodings such as UTF-16.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NewlinePolicy {
+    /// Keep line endings as-is.
+    #[default]
+    Keep,
+    /// Convert all line endings to LF.
+    Lf,
+    /// Convert all line endings to CRLF.
+    CrLf,
+}
+
+/// How NL (`0x15`) and LF (`0x25`) are mapped by the built-in EBCDIC
+/// encodings, such as `IBM037` and `IBM1047`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EbcdicNewline {
+    /// Map NL to `U+0085` (NEXT LINE) and LF to `U+000A`, as defined by IBM.
+    #[default]
+    Standard,
+    /// Map NL to `U+000A` and LF to `U+0085`, as expected for text files on
+    /// z/OS UNIX System Services. This is the same as appending `,swaplfnl`
+    /// (or `,swaps`) to the encoding name.
+    Swapped,
+}
+
+/// A Unicode normalization form, as defined in [UAX #15].
+///
+/// [UAX #15]: https://www.unicode.org/reports/tr15/
+#[cfg(feature = "normalization")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NormalizationForm {
+    /// Canonical decomposition followed by canonical composition.
+    Nfc,
+    /// Canonical decomposition.
+    Nfd,
+    /// Compatibility decomposition followed by canonical composition.
+    Nfkc,
+    /// Compatibility decomposition.
+    Nfkd,
+}
+
+/// Options for [`convert_with_options`](crate::convert_with_options),
+/// [`decode_with_options`](crate::decode_with_options) and their lossy
+/// counterparts.
+///
+/// # Examples
+///
+/// ```rust
+/// use iconv_native::{BomPolicy, ConvertOptions};
+///
+/// let options = ConvertOptions::new().bom_policy(BomPolicy::Strip);
+/// assert_eq!(options.get_bom_policy(), BomPolicy::Strip);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ConvertOptions {
+    bom_policy: BomPolicy,
+    default_byte_order: DefaultByteOrder,
+    newline_policy: NewlinePolicy,
+    ebcdic_newline: EbcdicNewline,
+    #[cfg(feature = "normalization")]
+    normalization: Option<NormalizationForm>,
+}
+
+impl ConvertOptions {
+    /// Creates options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the BOM policy. Defaults to [`BomPolicy::AddIfAmbiguous`].
+    pub fn bom_policy(mut self, bom_policy: BomPolicy) -> Self {
+        self.bom_policy = bom_policy;
+        self
+    }
+
+    /// Returns the BOM policy.
+    pub fn get_bom_policy(&self) -> BomPolicy {
+        self.bom_policy
+    }
+
+    /// Sets the byte order assumed for `UTF-16` and `UTF-32` input without a
+    /// BOM. Defaults to [`DefaultByteOrder::LittleEndian`].
+    pub fn default_byte_order(mut self, default_byte_order: DefaultByteOrder) -> Self {
+        self.default_byte_order = default_byte_order;
+        self
+    }
+
+    /// Returns the byte order assumed for `UTF-16` and `UTF-32` input without a
+    /// BOM.
+    pub fn get_default_byte_order(&self) -> DefaultByteOrder {
+        self.default_byte_order
+    }
+
+    /// Sets the newline policy. Defaults to [`NewlinePolicy::Keep`].
+    pub fn newline_policy(mut self, newline_policy: NewlinePolicy) -> Self {
+        self.newline_policy = newline_policy;
+        self
+    }
+
+    /// Returns the newline policy.
+    pub fn get_newline_policy(&self) -> NewlinePolicy {
+        self.newline_policy
+    }
+
+    /// Sets how NL and LF are mapped by EBCDIC encodings. Defaults to
+    /// [`EbcdicNewline::Standard`].
+    pub fn ebcdic_newline(mut self, ebcdic_newline: EbcdicNewline) -> Self {
+        self.ebcdic_newline = ebcdic_newline;
+        self
+    }
+
+    /// Returns how NL and LF are mapped by EBCDIC encodings.
+    pub fn get_ebcdic_newline(&self) -> EbcdicNewline {
+        self.ebcdic_newline
+    }
+
+    /// Sets the Unicode normalization form applied to the decoded text, or
+    /// `None` to leave it as-is. Defaults to `None`.
+    #[cfg(feature = "normalization")]
+    pub fn normalization(mut self, normalization: Option<NormalizationForm>) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Returns the Unicode normalization form applied to the decoded text.
+    #[cfg(feature = "normalization")]
+    pub fn get_normalization(&self) -> Option<NormalizationForm> {
+        self.normalization
+    }
+
+    /// Whether the decoded text needs to be transformed, in which case the
+    /// conversion must go through a decoded [`str`].
+    pub(crate) fn transforms_text(&self) -> bool {
+        #[cfg(feature = "normalization")]
+        if self.normalization.is_some() {
+            return true;
+        }
+        self.newline_policy != NewlinePolicy::Keep
+    }
+
+    /// Decodes `input`, whose BOM has been handled by [`Prepared`], and appends it to `output`
+    /// with the text transformations of the options applied in the same pass as decoding, which
+    /// is done a chunk at a time.
+    pub(crate) fn decode_transformed(
+        &self,
+        input: &[u8],
+        encoding: &str,
+        lossy: bool,
+        output: &mut String,
+    ) -> Result<(), ConvertError> {
+        if let Some(input) = crate::try_borrow_str(input, encoding) {
+            self.push_transformed(output, input.chars());
+            return Ok(());
+        }
+        let decoder = Decoder::new_unchecked_bom(encoding, lossy)?;
+        let mut chars = ChunkedChars::new(input, decoder);
+        self.push_transformed(output, &mut chars);
+        chars.finish()
+    }
+
+    fn push_transformed(&self, output: &mut String, chars: impl Iterator<Item = char>) {
+        #[cfg(feature = "normalization")]
+        if let Some(form) = self.normalization {
+            crate::normalization::push_normalized(output, chars, form, self.newline_policy);
+            return;
+        }
+        crate::newline::push_normalized(output, chars, self.newline_policy);
+    }
+}
+
+/// A conversion with BOMs resolved, so that the underlying implementation only
+/// sees encodings with an explicit byte order and input without a BOM. Labels
+/// of EBCDIC encodings are also resolved to reflect the newline mapping.
+#[derive(Debug)]
+pub(crate) struct Prepared<'i> {
+    pub(crate) input: &'i [u8],
+    from_label: Option<&'static str>,
+    to_label: Option<&'static str>,
+    pub(crate) output_bom: &'static [u8],
+}
+
+impl<'i> Prepared<'i> {
+    pub(crate) fn new(
+        mut input: &'i [u8],
+        from_encoding: &str,
+        to_encoding: &str,
+        options: &ConvertOptions,
+    ) -> Self {
+        let (from_utf, has_input_bom) = match UtfEncoding::from_str(from_encoding) {
+            Ok(utf) if utf.is_ambiguous() => {
+                let input_len = input.len();
+                let byte_order = utf.consume_input_bom(&mut input, options.default_byte_order);
+                (
+                    Some(utf.with_byte_order(byte_order)),
+                    input.len() != input_len,
+                )
+            }
+            Ok(utf) => {
+                let has_input_bom = utf.strip_bom(&mut input);
+                (Some(utf), has_input_bom)
+            }
+            Err(()) => (None, false),
+        };
+        let to_utf = UtfEncoding::from_str(to_encoding).ok();
+        let add_bom = to_utf.is_some_and(|utf| match options.bom_policy {
+            BomPolicy::Strip => false,
+            BomPolicy::Preserve => has_input_bom,
+            BomPolicy::AlwaysAdd => true,
+            BomPolicy::AddIfAmbiguous => utf.is_ambiguous(),
+        });
+        let to_utf = to_utf.map(|utf| utf.resolve(true));
+        Self {
+            input,
+            from_label: from_utf
+                .map(|utf| utf.label())
+                .or_else(|| ebcdic_label(from_encoding, options)),
+            to_label: to_utf
+                .map(|utf| utf.label())
+                .or_else(|| ebcdic_label(to_encoding, options)),
+            output_bom: match to_utf {
+                Some(utf) if add_bom => utf.bom(),
+                _ => &[],
+            },
+        }
+    }
+
+    pub(crate) fn source_encoding<'a>(&self, from_encoding: &'a str) -> &'a str {
+        self.from_label.unwrap_or(from_encoding)
+    }
+
+    pub(crate) fn target_encoding<'a>(&self, to_encoding: &'a str) -> &'a str {
+        self.to_label.unwrap_or(to_encoding)
+    }
+
+    pub(crate) fn output(&self, capacity: usize) -> Vec<u8> {
+        let mut output = Vec::with_capacity(self.output_bom.len() + capacity);
+        output.extend_from_slice(self.output_bom);
+        output
+    }
+}
+
+/// Returns the label of an EBCDIC encoding with the newline mapping of the
+/// options applied, unless already specified by the encoding name.
+fn ebcdic_label(encoding: &str, options: &ConvertOptions) -> Option<&'static str> {
+    match options.ebcdic_newline {
+        EbcdicNewline::Standard => None,
+        EbcdicNewline::Swapped => {
+            Ebcdic::from_encoding(encoding).map(|ebcdic| ebcdic.with_swapped_newlines(true).label())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepared() {
+        let testcases = [
+            (
+                &b"\xff\xfeab"[..],
+                "utf-16",
+                "utf-16",
+                BomPolicy::AddIfAmbiguous,
+                &b"ab"[..],
+                "UTF-16LE",
+                "UTF-16LE",
+                &b"\xff\xfe"[..],
+            ),
+            (
+                b"\xfe\xffab",
+                "utf-16",
+                "utf-8",
+                BomPolicy::Preserve,
+                b"ab",
+                "UTF-16BE",
+                "UTF-8",
+                b"\xef\xbb\xbf",
+            ),
+            (
+                b"\xef\xbb\xbfab",
+                "utf-8",
+                "utf-32be",
+                BomPolicy::Strip,
+                b"ab",
+                "UTF-8",
+                "UTF-32BE",
+                b"",
+            ),
+            (
+                b"ab",
+                "utf-8",
+                "utf-32be",
+                BomPolicy::Preserve,
+                b"ab",
+                "UTF-8",
+                "UTF-32BE",
+                b"",
+            ),
+            (
+                b"\xff\xfeab",
+                "utf-16be",
+                "utf-32",
+                BomPolicy::Strip,
+                b"\xff\xfeab",
+                "UTF-16BE",
+                "UTF-32LE",
+                b"",
+            ),
+            (
+                b"ab",
+                "gb18030",
+                "utf-8",
+                BomPolicy::AlwaysAdd,
+                b"ab",
+                "gb18030",
+                "UTF-8",
+                b"\xef\xbb\xbf",
+            ),
+            (
+                b"\xef\xbb\xbfab",
+                "utf-8",
+                "gb18030",
+                BomPolicy::AlwaysAdd,
+                b"ab",
+                "UTF-8",
+                "gb18030",
+                b"",
+            ),
+            (
+                b"\0a\0b",
+                "utf-16",
+                "utf-16",
+                BomPolicy::Preserve,
+                b"\0a\0b",
+                "UTF-16LE",
+                "UTF-16LE",
+                b"",
+            ),
+        ];
+        for (
+            idx,
+            (input, from, to, bom_policy, expected_input, expected_from, expected_to, expected_bom),
+        ) in testcases.into_iter().enumerate()
+        {
+            let options = ConvertOptions::new().bom_policy(bom_policy);
+            let prepared = Prepared::new(input, from, to, &options);
+            assert_eq!(prepared.input, expected_input, "{idx}");
+            assert_eq!(prepared.source_encoding(from), expected_from, "{idx}");
+            assert_eq!(prepared.target_encoding(to), expected_to, "{idx}");
+            assert_eq!(prepared.output_bom, expected_bom, "{idx}");
+        }
+    }
+}
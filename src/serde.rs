@@ -0,0 +1,148 @@
+//! Helpers for [`String`] fields stored as byte strings in a legacy encoding,
+//! for use with `#[serde(with = "...")]`.
+//!
+//! Each module named after an encoding, such as [`cp932`], decodes a byte
+//! string into a [`String`] when deserializing and encodes it back when
+//! serializing. Invalid input and characters that cannot be represented are
+//! errors. For other encodings, call [`serialize`] and [`deserialize`] from a
+//! module of your own.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Record {
+//!     #[serde(with = "iconv_native::serde::cp932")]
+//!     name: String,
+//! }
+//!
+//! let json = r#"{"name":[130,179,131,134,130,232]}"#;
+//! let record: Record = serde_json::from_str(json)?;
+//! assert_eq!(record.name, "さユり");
+//! assert_eq!(serde_json::to_string(&record)?, json);
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+//!
+//! A module for an encoding not listed here:
+//!
+//! ```rust
+//! mod koi8_u {
+//!     use serde::{Deserializer, Serializer};
+//!
+//!     pub fn serialize<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+//!         iconv_native::serde::serialize(value, "KOI8-U", serializer)
+//!     }
+//!
+//!     pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+//!         iconv_native::serde::deserialize(deserializer, "KOI8-U")
+//!     }
+//! }
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Record {
+//!     #[serde(with = "koi8_u")]
+//!     name: String,
+//! }
+//! ```
+
+use core::fmt;
+
+use alloc::{string::String, vec::Vec};
+
+use ::serde::de::{self, Deserializer, SeqAccess, Visitor};
+use ::serde::ser::{self, Serializer};
+
+/// Serializes `value` as a byte string in `encoding`, like
+/// [`encode`](crate::encode).
+pub fn serialize<S: Serializer>(
+    value: &str,
+    encoding: &str,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let encoded = crate::encode(value, encoding).map_err(ser::Error::custom)?;
+    serializer.serialize_bytes(&encoded)
+}
+
+/// Deserializes a byte string in `encoding` into a [`String`], like
+/// [`decode`](crate::decode).
+///
+/// Formats without a byte string type may provide a sequence of bytes instead,
+/// which is accepted as well.
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    encoding: &str,
+) -> Result<String, D::Error> {
+    deserializer.deserialize_bytes(BytesVisitor { encoding })
+}
+
+struct BytesVisitor<'e> {
+    encoding: &'e str,
+}
+
+impl<'de> Visitor<'de> for BytesVisitor<'_> {
+    type Value = String;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a byte string in {}", self.encoding)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        crate::decode(v, self.encoding).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // Do not trust the size hint too much, since it comes from the input.
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+macro_rules! encoding_modules {
+    ($($name:ident => $label:literal,)*) => {
+        $(
+            #[doc = concat!("Serializes and deserializes [`String`] fields as `", $label, "` byte strings.")]
+            pub mod $name {
+                use alloc::string::String;
+
+                use ::serde::{Deserializer, Serializer};
+
+                #[doc = concat!("Serializes `value` as a byte string in `", $label, "`.")]
+                pub fn serialize<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+                    super::serialize(value, $label, serializer)
+                }
+
+                #[doc = concat!("Deserializes a byte string in `", $label, "` into a [`String`].")]
+                pub fn deserialize<'de, D: Deserializer<'de>>(
+                    deserializer: D,
+                ) -> Result<String, D::Error> {
+                    super::deserialize(deserializer, $label)
+                }
+            }
+        )*
+    };
+}
+
+encoding_modules! {
+    cp932 => "CP932",
+    shift_jis => "SHIFT_JIS",
+    euc_jp => "EUC-JP",
+    iso_2022_jp => "ISO-2022-JP",
+    gbk => "GBK",
+    gb18030 => "GB18030",
+    big5 => "BIG5",
+    euc_kr => "EUC-KR",
+    cp949 => "CP949",
+    cp437 => "CP437",
+    cp850 => "CP850",
+    windows_1251 => "WINDOWS-1251",
+    windows_1252 => "WINDOWS-1252",
+    iso_8859_1 => "ISO-8859-1",
+    koi8_r => "KOI8-R",
+    utf_16le => "UTF-16LE",
+    utf_16be => "UTF-16BE",
+}
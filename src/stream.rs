@@ -0,0 +1,1172 @@
+//! Incremental conversion of input arriving in chunks, such as a large file read piece by piece or
+//! a network stream.
+//!
+//! The underlying implementations only convert complete input, so chunks are split at sequence
+//! boundaries found by decoding, and an incomplete sequence at the end of a chunk is kept until
+//! more input arrives. The state of stateful encodings is carried over from one chunk to the next:
+//! designations and shifts of ISO-2022 encodings and HZ are replayed before the rest of the input,
+//! and base64 runs of UTF-7 are split at unit boundaries.
+
+use core::mem;
+use core::str::FromStr;
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+use crate::codec::{self, gsm0338, utf7};
+use crate::encoding::{is_ascii_compatible, match_encoding_parts_exact};
+use crate::options::DefaultByteOrder;
+use crate::utf::UtfEncoding;
+use crate::{sys, ConvertError, ConvertLossyError};
+
+/// Upper bound of the length of a single sequence, including an escape sequence in front of it.
+const MAX_SEQUENCE_LEN: usize = 8;
+
+/// Input shorter than this at the end of a chunk that cannot be decoded yet is kept until more
+/// input arrives, since it may be completed by the next chunk.
+const MAX_PENDING_LEN: usize = 32;
+
+/// Length of the longest BOM, which must be seen as a whole at the beginning of the input.
+const MAX_BOM_LEN: usize = 4;
+
+/// The smallest number of pending bytes decoded at a time after an invalid sequence. The window
+/// grows while the input is valid, so that searching for the next invalid sequence costs about as
+/// much as the valid input before it.
+const MIN_WINDOW_LEN: usize = 64;
+
+/// The number of input bytes decoded at a time by [`ChunkedChars`].
+const CHUNK_LEN: usize = 8 * 1024;
+
+const SO: u8 = 0x0E;
+const SI: u8 = 0x0F;
+const ESC: u8 = 0x1B;
+
+/// Returns which of G0 to G3 is designated by an ISO-2022 escape sequence with `intermediates`.
+fn designated_set(intermediates: &[u8]) -> Option<usize> {
+    let (is_multibyte, intermediates) = match intermediates.strip_prefix(b"$") {
+        Some(rest) => (true, rest),
+        None => (false, intermediates),
+    };
+    match intermediates {
+        // ESC $ @, ESC $ A and ESC $ B designate G0 without an intermediate.
+        [] if is_multibyte => Some(0),
+        [b'('] => Some(0),
+        [b')' | b'-'] => Some(1),
+        [b'*' | b'.'] => Some(2),
+        [b'+' | b'/'] => Some(3),
+        _ => None,
+    }
+}
+
+/// Designations and shift state of an ISO-2022 encoding.
+#[derive(Debug, Clone, Default)]
+struct Iso2022State {
+    designations: [Vec<u8>; 4],
+    is_shifted_out: bool,
+}
+
+impl Iso2022State {
+    fn update(&mut self, input: &[u8]) {
+        let mut idx = 0;
+        while let Some(&byte) = input.get(idx) {
+            match byte {
+                SO => self.is_shifted_out = true,
+                SI => self.is_shifted_out = false,
+                ESC => {
+                    let intermediates_len = input[idx + 1..]
+                        .iter()
+                        .take_while(|b| (0x20..=0x2F).contains(*b))
+                        .count();
+                    let end = idx + 1 + intermediates_len;
+                    if end >= input.len() {
+                        break;
+                    }
+                    if let Some(set) = designated_set(&input[idx + 1..end]) {
+                        self.designations[set] = input[idx..=end].to_vec();
+                    }
+                    idx = end;
+                }
+                _ => {}
+            }
+            idx += 1;
+        }
+    }
+
+    fn replay(&self, output: &mut Vec<u8>) {
+        for designation in &self.designations {
+            output.extend_from_slice(designation);
+        }
+        if self.is_shifted_out {
+            output.push(SO);
+        }
+    }
+}
+
+/// How the input is split into chunks that can be decoded on their own.
+#[derive(Debug, Clone)]
+enum Framing {
+    /// UTF-8, whose sequence boundaries are found without decoding.
+    Utf8,
+    Stateless,
+    Iso2022(Iso2022State),
+    /// HZ, which switches to GB2312 with `~{` and back with `~}`.
+    Hz {
+        is_gb: bool,
+    },
+    Utf7 {
+        variant: utf7::Variant,
+        in_run: bool,
+    },
+}
+
+impl Framing {
+    fn from_encoding(encoding: &str) -> Self {
+        if crate::is_utf8(encoding) {
+            return Self::Utf8;
+        }
+        if let Some(variant) = utf7::Variant::from_encoding(encoding) {
+            return Self::Utf7 {
+                variant,
+                in_run: false,
+            };
+        }
+        let upper = encoding.to_ascii_uppercase();
+        if upper.contains("2022") {
+            return Self::Iso2022(Iso2022State::default());
+        }
+        if match_encoding_parts_exact(encoding, &["hz"])
+            || match_encoding_parts_exact(encoding, &["hz", "gb", "2312"])
+        {
+            return Self::Hz { is_gb: false };
+        }
+        Self::Stateless
+    }
+
+    /// Writes the bytes restoring the current state to `output`.
+    fn replay(&self, output: &mut Vec<u8>) {
+        match self {
+            Self::Iso2022(state) => state.replay(output),
+            Self::Hz { is_gb: true } => output.extend_from_slice(b"~{"),
+            Self::Utf7 {
+                variant,
+                in_run: true,
+            } => output.push(match variant {
+                utf7::Variant::Utf7 => b'+',
+                utf7::Variant::Imap => b'&',
+            }),
+            _ => {}
+        }
+    }
+
+    /// Updates the state after `input`, which starts with the replayed state, has been decoded.
+    fn update(&mut self, input: &[u8], in_run: bool) {
+        match self {
+            Self::Iso2022(state) => state.update(input),
+            Self::Hz { is_gb } => {
+                let mut idx = 0;
+                while idx < input.len() {
+                    match input[idx..] {
+                        [b'~', b'{', ..] => *is_gb = true,
+                        [b'~', b'}', ..] => *is_gb = false,
+                        [b'~', ..] => {}
+                        // Characters of GB2312 take two bytes.
+                        _ if *is_gb => {}
+                        _ => {
+                            idx += 1;
+                            continue;
+                        }
+                    }
+                    idx += 2;
+                }
+            }
+            Self::Utf7 { in_run: state, .. } => *state = in_run,
+            Self::Utf8 | Self::Stateless => {}
+        }
+    }
+
+    /// Drops the state after an invalid sequence, so that decoding resumes from the initial state.
+    fn resynchronize(&mut self) {
+        if let Self::Utf7 { in_run, .. } = self {
+            *in_run = false;
+        }
+    }
+}
+
+/// A prefix of the input that can be decoded on its own.
+#[derive(Debug)]
+struct Split {
+    len: usize,
+    /// Whether the prefix ends in the middle of a base64 run of UTF-7.
+    in_run: bool,
+    decoded: String,
+}
+
+/// An incremental decoder, converting a byte sequence arriving in chunks to UTF-8.
+///
+/// Feed chunks with [`decode`](Self::decode) and call [`finish`](Self::finish) at the end of the
+/// input. A sequence split across chunks is kept until the rest of it arrives, so the result is
+/// the same as [`decode`](crate::decode) on the whole input, and only a few bytes are buffered
+/// between calls.
+///
+/// # BOM handling
+///
+/// A BOM at the beginning of a UTF input is consumed and determines the byte order of `UTF-16`
+/// and `UTF-32`, like [`decode`](crate::decode). BOM-less `UTF-16` and `UTF-32` input is treated as
+/// little-endian.
+///
+/// # Examples
+///
+/// ```rust
+/// use iconv_native::Decoder;
+///
+/// let mut decoder = Decoder::new("gb18030")?;
+/// let mut output = String::new();
+/// // "芙宁娜" split in the middle of the second character
+/// decoder.decode(b"\xdc\xbd\xc4", &mut output)?;
+/// assert_eq!(output, "芙");
+/// decoder.decode(b"\xfe\xc4\xc8", &mut output)?;
+/// decoder.finish(&mut output)?;
+/// assert_eq!(output, "芙宁娜");
+/// # Ok::<(), iconv_native::ConvertError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    source_encoding: String,
+    /// The encoding passed to the underlying implementation, with the byte order resolved once
+    /// the BOM has been consumed.
+    encoding: String,
+    lossy: bool,
+    framing: Framing,
+    is_started: bool,
+    pending: Vec<u8>,
+    /// Bytes of packed GSM 03.38 not unpacked yet. The septets are decoded as unpacked GSM 03.38.
+    packed: Option<Vec<u8>>,
+    position: u64,
+    /// The number of pending bytes decoded at a time, see [`MIN_WINDOW_LEN`].
+    window_len: usize,
+}
+
+impl Decoder {
+    fn init(encoding: &str, lossy: bool) -> Self {
+        let packed = gsm0338::parse(encoding) == Some(true);
+        Self {
+            source_encoding: encoding.into(),
+            encoding: if packed { "GSM0338" } else { encoding }.into(),
+            lossy,
+            framing: Framing::from_encoding(encoding),
+            is_started: false,
+            pending: Vec::new(),
+            packed: packed.then(Vec::new),
+            position: 0,
+            window_len: usize::MAX,
+        }
+    }
+
+    /// Creates a decoder for `encoding`, which fails on invalid input like
+    /// [`decode`](crate::decode).
+    ///
+    /// Returns [`ConvertError::UnknownConversion`] if `encoding` is not supported.
+    pub fn new(encoding: &str) -> Result<Self, ConvertError> {
+        crate::decode([], encoding)?;
+        Ok(Self::init(encoding, false))
+    }
+
+    /// Creates a decoder for `encoding`, which replaces invalid input like
+    /// [`decode_lossy`](crate::decode_lossy).
+    ///
+    /// Returns [`ConvertLossyError::UnknownConversion`] if `encoding` is not supported.
+    pub fn new_lossy(encoding: &str) -> Result<Self, ConvertLossyError> {
+        crate::decode_lossy([], encoding)?;
+        Ok(Self::init(encoding, true))
+    }
+
+    /// Creates a decoder for input whose BOM has been handled by
+    /// [`Prepared`](crate::options::Prepared), so that `encoding` has an explicit byte order.
+    pub(crate) fn new_unchecked_bom(encoding: &str, lossy: bool) -> Result<Self, ConvertError> {
+        crate::decode_unchecked_bom(&[], encoding)?;
+        let mut decoder = Self::init(encoding, lossy);
+        decoder.is_started = true;
+        Ok(decoder)
+    }
+
+    /// Returns the number of input bytes decoded so far.
+    ///
+    /// After [`decode`](Self::decode) or [`finish`](Self::finish) returned
+    /// [`ConvertError::InvalidInput`], this is the offset of the first invalid sequence.
+    pub fn position(&self) -> u64 {
+        match self.packed {
+            // Septets are counted while decoding.
+            Some(_) => self.position * 7 / 8,
+            None => self.position,
+        }
+    }
+
+    /// Decodes a chunk of input, appending the result to `output`.
+    ///
+    /// If the input is invalid, [`ConvertError::InvalidInput`] is returned after the valid part
+    /// before the invalid sequence has been appended to `output`. An incomplete sequence at the end
+    /// of the chunk is not an error, unless the input ends there.
+    pub fn decode(&mut self, input: &[u8], output: &mut String) -> Result<(), ConvertError> {
+        match &mut self.packed {
+            Some(packed) => packed.extend_from_slice(input),
+            None => self.pending.extend_from_slice(input),
+        }
+        self.process(false, output)
+    }
+
+    /// Decodes the rest of the input, appending the result to `output`, and resets the decoder for
+    /// a new input.
+    ///
+    /// Returns [`ConvertError::InvalidInput`] if the input ends with an incomplete sequence.
+    pub fn finish(&mut self, output: &mut String) -> Result<(), ConvertError> {
+        let result = self.process(true, output);
+        let position = self.position;
+        *self = Self::init(&self.source_encoding, self.lossy);
+        if result.is_err() {
+            self.position = position;
+        }
+        result
+    }
+
+    /// Decodes the whole `input` in one pass, passing the decoded text and each invalid sequence
+    /// to `push` in order, and resets the decoder for a new input.
+    #[cfg(all(feature = "std", unix))]
+    pub(crate) fn decode_all(&mut self, input: &[u8], mut push: impl FnMut(Result<&str, &[u8]>)) {
+        let mut output = String::new();
+        match &mut self.packed {
+            Some(packed) => packed.extend_from_slice(input),
+            None => self.pending.extend_from_slice(input),
+        }
+        while self.process(true, &mut output).is_err() {
+            push(Ok(&output));
+            output.clear();
+            // `process` stops at the invalid sequence, which is skipped to decode the rest.
+            let invalid_len = self.invalid_len(&self.pending);
+            push(Err(&self.pending[..invalid_len]));
+            self.pending.drain(..invalid_len);
+            self.framing.resynchronize();
+            self.position += invalid_len as u64;
+            self.window_len = MIN_WINDOW_LEN;
+        }
+        push(Ok(&output));
+        *self = Self::init(&self.source_encoding, self.lossy);
+    }
+
+    /// Consumes the BOM. Returns `false` if more input is needed to tell whether there is one.
+    fn start(&mut self, last: bool) -> bool {
+        if self.is_started {
+            return true;
+        }
+        let Ok(utf) = UtfEncoding::from_str(&self.encoding) else {
+            self.is_started = true;
+            return true;
+        };
+        if self.pending.len() < MAX_BOM_LEN && !last {
+            return false;
+        }
+        let mut input = &self.pending[..];
+        let utf = if utf.is_ambiguous() {
+            utf.with_byte_order(utf.consume_input_bom(&mut input, DefaultByteOrder::default()))
+        } else {
+            utf.strip_bom(&mut input);
+            utf
+        };
+        let bom_len = self.pending.len() - input.len();
+        self.pending.drain(..bom_len);
+        self.position += bom_len as u64;
+        self.encoding = utf.label().into();
+        self.is_started = true;
+        true
+    }
+
+    fn try_decode(&self, input: &[u8]) -> Option<String> {
+        crate::decode_unchecked_bom(input, &self.encoding)
+            .ok()
+            .map(|decoded| decoded.into_owned())
+    }
+
+    fn try_split(&self, work: &[u8], len: usize) -> Option<Split> {
+        Some(Split {
+            len,
+            in_run: false,
+            decoded: self.try_decode(&work[..len])?,
+        })
+    }
+
+    /// Finds the longest prefix of `work` that can be decoded on its own. `work` starts with the
+    /// replayed state of `replay_len` bytes.
+    fn split(&self, work: &[u8], replay_len: usize, last: bool) -> Option<Split> {
+        let mut end = work.len();
+        match self.framing {
+            Framing::Utf8 => {
+                let len = match core::str::from_utf8(work) {
+                    Ok(_) => work.len(),
+                    Err(err) => err.valid_up_to(),
+                };
+                // Safety: checked by `from_utf8` above.
+                let decoded = unsafe { core::str::from_utf8_unchecked(&work[..len]) };
+                return Some(Split {
+                    len,
+                    in_run: false,
+                    decoded: decoded.into(),
+                });
+            }
+            Framing::Utf7 { variant, .. } if !last => {
+                let (len, in_run) = utf7::split_incomplete(work, variant);
+                if in_run {
+                    let mut prefix = work[..len].to_vec();
+                    prefix.push(b'-');
+                    if let Some(decoded) = self.try_decode(&prefix) {
+                        return Some(Split {
+                            len,
+                            in_run,
+                            decoded,
+                        });
+                    }
+                }
+                end = len;
+            }
+            _ => {}
+        }
+        if let Some((decoded, len)) = codec::decode_prefix(&work[..end], &self.encoding) {
+            return Some(Split {
+                len,
+                in_run: false,
+                decoded,
+            });
+        }
+        if let Some(split) = self.try_split(work, end) {
+            return Some(split);
+        }
+        let window_start = end.saturating_sub(MAX_SEQUENCE_LEN).max(replay_len);
+        if let Some(split) = (window_start..end)
+            .rev()
+            .find_map(|len| self.try_split(work, len))
+        {
+            return Some(split);
+        }
+        // There is an invalid sequence before the end. A prefix counts as valid if it can be
+        // extended into a valid one, since it may end in the middle of a sequence.
+        let completes = |len: usize| {
+            (len..=end.min(len + MAX_SEQUENCE_LEN))
+                .any(|len| self.try_decode(&work[..len]).is_some())
+        };
+        let (mut low, mut high) = (replay_len, window_start);
+        if !completes(low) {
+            return None;
+        }
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            if completes(mid) {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        (low..=end.min(low + MAX_SEQUENCE_LEN))
+            .rev()
+            .find_map(|len| self.try_split(work, len))
+    }
+
+    /// Returns the length of the invalid sequence at the beginning of `input`, which is a whole
+    /// code unit for UTF-16 and UTF-32, so that decoding resumes on a unit boundary.
+    fn invalid_len(&self, input: &[u8]) -> usize {
+        match (&self.framing, core::str::from_utf8(input)) {
+            (Framing::Utf8, Err(err)) => err.error_len().unwrap_or(input.len()),
+            _ => UtfEncoding::from_str(&self.encoding)
+                .map_or(1, |utf| utf.code_unit_len())
+                .min(input.len()),
+        }
+    }
+
+    fn process(&mut self, last: bool, output: &mut String) -> Result<(), ConvertError> {
+        if let Some(packed) = &mut self.packed {
+            if last {
+                self.pending.extend(gsm0338::unpack(packed));
+                packed.clear();
+            } else {
+                // Keep the last group, which may end with the padding.
+                let len = packed.len().saturating_sub(1) / 7 * 7;
+                self.pending.extend(gsm0338::unpack_septets(&packed[..len]));
+                packed.drain(..len);
+            }
+        }
+        if !self.start(last) {
+            return Ok(());
+        }
+        let mut work = Vec::new();
+        // Bytes of `pending` consumed so far, drained at the end instead of after each split.
+        let mut start = 0;
+        let result = loop {
+            let pending = &self.pending[start..];
+            if pending.is_empty() {
+                break Ok(());
+            }
+            let window_len = pending.len().min(self.window_len);
+            work.clear();
+            self.framing.replay(&mut work);
+            let replay_len = work.len();
+            work.extend_from_slice(&pending[..window_len]);
+
+            let is_whole = window_len == pending.len();
+            if let Some(split) = self.split(&work, replay_len, last && is_whole) {
+                if split.len > replay_len {
+                    let consumed = split.len - replay_len;
+                    output.push_str(&split.decoded);
+                    self.framing.update(&work[..split.len], split.in_run);
+                    start += consumed;
+                    self.position += consumed as u64;
+                    self.window_len = match consumed == window_len {
+                        true => self.window_len.saturating_mul(2),
+                        false => (consumed * 2).max(MIN_WINDOW_LEN),
+                    };
+                    continue;
+                }
+            }
+            if !last && pending.len() < MAX_PENDING_LEN {
+                break Ok(());
+            }
+            if !self.lossy {
+                break Err(ConvertError::InvalidInput);
+            }
+            let invalid_len = self.invalid_len(pending);
+            work.truncate(replay_len);
+            work.extend_from_slice(&pending[..invalid_len]);
+            if let Ok(replaced) = crate::decode_lossy_unchecked_bom(&work, &self.encoding) {
+                output.push_str(&replaced);
+            }
+            self.framing.resynchronize();
+            start += invalid_len;
+            self.position += invalid_len as u64;
+            self.window_len = MIN_WINDOW_LEN;
+        };
+        self.pending.drain(..start);
+        result
+    }
+}
+
+/// The characters of a byte slice, decoded [`CHUNK_LEN`] bytes at a time, so that text
+/// transformations can be applied while decoding instead of on the whole decoded text.
+///
+/// The iteration ends at the first error, which is returned by [`finish`](Self::finish).
+pub(crate) struct ChunkedChars<'i> {
+    input: &'i [u8],
+    decoder: Decoder,
+    chunk: String,
+    /// Offset of the next character in `chunk`.
+    chunk_offset: usize,
+    is_finished: bool,
+    result: Result<(), ConvertError>,
+}
+
+impl<'i> ChunkedChars<'i> {
+    pub(crate) fn new(input: &'i [u8], decoder: Decoder) -> Self {
+        Self {
+            input,
+            decoder,
+            chunk: String::with_capacity(CHUNK_LEN),
+            chunk_offset: 0,
+            is_finished: false,
+            result: Ok(()),
+        }
+    }
+
+    /// Returns the error that ended the iteration, if any.
+    pub(crate) fn finish(self) -> Result<(), ConvertError> {
+        self.result
+    }
+
+    /// Decodes the next chunk once the current one is exhausted. Returns `false` at the end.
+    fn fill(&mut self) -> bool {
+        while self.chunk_offset == self.chunk.len() {
+            if self.is_finished || self.result.is_err() {
+                return false;
+            }
+            self.chunk.clear();
+            self.chunk_offset = 0;
+            if self.input.is_empty() {
+                self.result = self.decoder.finish(&mut self.chunk);
+                self.is_finished = true;
+            } else {
+                let (chunk, rest) = self.input.split_at(self.input.len().min(CHUNK_LEN));
+                self.result = self.decoder.decode(chunk, &mut self.chunk);
+                self.input = rest;
+            }
+        }
+        true
+    }
+}
+
+impl Iterator for ChunkedChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if !self.fill() {
+            return None;
+        }
+        let ch = self.chunk[self.chunk_offset..].chars().next()?;
+        self.chunk_offset += ch.len_utf8();
+        Some(ch)
+    }
+}
+
+/// An iterator over the characters of a byte slice in any supported encoding, with their byte
+/// offsets, created with [`decode_chars`](crate::decode_chars) or
+/// [`decode_chars_lossy`](crate::decode_chars_lossy).
+///
+/// Characters are decoded one sequence at a time as the iterator advances, so stopping early
+/// leaves the rest of the input untouched. Each character comes with the offset of the sequence it
+/// was decoded from, including any escape sequence or shift in front of it. Characters decoded
+/// from one sequence, such as a base64 group of UTF-7, share its offset. For packed GSM 03.38,
+/// offsets are those of the bytes holding the first bit of each septet.
+///
+/// After [`ConvertError::InvalidInput`], the iteration ends, and [`position`](Self::position)
+/// returns the offset of the invalid sequence.
+#[derive(Debug)]
+pub struct DecodeChars<'i> {
+    input: Cow<'i, [u8]>,
+    decoder: Decoder,
+    /// A conversion kept open to decode one sequence per call, if the underlying implementation
+    /// supports it. Otherwise, the length of each sequence is searched for with `decoder`.
+    sequences: Option<sys::SequenceDecoder>,
+    /// Offset of the next byte of `input` to decode.
+    offset: usize,
+    /// Characters decoded from the last sequence and not returned yet, in reverse order.
+    decoded: Vec<char>,
+    decoded_offset: usize,
+    /// Buffer for the replayed state and the candidate sequence.
+    work: Vec<u8>,
+    is_failed: bool,
+}
+
+impl Clone for DecodeChars<'_> {
+    fn clone(&self) -> Self {
+        Self {
+            input: self.input.clone(),
+            decoder: self.decoder.clone(),
+            sequences: self.sequences.as_ref().and_then(|_| self.open_sequences()),
+            offset: self.offset,
+            decoded: self.decoded.clone(),
+            decoded_offset: self.decoded_offset,
+            work: Vec::new(),
+            is_failed: self.is_failed,
+        }
+    }
+}
+
+impl<'i> DecodeChars<'i> {
+    pub(crate) fn new(input: &'i [u8], decoder: Decoder) -> Self {
+        let input = match decoder.packed {
+            Some(_) => Cow::Owned(gsm0338::unpack(input)),
+            None => Cow::Borrowed(input),
+        };
+        Self {
+            input,
+            decoder,
+            sequences: None,
+            offset: 0,
+            decoded: Vec::new(),
+            decoded_offset: 0,
+            work: Vec::new(),
+            is_failed: false,
+        }
+    }
+
+    /// Returns the number of input bytes decoded so far.
+    ///
+    /// After [`ConvertError::InvalidInput`] has been returned, this is the offset of the invalid
+    /// sequence.
+    pub fn position(&self) -> usize {
+        self.to_byte_offset(self.offset)
+    }
+
+    fn to_byte_offset(&self, offset: usize) -> usize {
+        match self.decoder.packed {
+            Some(_) => offset * 7 / 8,
+            None => offset,
+        }
+    }
+
+    /// Consumes the BOM at the beginning of the input.
+    fn start(&mut self) {
+        let decoder = &mut self.decoder;
+        decoder
+            .pending
+            .extend_from_slice(&self.input[..self.input.len().min(MAX_BOM_LEN)]);
+        let len = decoder.pending.len();
+        decoder.start(true);
+        self.offset = len - decoder.pending.len();
+        decoder.pending.clear();
+        if !matches!(self.decoder.framing, Framing::Utf8) {
+            self.sequences = self.open_sequences();
+        }
+    }
+
+    /// Opens a conversion to decode one sequence at a time, in the state of `decoder`.
+    fn open_sequences(&self) -> Option<sys::SequenceDecoder> {
+        let mut sequences = codec::sequence_decoder(&self.decoder.encoding)?;
+        let mut replay = Vec::new();
+        self.decoder.framing.replay(&mut replay);
+        if !replay.is_empty() {
+            sequences.decode_next(&replay, &mut Vec::new())?;
+        }
+        Some(sequences)
+    }
+
+    /// Decodes the first sequence of `rest` with `sequences`, appending its characters to
+    /// `decoded`. Returns the length of the sequence.
+    fn decode_sequence(&mut self, rest_offset: usize) -> Option<usize> {
+        let rest = &self.input[rest_offset..];
+        // The conversion may decode ahead as much input as it is given, so it is given no more
+        // than the longest sequence.
+        let head = &rest[..rest.len().min(MAX_PENDING_LEN)];
+        let len = self
+            .sequences
+            .as_mut()?
+            .decode_next(head, &mut self.decoded)?;
+        self.decoder.framing.update(&head[..len], false);
+        Some(len)
+    }
+
+    /// Finds the length of the first sequence of `rest` by decoding prefixes of increasing length,
+    /// appending its characters to `decoded`.
+    fn search_sequence(&mut self, rest_offset: usize) -> Option<usize> {
+        let rest = &self.input[rest_offset..];
+        let decoder = &mut self.decoder;
+        let work = &mut self.work;
+        (1..=rest.len().min(MAX_PENDING_LEN)).find_map(|len| {
+            work.clear();
+            decoder.framing.replay(work);
+            let replay_len = work.len();
+            work.extend_from_slice(&rest[..len]);
+            let last = len == rest.len();
+            let (split_len, in_run) = match decoder.framing {
+                Framing::Utf7 { variant, .. } if !last => utf7::split_incomplete(work, variant),
+                _ => (work.len(), false),
+            };
+            if split_len <= replay_len {
+                return None;
+            }
+            work.truncate(split_len);
+            if in_run {
+                work.push(b'-');
+            }
+            let decoded = decoder.try_decode(work)?;
+            work.truncate(split_len);
+            // An escape sequence alone is valid, but is kept with the character after it.
+            if decoded.is_empty() && !last {
+                return None;
+            }
+            decoder.framing.update(work, in_run);
+            self.decoded.extend(decoded.chars());
+            Some(split_len - replay_len)
+        })
+    }
+
+    /// Decodes the next sequence into `decoded`, which stays empty if the sequence holds no
+    /// characters. Returns `None` at the end of the input.
+    fn decode_next(&mut self) -> Option<Result<(), ConvertError>> {
+        if !self.decoder.is_started {
+            self.start();
+        }
+        let rest = &self.input[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        let found = match self.decoder.framing {
+            Framing::Utf8 => {
+                let head = &rest[..rest.len().min(4)];
+                let valid = match core::str::from_utf8(head) {
+                    Ok(valid) => valid,
+                    // Safety: checked by `from_utf8` above.
+                    Err(err) => unsafe {
+                        core::str::from_utf8_unchecked(&head[..err.valid_up_to()])
+                    },
+                };
+                valid.chars().next().map(|char| {
+                    self.decoded.push(char);
+                    char.len_utf8()
+                })
+            }
+            Framing::Stateless
+                if rest[0].is_ascii() && is_ascii_compatible(&self.decoder.encoding) =>
+            {
+                self.decoded.push(char::from(rest[0]));
+                Some(1)
+            }
+            _ if self.sequences.is_some() => self.decode_sequence(self.offset),
+            _ => self.search_sequence(self.offset),
+        };
+        self.decoded_offset = self.offset;
+        if let Some(len) = found {
+            self.decoded.reverse();
+            self.offset += len;
+            return Some(Ok(()));
+        }
+        if !self.decoder.lossy {
+            self.is_failed = true;
+            return Some(Err(ConvertError::InvalidInput));
+        }
+        let rest = &self.input[self.offset..];
+        let invalid_len = self.decoder.invalid_len(rest);
+        self.work.clear();
+        self.decoder.framing.replay(&mut self.work);
+        self.work.extend_from_slice(&rest[..invalid_len]);
+        if let Ok(replaced) = crate::decode_lossy_unchecked_bom(&self.work, &self.decoder.encoding)
+        {
+            self.decoded.extend(replaced.chars().rev());
+        }
+        self.decoder.framing.resynchronize();
+        if self.sequences.is_some() {
+            // The conversion may have consumed part of the invalid sequence.
+            self.sequences = self.open_sequences();
+        }
+        self.offset += invalid_len;
+        Some(Ok(()))
+    }
+}
+
+impl Iterator for DecodeChars<'_> {
+    type Item = Result<(usize, char), ConvertError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(char) = self.decoded.pop() {
+                return Some(Ok((self.to_byte_offset(self.decoded_offset), char)));
+            }
+            if self.is_failed {
+                return None;
+            }
+            if let Err(err) = self.decode_next()? {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+/// An incremental encoder, converting text arriving in chunks to a byte sequence.
+///
+/// Feed chunks with [`encode`](Self::encode) and call [`finish`](Self::finish) at the end of the
+/// text. Each chunk is encoded as soon as it is fed, except for packed GSM 03.38, where up to 8
+/// septets are kept to be packed together.
+///
+/// # BOM handling
+///
+/// If the encoding is `UTF-16` or `UTF-32`, a BOM is written before the first output, followed
+/// by little-endian text. No BOM is written for other encodings.
+///
+/// Stateful encodings such as ISO-2022-JP return to the initial state at the end of each chunk,
+/// so the output may contain more escape sequences than the output of [`encode`](crate::encode),
+/// while being decoded to the same text.
+///
+/// # Examples
+///
+/// ```rust
+/// use iconv_native::Encoder;
+///
+/// let mut encoder = Encoder::new("utf-16")?;
+/// let mut output = vec![];
+/// encoder.encode("芙宁", &mut output)?;
+/// encoder.encode("娜", &mut output)?;
+/// encoder.finish(&mut output)?;
+/// assert_eq!(output, b"\xff\xfe\x99\x82\x81\x5b\x1c\x5a");
+/// # Ok::<(), iconv_native::ConvertError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    target_encoding: String,
+    encoding: String,
+    lossy: bool,
+    /// The BOM to be written before the first output.
+    bom: &'static [u8],
+    /// Septets of packed GSM 03.38 not packed yet.
+    packed: Option<Vec<u8>>,
+}
+
+impl Encoder {
+    fn init(encoding: &str, lossy: bool) -> Self {
+        let packed = gsm0338::parse(encoding) == Some(true);
+        let (label, bom) = match UtfEncoding::from_str(encoding) {
+            Ok(utf) if utf.is_ambiguous() => {
+                let utf = utf.resolve(true);
+                (utf.label(), utf.bom())
+            }
+            _ if packed => ("GSM0338", &[][..]),
+            _ => (encoding, &[][..]),
+        };
+        Self {
+            target_encoding: encoding.into(),
+            encoding: label.into(),
+            lossy,
+            bom,
+            packed: packed.then(Vec::new),
+        }
+    }
+
+    /// Creates an encoder for `encoding`, which fails on unrepresentable characters like
+    /// [`encode`](crate::encode).
+    ///
+    /// Returns [`ConvertError::UnknownConversion`] if `encoding` is not supported.
+    pub fn new(encoding: &str) -> Result<Self, ConvertError> {
+        crate::codec::encode("", encoding)?;
+        Ok(Self::init(encoding, false))
+    }
+
+    /// Creates an encoder for `encoding`, which replaces unrepresentable characters like
+    /// [`encode_lossy`](crate::encode_lossy).
+    ///
+    /// Returns [`ConvertLossyError::UnknownConversion`] if `encoding` is not supported.
+    pub fn new_lossy(encoding: &str) -> Result<Self, ConvertLossyError> {
+        crate::codec::encode_lossy("", encoding)?;
+        Ok(Self::init(encoding, true))
+    }
+
+    /// Encodes a chunk of text, appending the result to `output`.
+    ///
+    /// If the chunk contains characters that cannot be represented, [`ConvertError::InvalidInput`]
+    /// is returned and nothing is appended.
+    pub fn encode(&mut self, input: &str, output: &mut Vec<u8>) -> Result<(), ConvertError> {
+        if input.is_empty() {
+            return Ok(());
+        }
+        let encoded = if self.lossy {
+            crate::encode_lossy(input, &self.encoding)?
+        } else {
+            crate::encode(input, &self.encoding)?
+        };
+        output.extend_from_slice(mem::take(&mut self.bom));
+        match &mut self.packed {
+            Some(septets) => {
+                septets.extend_from_slice(&encoded);
+                // Keep the last group, which may need the padding.
+                let len = septets.len().saturating_sub(1) / 8 * 8;
+                output.extend_from_slice(&gsm0338::pack_septets(&septets[..len]));
+                septets.drain(..len);
+            }
+            None => output.extend_from_slice(&encoded),
+        }
+        Ok(())
+    }
+
+    /// Writes the rest of the output to `output`, and resets the encoder for a new text.
+    pub fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), ConvertError> {
+        if let Some(septets) = self.packed.take() {
+            output.extend_from_slice(&gsm0338::pack(septets));
+        }
+        *self = Self::init(&self.target_encoding, self.lossy);
+        Ok(())
+    }
+}
+
+/// An incremental converter between two encodings, combining a [`Decoder`] and an [`Encoder`].
+///
+/// # Examples
+///
+/// ```rust
+/// use iconv_native::Converter;
+///
+/// let mut converter = Converter::new("shift_jis", "utf-16le")?;
+/// let mut output = vec![];
+/// for chunk in [&b"\x82\xb3\x83"[..], b"\x86\x82", b"\xe8"] {
+///     converter.convert(chunk, &mut output)?;
+/// }
+/// converter.finish(&mut output)?;
+/// assert_eq!(output, b"\x55\x30\xe6\x30\x8a\x30");
+/// # Ok::<(), iconv_native::ConvertError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Converter {
+    decoder: Decoder,
+    encoder: Encoder,
+    decoded: String,
+}
+
+impl Converter {
+    /// Creates a converter from `from_encoding` to `to_encoding`, which fails on invalid input
+    /// and unrepresentable characters like [`convert`](crate::convert).
+    ///
+    /// Returns [`ConvertError::UnknownConversion`] if either encoding is not supported.
+    pub fn new(from_encoding: &str, to_encoding: &str) -> Result<Self, ConvertError> {
+        Ok(Self {
+            decoder: Decoder::new(from_encoding)?,
+            encoder: Encoder::new(to_encoding)?,
+            decoded: String::new(),
+        })
+    }
+
+    /// Creates a converter from `from_encoding` to `to_encoding`, which replaces invalid input and
+    /// unrepresentable characters like [`convert_lossy`](crate::convert_lossy).
+    ///
+    /// Returns [`ConvertLossyError::UnknownConversion`] if either encoding is not supported.
+    pub fn new_lossy(from_encoding: &str, to_encoding: &str) -> Result<Self, ConvertLossyError> {
+        Ok(Self {
+            decoder: Decoder::new_lossy(from_encoding)?,
+            encoder: Encoder::new_lossy(to_encoding)?,
+            decoded: String::new(),
+        })
+    }
+
+    /// Returns the number of input bytes converted so far. See [`Decoder::position`].
+    pub fn position(&self) -> u64 {
+        self.decoder.position()
+    }
+
+    /// Converts a chunk of input, appending the result to `output`.
+    ///
+    /// On [`ConvertError::InvalidInput`], the valid part before the invalid sequence has been
+    /// appended to `output`, unless it contains characters that cannot be represented.
+    pub fn convert(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), ConvertError> {
+        self.decoded.clear();
+        let result = self.decoder.decode(input, &mut self.decoded);
+        self.encoder.encode(&self.decoded, output)?;
+        result
+    }
+
+    /// Converts the rest of the input, appending the result to `output`, and resets the converter
+    /// for a new input.
+    pub fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), ConvertError> {
+        self.decoded.clear();
+        let result = self.decoder.finish(&mut self.decoded);
+        self.encoder.encode(&self.decoded, output)?;
+        self.encoder.finish(output)?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_chunks(input: &[u8], encoding: &str, chunk_len: usize) -> Result<String, u64> {
+        let mut decoder = Decoder::new(encoding).unwrap();
+        let mut output = String::new();
+        for chunk in input.chunks(chunk_len) {
+            decoder
+                .decode(chunk, &mut output)
+                .map_err(|_| decoder.position())?;
+        }
+        decoder
+            .finish(&mut output)
+            .map_err(|_| decoder.position())?;
+        Ok(output)
+    }
+
+    #[test]
+    fn test_decode_chunks() {
+        let text = "芙宁娜 and 😀 in 「文章」\n";
+        let testcases = [
+            "utf-8",
+            "utf-16",
+            "utf-16be",
+            "utf-32",
+            "gb18030",
+            "cesu-8",
+            "ucs-2be",
+            "utf-7",
+            "utf-7-imap",
+        ];
+        for encoding in testcases {
+            let text = if encoding.starts_with("ucs") {
+                text.replace('😀', ":)")
+            } else {
+                text.into()
+            };
+            let input = crate::encode(&text, encoding).unwrap();
+            for chunk_len in 1..=input.len() {
+                let result = decode_chunks(&input, encoding, chunk_len);
+                assert_eq!(result.as_deref(), Ok(&*text), "{encoding} {chunk_len}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_chunks_stateful() {
+        let testcases = [
+            ("ascii あい ascii\nかな", "iso-2022-jp"),
+            ("한국어 text 문장\n한국어", "iso-2022-kr"),
+            ("GSM {text} €", "gsm0338"),
+            ("GSM {text} € in packed form\r", "gsm0338-packed"),
+            ("EBCDIC\n", "ibm1047"),
+        ];
+        for (text, encoding) in testcases {
+            let input = crate::encode(text, encoding).unwrap();
+            for chunk_len in 1..=input.len() {
+                let result = decode_chunks(&input, encoding, chunk_len);
+                assert_eq!(result.as_deref(), Ok(text), "{encoding} {chunk_len}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_chunks_invalid() {
+        let testcases = [
+            (&b"ab\xffcd"[..], "utf-8", 2),
+            (b"ab\xe8\x8a", "utf-8", 2),
+            (b"\xdc\xbd\xff\xc4\xfe", "gb18030", 2),
+            (b"a\0\0\xd8b\0", "utf-16le", 2),
+        ];
+        for (input, encoding, position) in testcases {
+            for chunk_len in 1..=input.len() {
+                let result = decode_chunks(input, encoding, chunk_len);
+                assert_eq!(result, Err(position), "{encoding} {chunk_len}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_lossy_chunks() {
+        let input = b"a\xffb\xe8\x8a\x99c\xe8";
+        let expected = crate::decode_lossy(input, "utf-8").unwrap();
+        for chunk_len in 1..=input.len() {
+            let mut decoder = Decoder::new_lossy("utf-8").unwrap();
+            let mut output = String::new();
+            for chunk in input.chunks(chunk_len) {
+                decoder.decode(chunk, &mut output).unwrap();
+            }
+            decoder.finish(&mut output).unwrap();
+            assert_eq!(output, expected, "{chunk_len}");
+        }
+    }
+
+    #[test]
+    fn test_encode_chunks() {
+        let text = "GSM {text} € in packed form";
+        let expected = crate::encode(text, "gsm0338-packed").unwrap();
+        let chars: Vec<char> = text.chars().collect();
+        for chunk_len in 1..=chars.len() {
+            let mut encoder = Encoder::new("gsm0338-packed").unwrap();
+            let mut output = vec![];
+            for chunk in chars.chunks(chunk_len) {
+                let chunk: String = chunk.iter().collect();
+                encoder.encode(&chunk, &mut output).unwrap();
+            }
+            encoder.finish(&mut output).unwrap();
+            assert_eq!(output, expected, "{chunk_len}");
+        }
+    }
+
+    #[test]
+    fn test_iso2022_state() {
+        let mut state = Iso2022State::default();
+        state.update(b"\x1b$)Ca\x0eb\x1b(Bc\x1b$B");
+        let mut replay = vec![];
+        state.replay(&mut replay);
+        assert_eq!(replay, b"\x1b$B\x1b$)C\x0e");
+        state.update(b"\x0f\x1b(J");
+        replay.clear();
+        state.replay(&mut replay);
+        assert_eq!(replay, b"\x1b(J\x1b$)C");
+    }
+
+    #[test]
+    fn test_hz_state() {
+        let mut framing = Framing::from_encoding("hz-gb-2312");
+        framing.update(b"a~{~}b~{<V", false);
+        let mut replay = vec![];
+        framing.replay(&mut replay);
+        assert_eq!(replay, b"~{");
+        framing.update(b"~{<V~}~~", false);
+        replay.clear();
+        framing.replay(&mut replay);
+        assert_eq!(replay, b"");
+    }
+}
@@ -26,4 +26,19 @@ cfg_if! {
     }
 }
 
-pub(crate) use inner::{convert, convert_lossy, decode, decode_lossy};
+pub(crate) use inner::{
+    convert, convert_append, convert_into, convert_lossy, decode, decode_lossy, decode_prefix,
+    encode, encode_lossy, SequenceDecoder,
+};
+
+/// Copies a complete conversion result into `output` for implementations that cannot report
+/// partial progress. Nothing is copied if `converted` does not fit.
+pub(crate) fn copy_converted(input: &[u8], converted: &[u8], output: &mut [u8]) -> (usize, usize) {
+    match output.get_mut(..converted.len()) {
+        Some(output) => {
+            output.copy_from_slice(converted);
+            (input.len(), converted.len())
+        }
+        None => (0, 0),
+    }
+}
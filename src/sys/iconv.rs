@@ -1,38 +1,193 @@
+use core::fmt;
+use core::str::FromStr;
+
 use alloc::{string::String, vec::Vec};
 
+use crate::codec::utf7;
+use crate::encoding::match_encoding_parts_exact;
+use crate::options::DefaultByteOrder;
+use crate::utf::UtfEncoding;
 use crate::{ConvertError, ConvertLossyError};
 
 pub mod ffi;
 
+/// Consumes the BOM of `UTF-16` or `UTF-32` input and returns an encoding with an explicit byte
+/// order, since iconv implementations disagree on the byte order of BOM-less input.
+fn resolve_input<'a>(input: &mut &[u8], encoding: &'a str) -> &'a str {
+    match UtfEncoding::from_str(encoding) {
+        Ok(utf) if utf.is_ambiguous() => utf
+            .with_byte_order(utf.consume_input_bom(input, DefaultByteOrder::default()))
+            .label(),
+        _ => encoding,
+    }
+}
+
+/// Returns whether `encoding` keeps a shift state between characters, so that a conversion cannot
+/// be resumed from the middle of the input or output without that state.
+fn is_stateful(encoding: &str) -> bool {
+    encoding.to_ascii_uppercase().contains("2022")
+        || match_encoding_parts_exact(encoding, &["hz"])
+        || match_encoding_parts_exact(encoding, &["hz", "gb", "2312"])
+        || utf7::Variant::from_encoding(encoding).is_some()
+}
+
+/// Returns whether a conversion to `encoding` starts its output with a BOM.
+fn writes_bom(encoding: &str) -> bool {
+    UtfEncoding::from_str(encoding).is_ok_and(|utf| utf.is_ambiguous())
+}
+
 pub fn convert(
-    input: &[u8],
+    mut input: &[u8],
     from_encoding: &str,
     to_encoding: &str,
 ) -> Result<Vec<u8>, ConvertError> {
+    let from_encoding = resolve_input(&mut input, from_encoding);
     let mut iconv = ffi::Iconv::new(from_encoding, to_encoding)?;
     iconv.convert(input)
 }
 
-pub fn convert_lossy(
+pub fn convert_into(
     input: &[u8],
     from_encoding: &str,
     to_encoding: &str,
+    output: &mut [u8],
+) -> Result<(usize, usize), ConvertError> {
+    let mut rest = input;
+    let from_encoding = resolve_input(&mut rest, from_encoding);
+    let mut iconv = ffi::Iconv::new(from_encoding, to_encoding)?;
+    let (read, written) = iconv.convert_into(rest, output)?;
+    let is_partial = read < rest.len();
+    // A call with the rest of the input starts from the initial state, without the shift state of
+    // a stateful encoding or the byte order given by the BOM, and writes another BOM for `UTF-16`
+    // and `UTF-32` output, so report no progress at all. This includes a BOM consumed alone.
+    if is_partial
+        && (rest.len() < input.len()
+            || is_stateful(from_encoding)
+            || is_stateful(to_encoding)
+            || writes_bom(to_encoding))
+    {
+        return Ok((0, 0));
+    }
+    Ok((read + input.len() - rest.len(), written))
+}
+
+pub fn convert_append(
+    mut input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+    output: &mut Vec<u8>,
+) -> Result<(), ConvertError> {
+    let from_encoding = resolve_input(&mut input, from_encoding);
+    let mut iconv = ffi::Iconv::new(from_encoding, to_encoding)?;
+    iconv.convert_append(input, output)
+}
+
+pub fn convert_lossy(
+    mut input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
 ) -> Result<Vec<u8>, ConvertLossyError> {
+    let from_encoding = resolve_input(&mut input, from_encoding);
     let mut iconv = ffi::LossyIconv::new(from_encoding, to_encoding)?;
     let buf = iconv.convert(input);
     Ok(buf)
 }
 
-pub fn decode(input: &[u8], encoding: &str) -> Result<String, ConvertError> {
+pub fn encode(input: &str, encoding: &str) -> Result<Vec<u8>, ConvertError> {
+    let mut iconv = ffi::Iconv::new("UTF-8", encoding)?;
+    iconv.convert(input.as_bytes())
+}
+
+pub fn encode_lossy(input: &str, encoding: &str) -> Result<Vec<u8>, ConvertLossyError> {
+    let mut iconv = ffi::LossyIconv::new("UTF-8", encoding)?;
+    Ok(iconv.convert(input.as_bytes()))
+}
+
+pub fn decode(mut input: &[u8], encoding: &str) -> Result<String, ConvertError> {
+    let encoding = resolve_input(&mut input, encoding);
     let mut iconv = ffi::Iconv::new(encoding, "UTF-8")?;
     let buf = iconv.convert(input)?;
     // Safety: relies on the correctness of iconv implementation
     unsafe { Ok(String::from_utf8_unchecked(buf)) }
 }
 
-pub fn decode_lossy(input: &[u8], encoding: &str) -> Result<String, ConvertLossyError> {
+pub fn decode_prefix(mut input: &[u8], encoding: &str) -> Option<(String, usize)> {
+    let len = input.len();
+    let encoding = resolve_input(&mut input, encoding);
+    let mut iconv = ffi::Iconv::new(encoding, "UTF-8").ok()?;
+    let (buf, read) = iconv.convert_prefix(input);
+    // Safety: relies on the correctness of iconv implementation
+    let decoded = unsafe { String::from_utf8_unchecked(buf) };
+    Some((decoded, len - input.len() + read))
+}
+
+/// Decodes one sequence at a time with the same conversion descriptor, so that the length of each
+/// sequence is known without a conversion for every candidate length.
+pub struct SequenceDecoder(ffi::Iconv);
+
+impl fmt::Debug for SequenceDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SequenceDecoder").finish_non_exhaustive()
+    }
+}
+
+impl SequenceDecoder {
+    pub fn new(encoding: &str) -> Option<Self> {
+        ffi::Iconv::new(encoding, "UTF-32LE").ok().map(Self)
+    }
+
+    /// Decodes the first sequence of `input`, with any escape sequence in front of it, appending
+    /// its characters to `output`. Returns the length of the sequence, or `None` if it is invalid
+    /// or incomplete.
+    pub fn decode_next(&mut self, input: &[u8], output: &mut Vec<char>) -> Option<usize> {
+        // Room for a single character, so that the conversion stops after the first sequence,
+        // unless the sequence decodes to several characters.
+        let mut buf = [0; 16];
+        let mut buf_len = 4;
+        loop {
+            let (read, written, step) = self.0.convert_step(input, &mut buf[..buf_len]);
+            match step {
+                ffi::Step::OutputFull if written == 0 && buf_len < buf.len() => {
+                    buf_len *= 2;
+                    continue;
+                }
+                ffi::Step::InvalidInput if written == 0 => return None,
+                _ => {}
+            }
+            output.extend(buf[..written].chunks_exact(4).filter_map(|bytes| {
+                char::from_u32(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }));
+            return Some(read);
+        }
+    }
+}
+
+pub fn decode_lossy(mut input: &[u8], encoding: &str) -> Result<String, ConvertLossyError> {
+    let encoding = resolve_input(&mut input, encoding);
     let mut iconv = ffi::LossyIconv::new(encoding, "UTF-8")?;
     let buf = iconv.convert(input);
     // Safety: relies on the correctness of iconv implementation
     unsafe { Ok(String::from_utf8_unchecked(buf)) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_stateful() {
+        let testcases = [
+            ("iso-2022-jp", true),
+            ("ISO-2022-KR", true),
+            ("csISO2022JP", true),
+            ("hz-gb-2312", true),
+            ("utf-7", true),
+            ("utf-7-imap", true),
+            ("utf-8", false),
+            ("shift_jis", false),
+        ];
+        for (input, expected) in testcases {
+            assert_eq!(is_stateful(input), expected, "{input}");
+        }
+    }
+}
@@ -5,6 +5,7 @@ use alloc::{ffi::CString, string::ToString, vec::Vec};
 
 use cfg_if::cfg_if;
 
+use crate::bound::OutputBound;
 use crate::{ConvertError, ConvertLossyError};
 
 #[allow(non_camel_case_types)]
@@ -54,8 +55,10 @@ cfg_if! {
     }
 }
 
-#[repr(transparent)]
-pub struct IconvHandle(iconv_t);
+pub struct IconvHandle {
+    cd: iconv_t,
+    output_bound: Option<OutputBound>,
+}
 #[repr(transparent)]
 pub struct LossyIconv(IconvHandle);
 #[repr(transparent)]
@@ -66,6 +69,7 @@ unsafe impl Sync for IconvHandle {}
 
 impl IconvHandle {
     fn new(from_encoding: &str, to_encoding: &str, lossy: bool) -> Result<Self, ConvertLossyError> {
+        let output_bound = OutputBound::new(from_encoding, to_encoding);
         let from_encoding =
             CString::new(from_encoding).map_err(|_| ConvertLossyError::UnknownConversion)?;
         let to_encoding = to_encoding.to_string()
@@ -81,13 +85,37 @@ impl IconvHandle {
             if cd as isize == -1 {
                 return Err(ConvertLossyError::UnknownConversion);
             }
-            Ok(Self(cd))
+            Ok(Self { cd, output_bound })
         }
     }
 
     fn convert(&mut self, mut input: &[u8]) -> Result<Vec<u8>, (Vec<u8>, ConvertError)> {
-        // Ensure the initial capacity is not zero to avoid endless loop.
-        let mut output: Vec<u8> = Vec::with_capacity(input.len() + 4);
+        let mut output = Vec::new();
+        let res = self.convert_append(&mut input, &mut output);
+        output.shrink_to_fit();
+        match res {
+            Ok(()) => Ok(output),
+            Err(err) => Err((output, err)),
+        }
+    }
+
+    /// Converts `input`, advancing it past the converted part, which on error ends where the
+    /// invalid or incomplete sequence starts.
+    fn convert_append(
+        &mut self,
+        input: &mut &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), ConvertError> {
+        // Start with a capacity large enough for the whole output if possible to avoid
+        // reallocations, and ensure it is not zero to avoid endless loop.
+        let initial_capacity = match self
+            .output_bound
+            .and_then(|bound| bound.max_output_len(input.len()))
+        {
+            Some(len) => len.max(4),
+            None => input.len() + 4,
+        };
+        output.reserve(initial_capacity);
         unsafe {
             loop {
                 let mut inlen = input.len();
@@ -100,7 +128,7 @@ impl IconvHandle {
                 let mut outbuf_ptr = outbuf.as_mut_ptr();
                 let mut outlen = outbuf.len();
                 let res = iconv(
-                    self.0,
+                    self.cd,
                     &mut inbuf_ptr as *mut _,
                     &mut inlen,
                     &mut outbuf_ptr as *mut _ as *mut *mut c_char,
@@ -109,7 +137,7 @@ impl IconvHandle {
                 let is_last_error_e2big = is_last_error_e2big();
                 let new_len = outbuf.len() - outlen + output.len();
                 output.set_len(new_len);
-                input = &input[input.len() - inlen..];
+                *input = &input[input.len() - inlen..];
                 if res as isize == -1 && is_last_error_e2big {
                     // E2BIG
                     output.reserve(output.capacity() * 2 - output.len());
@@ -118,18 +146,72 @@ impl IconvHandle {
                 // glibc: even when //IGNORE is used, iconv may still return EINVAL.
                 // Ignore any errors for a best-effort lossy conversion.
                 if res as isize == -1 {
-                    output.shrink_to_fit();
-                    break Err((output, ConvertError::InvalidInput));
+                    break Err(ConvertError::InvalidInput);
                 }
                 // When inbuf_ptr is null, it means iconv has been called once with an empty input,
                 // which is essentially a flush being done.
                 if inbuf_ptr.is_null() {
-                    output.shrink_to_fit();
-                    break Ok(output);
+                    break Ok(());
                 }
             }
         }
     }
+
+    fn convert_into(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(usize, usize), ConvertError> {
+        let mut inlen = input.len();
+        let mut outbuf_ptr = output.as_mut_ptr() as *mut c_char;
+        let mut outlen = output.len();
+        unsafe {
+            if inlen != 0 {
+                let mut inbuf_ptr = input.as_ptr() as *const c_char;
+                let res = iconv(
+                    self.cd,
+                    &mut inbuf_ptr as *mut _,
+                    &mut inlen,
+                    &mut outbuf_ptr as *mut _,
+                    &mut outlen,
+                );
+                if res as isize == -1 {
+                    if is_last_error_e2big() {
+                        return Ok((input.len() - inlen, output.len() - outlen));
+                    }
+                    return Err(ConvertError::InvalidInput);
+                }
+            }
+            let mut inbuf_ptr = null();
+            let res = iconv(
+                self.cd,
+                &mut inbuf_ptr as *mut _,
+                &mut inlen,
+                &mut outbuf_ptr as *mut _,
+                &mut outlen,
+            );
+            if res as isize == -1 {
+                if is_last_error_e2big() {
+                    // The shift sequence does not fit. Since the conversion state cannot be
+                    // carried over to the next call, report no progress at all.
+                    return Ok((0, 0));
+                }
+                return Err(ConvertError::InvalidInput);
+            }
+        }
+        Ok((input.len(), output.len() - outlen))
+    }
+}
+
+/// Why a single `iconv` call returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// The whole input has been converted.
+    Complete,
+    /// The output buffer is full.
+    OutputFull,
+    /// The input has an invalid or incomplete sequence where the conversion stopped.
+    InvalidInput,
 }
 
 impl LossyIconv {
@@ -150,6 +232,58 @@ impl Iconv {
     pub fn convert(&mut self, input: &[u8]) -> Result<Vec<u8>, ConvertError> {
         self.0.convert(input).map_err(|(_, e)| e)
     }
+
+    pub fn convert_append(
+        &mut self,
+        mut input: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), ConvertError> {
+        let original_len = output.len();
+        self.0.convert_append(&mut input, output).inspect_err(|_| {
+            output.truncate(original_len);
+        })
+    }
+
+    /// Converts as much of `input` as fits in `output` with a single `iconv` call, keeping the
+    /// conversion state for the next call. Returns the number of bytes read and written.
+    pub fn convert_step(&mut self, input: &[u8], output: &mut [u8]) -> (usize, usize, Step) {
+        let mut inlen = input.len();
+        let mut inbuf_ptr = input.as_ptr() as *const c_char;
+        let mut outlen = output.len();
+        let mut outbuf_ptr = output.as_mut_ptr() as *mut c_char;
+        let res = unsafe {
+            iconv(
+                self.0.cd,
+                &mut inbuf_ptr as *mut _,
+                &mut inlen,
+                &mut outbuf_ptr as *mut _,
+                &mut outlen,
+            )
+        };
+        let step = match res as isize {
+            -1 if is_last_error_e2big() => Step::OutputFull,
+            -1 => Step::InvalidInput,
+            _ => Step::Complete,
+        };
+        (input.len() - inlen, output.len() - outlen, step)
+    }
+
+    /// Converts the longest prefix of `input` that is valid on its own, returning the output
+    /// and the length of the prefix.
+    pub fn convert_prefix(&mut self, mut input: &[u8]) -> (Vec<u8>, usize) {
+        let len = input.len();
+        let mut output = Vec::new();
+        let _ = self.0.convert_append(&mut input, &mut output);
+        (output, len - input.len())
+    }
+
+    pub fn convert_into(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(usize, usize), ConvertError> {
+        self.0.convert_into(input, output)
+    }
 }
 
 #[cfg(windows)]
@@ -167,6 +301,6 @@ fn is_last_error_e2big() -> bool {
 
 impl Drop for IconvHandle {
     fn drop(&mut self) {
-        unsafe { iconv_close(self.0) };
+        unsafe { iconv_close(self.cd) };
     }
 }
@@ -22,6 +22,27 @@ pub fn convert(
     convert::convert_inner(input, from_encoding, to_encoding, false)
 }
 
+pub fn convert_into(
+    input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+    output: &mut [u8],
+) -> Result<(usize, usize), ConvertError> {
+    let converted = convert(input, from_encoding, to_encoding)?;
+    Ok(super::copy_converted(input, &converted, output))
+}
+
+pub fn convert_append(
+    input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+    output: &mut Vec<u8>,
+) -> Result<(), ConvertError> {
+    let converted = convert(input, from_encoding, to_encoding)?;
+    output.extend_from_slice(&converted);
+    Ok(())
+}
+
 pub fn convert_lossy(
     input: &[u8],
     from_encoding: &str,
@@ -36,6 +57,20 @@ pub fn convert_lossy(
     }
 }
 
+pub fn encode(input: &str, encoding: &str) -> Result<Vec<u8>, ConvertError> {
+    convert::encode_inner(input, encoding)
+}
+
+pub fn encode_lossy(input: &str, encoding: &str) -> Result<Vec<u8>, ConvertLossyError> {
+    match convert::encode_inner(input, encoding) {
+        Ok(v) => Ok(v),
+        Err(ConvertError::UnknownConversion) => Err(ConvertLossyError::UnknownConversion),
+        Err(ConvertError::InvalidInput) => {
+            panic!("TextEncoder.encode returned an error during encode_lossy")
+        }
+    }
+}
+
 pub fn decode(input: &[u8], encoding: &str) -> Result<String, ConvertError> {
     if let Ok(utf) = UtfEncoding::from_str(encoding) {
         return decode_utf(input, utf);
@@ -52,6 +87,25 @@ pub fn decode(input: &[u8], encoding: &str) -> Result<String, ConvertError> {
     Ok(str)
 }
 
+/// Where decoding stopped is not reported, so the prefix is searched for by the caller.
+pub fn decode_prefix(_input: &[u8], _encoding: &str) -> Option<(String, usize)> {
+    None
+}
+
+/// Sequences cannot be decoded one at a time, so their length is searched for by the caller.
+#[derive(Debug)]
+pub struct SequenceDecoder(core::convert::Infallible);
+
+impl SequenceDecoder {
+    pub fn new(_encoding: &str) -> Option<Self> {
+        None
+    }
+
+    pub fn decode_next(&mut self, _input: &[u8], _output: &mut Vec<char>) -> Option<usize> {
+        match self.0 {}
+    }
+}
+
 pub fn decode_lossy(input: &[u8], encoding: &str) -> Result<String, ConvertLossyError> {
     if let Ok(utf) = UtfEncoding::from_str(encoding) {
         return Ok(decode_utf_lossy(input, utf));
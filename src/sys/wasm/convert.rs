@@ -8,7 +8,7 @@ use web_sys::{js_sys, TextDecoder, TextDecoderOptions};
 use super::ffi::*;
 use super::utf16::string_to_utf16;
 use super::utf32::string_to_utf32;
-use crate::utf::{decode_utf, decode_utf_lossy, UtfEncoding, UtfType};
+use crate::utf::{decode_utf, decode_utf_lossy, encode_cesu8, UtfEncoding, UtfType};
 use crate::ConvertError;
 
 pub fn convert_inner(
@@ -61,39 +61,62 @@ pub fn convert_inner(
 
     if let Some(to_utf) = to_utf {
         let decoded = decoded.as_string().unwrap_or_default();
-        let add_bom = to_utf.is_ambiguous();
-        Ok(match (to_utf.r#type(), to_utf.byte_order().is_le(true)) {
-            (UtfType::Utf8, _) => decoded.into_bytes(),
-            (UtfType::Utf16, true) => string_to_utf16(decoded, add_bom, u16::to_le_bytes),
-            (UtfType::Utf16, false) => string_to_utf16(decoded, add_bom, u16::to_be_bytes),
-            (UtfType::Utf32, true) => string_to_utf32(decoded, add_bom, u32::to_le_bytes),
-            (UtfType::Utf32, false) => string_to_utf32(decoded, add_bom, u32::to_be_bytes),
-        })
+        Ok(encode_utf(&decoded, to_utf))
     } else {
-        let options = js_sys::Object::new();
-        #[cfg(feature = "wasm-nonstandard-allow-legacy-encoding")]
-        {
-            Reflect::set(
-                &options,
-                &"NONSTANDARD_allowLegacyEncoding".into(),
-                &true.into(),
-            )
-            .expect("failed to set NONSTANDARD_allowLegacyEncoding");
-            Reflect::set(&options, &"fatal".into(), &true.into()).expect("failed to set fatal");
-        }
-        let encoder = TextEncoderNonStandard::new_with_label(to_encoding, options)
-            .map_err(|_| ConvertError::UnknownConversion)?;
-        #[cfg(feature = "wasm-nonstandard-allow-legacy-encoding")]
-        {
-            if !to_utf.map_or(false, |u| u.is_utf8())
-                && encoder.get_encoding().as_deref() == Some("utf-8")
-            {
-                // Maybe using a non-polyfilled TextEncoder
-                return Err(ConvertError::UnknownConversion);
-            }
-        }
-        encoder
-            .encode_with_raw_input(decoded)
-            .map_err(|_| ConvertError::InvalidInput)
+        encode_legacy(decoded, to_encoding)
+    }
+}
+
+pub fn encode_inner(input: &str, to_encoding: &str) -> Result<Vec<u8>, ConvertError> {
+    let to_utf = UtfEncoding::from_str(to_encoding).ok();
+
+    #[cfg(not(feature = "wasm-nonstandard-allow-legacy-encoding"))]
+    if to_utf.is_none() {
+        return Err(ConvertError::UnknownConversion);
+    }
+
+    if let Some(to_utf) = to_utf {
+        Ok(encode_utf(input, to_utf))
+    } else {
+        encode_legacy(JsValue::from_str(input), to_encoding)
+    }
+}
+
+fn encode_utf(input: &str, to_utf: UtfEncoding) -> Vec<u8> {
+    let add_bom = to_utf.is_ambiguous();
+    match (to_utf.r#type(), to_utf.byte_order().is_le(true)) {
+        (UtfType::Utf8, _) => input.as_bytes().to_vec(),
+        (UtfType::Cesu8, _) => encode_cesu8(input, false),
+        (UtfType::Mutf8, _) => encode_cesu8(input, true),
+        (UtfType::Ucs2, _) => unreachable!("UCS-2 is handled by the codec layer"),
+        (UtfType::Utf16, true) => string_to_utf16(input, add_bom, u16::to_le_bytes),
+        (UtfType::Utf16, false) => string_to_utf16(input, add_bom, u16::to_be_bytes),
+        (UtfType::Utf32, true) => string_to_utf32(input, add_bom, u32::to_le_bytes),
+        (UtfType::Utf32, false) => string_to_utf32(input, add_bom, u32::to_be_bytes),
+    }
+}
+
+fn encode_legacy(input: JsValue, to_encoding: &str) -> Result<Vec<u8>, ConvertError> {
+    let options = js_sys::Object::new();
+    #[cfg(feature = "wasm-nonstandard-allow-legacy-encoding")]
+    {
+        js_sys::Reflect::set(
+            &options,
+            &"NONSTANDARD_allowLegacyEncoding".into(),
+            &true.into(),
+        )
+        .expect("failed to set NONSTANDARD_allowLegacyEncoding");
+        js_sys::Reflect::set(&options, &"fatal".into(), &true.into()).expect("failed to set fatal");
+    }
+    let encoder = TextEncoderNonStandard::new_with_label(to_encoding, options)
+        .map_err(|_| ConvertError::UnknownConversion)?;
+    // `to_encoding` is never UTF-8 here.
+    #[cfg(feature = "wasm-nonstandard-allow-legacy-encoding")]
+    if encoder.get_encoding().as_deref() == Some("utf-8") {
+        // Maybe using a non-polyfilled TextEncoder
+        return Err(ConvertError::UnknownConversion);
     }
+    encoder
+        .encode_with_raw_input(input)
+        .map_err(|_| ConvertError::InvalidInput)
 }
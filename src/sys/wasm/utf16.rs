@@ -1,9 +1,9 @@
-use alloc::{string::String, vec::Vec};
+use alloc::vec::Vec;
 
 use widestring::encode_utf16;
 
 pub(super) fn string_to_utf16(
-    input: String,
+    input: &str,
     add_bom: bool,
     mut bytes_to_num: impl FnMut(u16) -> [u8; 2],
 ) -> Vec<u8> {
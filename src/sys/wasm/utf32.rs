@@ -1,9 +1,9 @@
-use alloc::{string::String, vec::Vec};
+use alloc::vec::Vec;
 
 use widestring::encode_utf32;
 
 pub(super) fn string_to_utf32(
-    input: String,
+    input: &str,
     add_bom: bool,
     mut bytes_to_num: impl FnMut(u32) -> [u8; 4],
 ) -> Vec<u8> {
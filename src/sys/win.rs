@@ -32,6 +32,27 @@ pub fn convert(
     )
 }
 
+pub fn convert_into(
+    input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+    output: &mut [u8],
+) -> Result<(usize, usize), ConvertError> {
+    let converted = convert(input, from_encoding, to_encoding)?;
+    Ok(super::copy_converted(input, &converted, output))
+}
+
+pub fn convert_append(
+    input: &[u8],
+    from_encoding: &str,
+    to_encoding: &str,
+    output: &mut Vec<u8>,
+) -> Result<(), ConvertError> {
+    let converted = convert(input, from_encoding, to_encoding)?;
+    output.extend_from_slice(&converted);
+    Ok(())
+}
+
 pub fn convert_lossy(
     input: &[u8],
     from_encoding: &str,
@@ -53,6 +74,16 @@ pub fn convert_lossy(
     )
 }
 
+pub fn encode(input: &str, encoding: &str) -> Result<Vec<u8>, ConvertError> {
+    let to_codepage = encoding_to_codepage(encoding).ok_or(ConvertLossyError::UnknownConversion)?;
+    wide::encode(input, encoding, to_codepage)
+}
+
+pub fn encode_lossy(input: &str, encoding: &str) -> Result<Vec<u8>, ConvertLossyError> {
+    let to_codepage = encoding_to_codepage(encoding).ok_or(ConvertLossyError::UnknownConversion)?;
+    wide::encode_lossy(input, encoding, to_codepage)
+}
+
 pub fn decode(input: &[u8], encoding: &str) -> Result<String, ConvertError> {
     if let Ok(utf) = UtfEncoding::from_str(encoding) {
         return decode_utf(input, utf);
@@ -65,6 +96,25 @@ pub fn decode(input: &[u8], encoding: &str) -> Result<String, ConvertError> {
     unsafe { Ok(String::from_utf8_unchecked(buf)) }
 }
 
+/// Where decoding stopped is not reported, so the prefix is searched for by the caller.
+pub fn decode_prefix(_input: &[u8], _encoding: &str) -> Option<(String, usize)> {
+    None
+}
+
+/// Sequences cannot be decoded one at a time, so their length is searched for by the caller.
+#[derive(Debug)]
+pub struct SequenceDecoder(core::convert::Infallible);
+
+impl SequenceDecoder {
+    pub fn new(_encoding: &str) -> Option<Self> {
+        None
+    }
+
+    pub fn decode_next(&mut self, _input: &[u8], _output: &mut Vec<char>) -> Option<usize> {
+        match self.0 {}
+    }
+}
+
 pub fn decode_lossy(input: &[u8], encoding: &str) -> Result<String, ConvertLossyError> {
     if let Ok(utf) = UtfEncoding::from_str(encoding) {
         return Ok(decode_utf_lossy(input, utf));
@@ -14,6 +14,7 @@ use super::codepage::{
 };
 use super::utf16::{utf16_to_wide, utf16_to_wide_lossy};
 use super::utf32::{utf32_to_wide, utf32_to_wide_lossy, wide_to_utf32, wide_to_utf32_lossy};
+use crate::options::DefaultByteOrder;
 use crate::utf::{UtfEncoding, UtfType};
 use crate::{ConvertError, ConvertLossyError};
 
@@ -157,6 +158,30 @@ fn encode_wide(
     Ok(output)
 }
 
+fn is_ambiguous_utf(encoding: &str) -> bool {
+    UtfEncoding::from_str(encoding)
+        .as_ref()
+        .map_or(false, UtfEncoding::is_ambiguous)
+}
+
+pub fn encode(input: &str, to_encoding: &str, to_codepage: u32) -> Result<Vec<u8>, ConvertError> {
+    // `&str` is always valid. Skip the validation done by `MultiByteToWideChar`.
+    let wide: Vec<u16> = input.encode_utf16().collect();
+    encode_wide(&wide, to_codepage, is_ambiguous_utf(to_encoding), false)
+}
+
+pub fn encode_lossy(
+    input: &str,
+    to_encoding: &str,
+    to_codepage: u32,
+) -> Result<Vec<u8>, ConvertLossyError> {
+    let wide: Vec<u16> = input.encode_utf16().collect();
+    encode_wide(&wide, to_codepage, is_ambiguous_utf(to_encoding), true).map_err(|e| match e {
+        ConvertError::UnknownConversion => ConvertLossyError::UnknownConversion,
+        ConvertError::InvalidInput => panic!("encode_wide failed during encode_lossy"),
+    })
+}
+
 pub fn convert(
     mut input: &[u8],
     from_encoding: &str,
@@ -168,7 +193,7 @@ pub fn convert(
         .ok()
         .filter(|u| u.is_utf16() || u.is_utf32())
     {
-        let byte_order = from_utf.consume_input_bom(&mut input);
+        let byte_order = from_utf.consume_input_bom(&mut input, DefaultByteOrder::default());
         let is_le = byte_order.is_le(true);
         match (from_utf.r#type(), is_le) {
             (UtfType::Utf16, true) => utf16_to_wide(input, u16::from_le_bytes),
@@ -180,14 +205,7 @@ pub fn convert(
     } else {
         decode_wide(input, from_codepage, false)
     }?;
-    encode_wide(
-        &wide,
-        to_codepage,
-        UtfEncoding::from_str(to_encoding)
-            .as_ref()
-            .map_or(false, UtfEncoding::is_ambiguous),
-        false,
-    )
+    encode_wide(&wide, to_codepage, is_ambiguous_utf(to_encoding), false)
 }
 pub fn convert_lossy(
     mut input: &[u8],
@@ -200,7 +218,7 @@ pub fn convert_lossy(
         .ok()
         .filter(|u| u.is_utf16() || u.is_utf32())
     {
-        let byte_order = from_utf.consume_input_bom(&mut input);
+        let byte_order = from_utf.consume_input_bom(&mut input, DefaultByteOrder::default());
         let is_le = byte_order.is_le(true);
         match (from_utf.r#type(), is_le) {
             (UtfType::Utf16, true) => utf16_to_wide_lossy(input, u16::from_le_bytes),
@@ -221,15 +239,7 @@ pub fn convert_lossy(
             }
         }
     };
-    encode_wide(
-        &wide,
-        to_codepage,
-        UtfEncoding::from_str(to_encoding)
-            .as_ref()
-            .map_or(false, UtfEncoding::is_ambiguous),
-        true,
-    )
-    .map_err(|e| match e {
+    encode_wide(&wide, to_codepage, is_ambiguous_utf(to_encoding), true).map_err(|e| match e {
         ConvertError::UnknownConversion => ConvertLossyError::UnknownConversion,
         ConvertError::InvalidInput => panic!("encode_wide failed during convert_lossy"),
     })
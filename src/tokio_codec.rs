@@ -0,0 +1,194 @@
+//! A [`tokio_util::codec`] implementation for line-based protocols in any
+//! encoding supported by this crate.
+//!
+//! [`LinesCodec`] decodes the input incrementally with a [`Decoder`], so line
+//! boundaries are found in the decoded text rather than by looking for a
+//! `0x0A` byte. This works for encodings where a newline takes more than one
+//! byte (UTF-16), is not `0x0A` (EBCDIC), or where `0x0A` may appear inside
+//! other characters, and the state of stateful encodings such as ISO-2022-JP
+//! is carried over from one line to the next.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use bytes::BytesMut;
+//! use iconv_native::tokio_codec::LinesCodec;
+//! use tokio_util::codec::{Decoder, Encoder};
+//!
+//! let mut codec = LinesCodec::new("cp437")?;
+//! let mut buf = BytesMut::from(&b"\x82t\x82\r\nna\xa4"[..]);
+//! assert_eq!(codec.decode(&mut buf)?.as_deref(), Some("été"));
+//! assert_eq!(codec.decode(&mut buf)?, None);
+//! assert_eq!(codec.decode_eof(&mut buf)?.as_deref(), Some("nañ"));
+//!
+//! codec.encode("ñ", &mut buf)?;
+//! assert_eq!(&buf[..], b"\xa4\n");
+//! # Ok::<(), iconv_native::tokio_codec::LinesCodecError>(())
+//! ```
+
+use std::{error, fmt, io};
+
+use alloc::{string::String, vec::Vec};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::lines::LineBuffer;
+use crate::{ConvertError, ConvertLossyError, Decoder, Encoder};
+
+/// A codec splitting input in an encoding into lines of [`String`], and
+/// encoding lines back to that encoding.
+///
+/// Lines are terminated by `\n` or NEL (`U+0085`), which EBCDIC NL (`0x15`)
+/// is decoded to, and a `\r` before `\n` is removed as well. When encoding,
+/// `\n` is appended to each line.
+#[derive(Debug, Clone)]
+pub struct LinesCodec {
+    decoder: Decoder,
+    encoder: Encoder,
+    lines: LineBuffer,
+    max_length: usize,
+    encoded: Vec<u8>,
+}
+
+impl LinesCodec {
+    fn with_coders(decoder: Decoder, encoder: Encoder) -> Self {
+        Self {
+            decoder,
+            encoder,
+            lines: LineBuffer::default(),
+            max_length: usize::MAX,
+            encoded: Vec::new(),
+        }
+    }
+
+    /// Creates a codec for `encoding`, which fails on invalid input and
+    /// characters that cannot be represented.
+    ///
+    /// Returns [`ConvertError::UnknownConversion`] if `encoding` is not
+    /// supported.
+    pub fn new(encoding: &str) -> Result<Self, ConvertError> {
+        Ok(Self::with_coders(
+            Decoder::new(encoding)?,
+            Encoder::new(encoding)?,
+        ))
+    }
+
+    /// Creates a codec for `encoding`, which replaces invalid input and
+    /// characters that cannot be represented.
+    ///
+    /// Returns [`ConvertLossyError::UnknownConversion`] if `encoding` is not
+    /// supported.
+    pub fn new_lossy(encoding: &str) -> Result<Self, ConvertLossyError> {
+        Ok(Self::with_coders(
+            Decoder::new_lossy(encoding)?,
+            Encoder::new_lossy(encoding)?,
+        ))
+    }
+
+    /// Sets the maximum length of a decoded line in UTF-8 bytes, excluding
+    /// the terminator. Longer lines result in
+    /// [`LinesCodecError::MaxLineLengthExceeded`]. Defaults to unlimited.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Returns the maximum length of a decoded line.
+    pub fn get_max_length(&self) -> usize {
+        self.max_length
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>, LinesCodecError> {
+        match self.lines.next_line() {
+            Some(line) if line.len() > self.max_length => {
+                Err(LinesCodecError::MaxLineLengthExceeded)
+            }
+            Some(line) => Ok(Some(line)),
+            // A `\r` at the end may be followed by `\n` in the next chunk.
+            None if self.lines.pending().trim_end_matches('\r').len() > self.max_length => {
+                Err(LinesCodecError::MaxLineLengthExceeded)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl tokio_util::codec::Decoder for LinesCodec {
+    type Item = String;
+    type Error = LinesCodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if !src.is_empty() {
+            let input = src.split();
+            self.decoder.decode(&input, self.lines.text_mut())?;
+        }
+        self.next_line()
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(line) = self.decode(src)? {
+            return Ok(Some(line));
+        }
+        self.decoder.finish(self.lines.text_mut())?;
+        if let Some(line) = self.next_line()? {
+            return Ok(Some(line));
+        }
+        Ok(self.lines.take_last_line())
+    }
+}
+
+impl<T: AsRef<str>> tokio_util::codec::Encoder<T> for LinesCodec {
+    type Error = LinesCodecError;
+
+    fn encode(&mut self, line: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let line = line.as_ref();
+        self.encoded.clear();
+        self.encoder.encode(line, &mut self.encoded)?;
+        self.encoder.encode("\n", &mut self.encoded)?;
+        dst.put_slice(&self.encoded);
+        Ok(())
+    }
+}
+
+/// Error representation for [`LinesCodec`].
+#[derive(Debug)]
+pub enum LinesCodecError {
+    /// The input is invalid, or a line contains characters that cannot be
+    /// represented in the encoding.
+    Convert(ConvertError),
+    /// A line is longer than [`LinesCodec::get_max_length`].
+    MaxLineLengthExceeded,
+    /// Reading or writing the underlying I/O object failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for LinesCodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinesCodecError::Convert(err) => err.fmt(f),
+            LinesCodecError::MaxLineLengthExceeded => f.write_str("max line length exceeded"),
+            LinesCodecError::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for LinesCodecError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LinesCodecError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConvertError> for LinesCodecError {
+    fn from(err: ConvertError) -> Self {
+        LinesCodecError::Convert(err)
+    }
+}
+
+impl From<io::Error> for LinesCodecError {
+    fn from(err: io::Error) -> Self {
+        LinesCodecError::Io(err)
+    }
+}
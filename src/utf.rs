@@ -14,14 +14,31 @@ cfg_if! {
     }
 }
 
-use crate::bom::{ByteOrderMark, ByteOrderMarkExt};
-use crate::encoding::trim_encoding_prefix;
+mod cesu8;
+pub(crate) use cesu8::{decode_cesu8, encode_cesu8};
+mod ucs2;
+pub(crate) use ucs2::{decode_ucs2, encode_ucs2};
+
+use crate::bom::{
+    guess_byte_order, ByteOrderMark, ByteOrderMarkExt, UTF16_BE_BOM, UTF16_LE_BOM, UTF32_BE_BOM,
+    UTF32_LE_BOM, UTF8_BOM,
+};
+use crate::encoding::{match_encoding_parts_exact, trim_encoding_prefix};
+use crate::options::DefaultByteOrder;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum UtfType {
     Utf8,
     Utf16,
     Utf32,
+    /// CESU-8, which is not supported by any underlying implementation.
+    Cesu8,
+    /// Java's Modified UTF-8, which is not supported by any underlying
+    /// implementation.
+    Mutf8,
+    /// UCS-2, which is UTF-16 without surrogates. Implemented in Rust since
+    /// underlying implementations disagree on whether surrogates are allowed.
+    Ucs2,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,15 +49,37 @@ pub(crate) struct UtfEncoding {
 
 impl UtfEncoding {
     fn parse(s: &str) -> Option<Self> {
-        let encoding = trim_encoding_prefix(s, "utf")?;
-        let (r#type, byte_order) = if encoding.starts_with("8") {
-            (UtfType::Utf8, trim_encoding_prefix(encoding, "8"))
-        } else if encoding.starts_with("16") {
-            (UtfType::Utf16, trim_encoding_prefix(encoding, "16"))
-        } else if encoding.starts_with("32") {
-            (UtfType::Utf32, trim_encoding_prefix(encoding, "32"))
+        let non_standard = if match_encoding_parts_exact(s, &["cesu", "8"]) {
+            Some(UtfType::Cesu8)
+        } else if match_encoding_parts_exact(s, &["mutf", "8"])
+            || match_encoding_parts_exact(s, &["modified", "utf", "8"])
+        {
+            Some(UtfType::Mutf8)
+        } else {
+            None
+        };
+        if let Some(r#type) = non_standard {
+            return Some(Self {
+                r#type,
+                byte_order: ByteOrderMark::NotPresent,
+            });
+        }
+        let (r#type, byte_order) = if let Some(encoding) = trim_encoding_prefix(s, "ucs") {
+            if !encoding.starts_with("2") {
+                return None;
+            }
+            (UtfType::Ucs2, trim_encoding_prefix(encoding, "2"))
         } else {
-            return None;
+            let encoding = trim_encoding_prefix(s, "utf")?;
+            if encoding.starts_with("8") {
+                (UtfType::Utf8, trim_encoding_prefix(encoding, "8"))
+            } else if encoding.starts_with("16") {
+                (UtfType::Utf16, trim_encoding_prefix(encoding, "16"))
+            } else if encoding.starts_with("32") {
+                (UtfType::Utf32, trim_encoding_prefix(encoding, "32"))
+            } else {
+                return None;
+            }
         };
         let byte_order = match byte_order {
             Some("le" | "LE") => ByteOrderMark::Le,
@@ -52,7 +91,8 @@ impl UtfEncoding {
     }
 
     pub(crate) fn is_ambiguous(&self) -> bool {
-        !self.is_utf8() && self.byte_order == ByteOrderMark::NotPresent
+        (self.is_utf16() || self.is_utf32() || self.r#type == UtfType::Ucs2)
+            && self.byte_order == ByteOrderMark::NotPresent
     }
 
     #[allow(dead_code)]
@@ -70,22 +110,78 @@ impl UtfEncoding {
         self.r#type == UtfType::Utf8
     }
 
-    #[allow(dead_code)]
     pub(crate) fn is_utf16(&self) -> bool {
         self.r#type == UtfType::Utf16
     }
 
-    #[allow(dead_code)]
     pub(crate) fn is_utf32(&self) -> bool {
         self.r#type == UtfType::Utf32
     }
 
+    /// Returns the length of a code unit in bytes.
+    pub(crate) fn code_unit_len(&self) -> usize {
+        match self.r#type {
+            UtfType::Utf16 | UtfType::Ucs2 => 2,
+            UtfType::Utf32 => 4,
+            UtfType::Utf8 | UtfType::Cesu8 | UtfType::Mutf8 => 1,
+        }
+    }
+
+    pub(crate) fn with_byte_order(&self, byte_order: ByteOrderMark) -> Self {
+        Self {
+            r#type: self.r#type,
+            byte_order,
+        }
+    }
+
+    /// Resolves `UTF-16` and `UTF-32` to an explicit byte order.
+    pub(crate) fn resolve(&self, default_le: bool) -> Self {
+        if !self.is_ambiguous() {
+            return *self;
+        }
+        self.with_byte_order(if default_le {
+            ByteOrderMark::Le
+        } else {
+            ByteOrderMark::Be
+        })
+    }
+
+    /// Returns a label with an explicit byte order which is understood by all
+    /// implementations. Should only be called on resolved encodings.
+    pub(crate) fn label(&self) -> &'static str {
+        match (self.r#type, self.byte_order) {
+            (UtfType::Utf8, _) => "UTF-8",
+            (UtfType::Cesu8, _) => "CESU-8",
+            (UtfType::Mutf8, _) => "MUTF-8",
+            (UtfType::Utf16, ByteOrderMark::Be) => "UTF-16BE",
+            (UtfType::Utf16, _) => "UTF-16LE",
+            (UtfType::Ucs2, ByteOrderMark::Be) => "UCS-2BE",
+            (UtfType::Ucs2, _) => "UCS-2LE",
+            (UtfType::Utf32, ByteOrderMark::Be) => "UTF-32BE",
+            (UtfType::Utf32, _) => "UTF-32LE",
+        }
+    }
+
+    /// Returns the BOM of the encoding. Should only be called on resolved
+    /// encodings.
+    pub(crate) fn bom(&self) -> &'static [u8] {
+        match (self.r#type, self.byte_order) {
+            (UtfType::Utf8 | UtfType::Cesu8 | UtfType::Mutf8, _) => &UTF8_BOM,
+            (UtfType::Utf16 | UtfType::Ucs2, ByteOrderMark::Be) => &UTF16_BE_BOM,
+            (UtfType::Utf16 | UtfType::Ucs2, _) => &UTF16_LE_BOM,
+            (UtfType::Utf32, ByteOrderMark::Be) => &UTF32_BE_BOM,
+            (UtfType::Utf32, _) => &UTF32_LE_BOM,
+        }
+    }
+
     pub(crate) fn strip_bom(&self, input: &mut &[u8]) -> bool {
         match (self.r#type, self.byte_order) {
-            (UtfType::Utf8, _) if input.get_utf8_bom().is_present() => {
+            (UtfType::Utf8 | UtfType::Cesu8 | UtfType::Mutf8, _)
+                if input.get_utf8_bom().is_present() =>
+            {
                 *input = &input[3..];
             }
-            (UtfType::Utf16, byte_order)
+            (UtfType::Utf16 | UtfType::Ucs2, byte_order)
                 if byte_order.is_present() && input.get_utf16_bom() == byte_order =>
             {
                 *input = &input[2..];
@@ -100,13 +196,20 @@ impl UtfEncoding {
         true
     }
 
-    #[allow(dead_code)]
-    pub(crate) fn consume_input_bom(&self, input: &mut &[u8]) -> ByteOrderMark {
+    /// Consumes the BOM of `UTF-16` or `UTF-32` input and returns the byte order it indicates. If
+    /// the BOM is not present, the byte order is determined by `default_byte_order`.
+    ///
+    /// For other encodings, the input is left untouched and the specified byte order is returned.
+    pub(crate) fn consume_input_bom(
+        &self,
+        input: &mut &[u8],
+        default_byte_order: DefaultByteOrder,
+    ) -> ByteOrderMark {
         if !self.is_ambiguous() {
             return self.byte_order;
         }
         let (input_bom, bom_len) = match self.r#type {
-            UtfType::Utf16 => {
+            UtfType::Utf16 | UtfType::Ucs2 => {
                 let bom = input.get_utf16_bom();
                 (bom, 2)
             }
@@ -119,9 +222,16 @@ impl UtfEncoding {
         };
         if input_bom.is_present() {
             *input = &input[bom_len..];
-            input_bom
+            return input_bom;
+        }
+        let guessed = match default_byte_order {
+            DefaultByteOrder::Guess => guess_byte_order(input, bom_len),
+            _ => ByteOrderMark::NotPresent,
+        };
+        if guessed.is_le(default_byte_order != DefaultByteOrder::BigEndian) {
+            ByteOrderMark::Le
         } else {
-            self.byte_order
+            ByteOrderMark::Be
         }
     }
 }
@@ -150,6 +260,13 @@ mod tests {
             ("utf-16be", UtfType::Utf16, ByteOrderMark::Be),
             ("utf-32le", UtfType::Utf32, ByteOrderMark::Le),
             ("utf-32be", UtfType::Utf32, ByteOrderMark::Be),
+            ("cesu-8", UtfType::Cesu8, ByteOrderMark::NotPresent),
+            ("CESU8", UtfType::Cesu8, ByteOrderMark::NotPresent),
+            ("mutf-8", UtfType::Mutf8, ByteOrderMark::NotPresent),
+            ("modified-utf-8", UtfType::Mutf8, ByteOrderMark::NotPresent),
+            ("ucs-2", UtfType::Ucs2, ByteOrderMark::NotPresent),
+            ("UCS2LE", UtfType::Ucs2, ByteOrderMark::Le),
+            ("ucs-2be", UtfType::Ucs2, ByteOrderMark::Be),
         ];
         for (input, r#type, byte_order) in testcases.iter() {
             let encoding = UtfEncoding::parse(input).expect(&input);
@@ -160,7 +277,14 @@ mod tests {
 
     #[test]
     fn test_utf_encoding_parse_invalid() {
-        let testcases = ["utf", "utf-9", "utf-16lebe", "wtf-32"];
+        let testcases = [
+            "utf",
+            "utf-9",
+            "utf-16lebe",
+            "wtf-32",
+            "cesu-16",
+            "mutf-8le",
+        ];
         for input in testcases.iter() {
             let encoding = UtfEncoding::parse(input);
             assert!(encoding.is_none(), "{input}");
@@ -177,6 +301,10 @@ mod tests {
             ("utf-16be", false),
             ("utf-32le", false),
             ("utf-32be", false),
+            ("cesu-8", false),
+            ("mutf-8", false),
+            ("ucs-2", true),
+            ("ucs-2le", false),
         ];
         for (input, expected) in testcases.iter() {
             let encoding = UtfEncoding::parse(input).expect(&input);
@@ -193,7 +321,7 @@ mod tests {
                 ByteOrderMark::NotPresent,
                 &b"hello"[..],
             ),
-            ("utf-16", b"hello", ByteOrderMark::NotPresent, b"hello"),
+            ("utf-16", b"hello", ByteOrderMark::Le, b"hello"),
             ("utf-16", b"\xff\xfehello", ByteOrderMark::Le, b"hello"),
             ("utf-16", b"\xfe\xffhello", ByteOrderMark::Be, b"hello"),
             ("utf-16be", b"hello", ByteOrderMark::Be, b"hello"),
@@ -222,7 +350,7 @@ mod tests {
                 ByteOrderMark::Le,
                 b"\xfe\xffhello",
             ),
-            ("utf-32", b"hello", ByteOrderMark::NotPresent, b"hello"),
+            ("utf-32", b"hello", ByteOrderMark::Le, b"hello"),
             (
                 "utf-32",
                 b"\xff\xfe\x00\x00hello",
@@ -268,9 +396,72 @@ mod tests {
             let idx = idx.to_string();
             let mut input = input;
             let encoding = UtfEncoding::parse(encoding).expect(&idx);
-            let bom = encoding.consume_input_bom(&mut input);
+            let bom = encoding.consume_input_bom(&mut input, DefaultByteOrder::LittleEndian);
             assert_eq!(bom, expected_bom, "{idx}");
             assert_eq!(input, expected_input, "{idx}");
         }
     }
+
+    #[test]
+    fn test_utf_encoding_consume_input_bom_default() {
+        let testcases = [
+            (
+                "utf-16",
+                &b"h\0i\0"[..],
+                DefaultByteOrder::BigEndian,
+                ByteOrderMark::Be,
+            ),
+            (
+                "utf-16",
+                b"\xff\xfeh\0",
+                DefaultByteOrder::BigEndian,
+                ByteOrderMark::Le,
+            ),
+            (
+                "utf-16",
+                b"\0h\0i",
+                DefaultByteOrder::Guess,
+                ByteOrderMark::Be,
+            ),
+            (
+                "utf-16",
+                b"h\0i\0",
+                DefaultByteOrder::Guess,
+                ByteOrderMark::Le,
+            ),
+            (
+                "utf-16",
+                b"\x82\x99",
+                DefaultByteOrder::Guess,
+                ByteOrderMark::Le,
+            ),
+            (
+                "utf-32",
+                b"\0\0\0h",
+                DefaultByteOrder::Guess,
+                ByteOrderMark::Be,
+            ),
+            (
+                "utf-32",
+                b"h\0\0\0",
+                DefaultByteOrder::BigEndian,
+                ByteOrderMark::Be,
+            ),
+            (
+                "utf-16le",
+                b"\0h",
+                DefaultByteOrder::Guess,
+                ByteOrderMark::Le,
+            ),
+        ];
+        for (idx, (encoding, input, default_byte_order, expected_bom)) in
+            testcases.into_iter().enumerate()
+        {
+            let idx = idx.to_string();
+            let mut input = input;
+            let encoding = UtfEncoding::parse(encoding).expect(&idx);
+            let bom = encoding.consume_input_bom(&mut input, default_byte_order);
+            assert_eq!(bom, expected_bom, "{idx}");
+        }
+    }
 }
@@ -0,0 +1,155 @@
+//! [CESU-8](https://www.unicode.org/reports/tr26/) and Java's
+//! [Modified UTF-8](https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.4.7),
+//! which encode supplementary characters as surrogate pairs, each surrogate taking 3 bytes.
+//! Modified UTF-8 additionally encodes NUL as `C0 80`.
+
+use core::cell::Cell;
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+use crate::ConvertError;
+
+/// Iterates UTF-16 code units of CESU-8 or Modified UTF-8 input. Invalid sequences yield `None`
+/// and are skipped one byte at a time.
+struct Units<'i> {
+    input: &'i [u8],
+    modified: bool,
+}
+
+impl Iterator for Units<'_> {
+    type Item = Option<u16>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        const CONT: core::ops::RangeInclusive<u8> = 0x80..=0xBF;
+        let (unit, len) = match *self.input {
+            [] => return None,
+            [0x00, ..] if self.modified => (None, 1),
+            [b0 @ 0x00..=0x7F, ..] => (Some(u16::from(b0)), 1),
+            [0xC0, 0x80, ..] if self.modified => (Some(0), 2),
+            [b0 @ 0xC2..=0xDF, b1, ..] if CONT.contains(&b1) => {
+                (Some((u16::from(b0 & 0x1F) << 6) | u16::from(b1 & 0x3F)), 2)
+            }
+            [b0 @ 0xE0..=0xEF, b1, b2, ..]
+                if CONT.contains(&b1) && CONT.contains(&b2) && (b0 != 0xE0 || b1 >= 0xA0) =>
+            {
+                (
+                    Some(
+                        (u16::from(b0 & 0x0F) << 12)
+                            | (u16::from(b1 & 0x3F) << 6)
+                            | u16::from(b2 & 0x3F),
+                    ),
+                    3,
+                )
+            }
+            _ => (None, 1),
+        };
+        self.input = &self.input[len..];
+        Some(unit)
+    }
+}
+
+/// Whether `input` is identical in UTF-8 and CESU-8 (or Modified UTF-8 if `modified`).
+fn is_plain_utf8(input: &[u8], modified: bool) -> bool {
+    !input
+        .iter()
+        .any(|&b| b >= 0xF0 || (modified && (b == 0 || b == 0xC0)))
+}
+
+pub(crate) fn decode_cesu8(
+    input: &[u8],
+    modified: bool,
+    lossy: bool,
+) -> Result<Cow<'_, str>, ConvertError> {
+    if is_plain_utf8(input, modified) {
+        if let Ok(str) = core::str::from_utf8(input) {
+            return Ok(Cow::Borrowed(str));
+        }
+    }
+    let is_invalid = Cell::new(false);
+    let units = Units { input, modified }.map(|unit| {
+        unit.unwrap_or_else(|| {
+            is_invalid.set(true);
+            0xFFFD
+        })
+    });
+    let mut output = String::with_capacity(input.len());
+    for ch in char::decode_utf16(units) {
+        output.push(ch.unwrap_or_else(|_| {
+            is_invalid.set(true);
+            char::REPLACEMENT_CHARACTER
+        }));
+        if is_invalid.get() && !lossy {
+            return Err(ConvertError::InvalidInput);
+        }
+    }
+    Ok(Cow::Owned(output))
+}
+
+pub(crate) fn encode_cesu8(input: &str, modified: bool) -> Vec<u8> {
+    if is_plain_utf8(input.as_bytes(), modified) {
+        return input.as_bytes().to_vec();
+    }
+    let mut output = Vec::with_capacity(input.len() + input.len() / 2);
+    for ch in input.chars() {
+        match ch {
+            '\0' if modified => output.extend_from_slice(&[0xC0, 0x80]),
+            '\u{10000}'..='\u{10FFFF}' => {
+                for unit in ch.encode_utf16(&mut [0; 2]) {
+                    output.extend_from_slice(&[
+                        0xE0 | (*unit >> 12) as u8,
+                        0x80 | ((*unit >> 6) & 0x3F) as u8,
+                        0x80 | (*unit & 0x3F) as u8,
+                    ]);
+                }
+            }
+            _ => output.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cesu8_roundtrip() {
+        let testcases = [
+            ("a😀b", false, &b"a\xed\xa0\xbd\xed\xb8\x80b"[..]),
+            ("a😀\0", true, b"a\xed\xa0\xbd\xed\xb8\x80\xc0\x80"),
+            ("芙宁娜\0", false, "芙宁娜\0".as_bytes()),
+            ("", true, b""),
+        ];
+        for (idx, (str, modified, bytes)) in testcases.into_iter().enumerate() {
+            assert_eq!(encode_cesu8(str, modified), bytes, "{idx}");
+            assert_eq!(decode_cesu8(bytes, modified, false).unwrap(), str, "{idx}");
+        }
+    }
+
+    #[test]
+    fn test_cesu8_invalid() {
+        let testcases = [
+            (
+                &b"a\xf0\x9f\x98\x80"[..],
+                false,
+                "a\u{fffd}\u{fffd}\u{fffd}\u{fffd}",
+            ),
+            (b"\xed\xa0\xbdb", false, "\u{fffd}b"),
+            (b"\xc0\x80", false, "\u{fffd}\u{fffd}"),
+            (b"a\0", true, "a\u{fffd}"),
+            (b"\xe0\x80\x80", true, "\u{fffd}\u{fffd}\u{fffd}"),
+        ];
+        for (idx, (bytes, modified, expected_lossy)) in testcases.into_iter().enumerate() {
+            assert_eq!(
+                decode_cesu8(bytes, modified, false),
+                Err(ConvertError::InvalidInput),
+                "{idx}"
+            );
+            assert_eq!(
+                decode_cesu8(bytes, modified, true).unwrap(),
+                expected_lossy,
+                "{idx}_lossy"
+            );
+        }
+    }
+}
@@ -1,10 +1,12 @@
+use alloc::borrow::Cow;
 use alloc::string::{String, ToString};
 
 use widestring::{decode_utf16, decode_utf32};
 
+use crate::options::DefaultByteOrder;
 use crate::ConvertError;
 
-use super::{UtfEncoding, UtfType};
+use super::{decode_cesu8, decode_ucs2, UtfEncoding, UtfType};
 
 fn decode_utf16_inner(
     input: &[u8],
@@ -35,13 +37,16 @@ fn decode_utf32_inner(
 }
 
 pub(crate) fn decode_utf(mut input: &[u8], encoding: UtfEncoding) -> Result<String, ConvertError> {
-    let byte_order = encoding.consume_input_bom(&mut input);
+    let byte_order = encoding.consume_input_bom(&mut input, DefaultByteOrder::default());
     let is_le = byte_order.is_le(true);
     match (encoding.r#type, is_le) {
         (UtfType::Utf16, true) => decode_utf16_inner(input, u16::from_le_bytes),
         (UtfType::Utf16, false) => decode_utf16_inner(input, u16::from_be_bytes),
         (UtfType::Utf32, true) => decode_utf32_inner(input, u32::from_le_bytes),
         (UtfType::Utf32, false) => decode_utf32_inner(input, u32::from_be_bytes),
+        (UtfType::Cesu8, _) => decode_cesu8(input, false, false).map(Cow::into_owned),
+        (UtfType::Mutf8, _) => decode_cesu8(input, true, false).map(Cow::into_owned),
+        (UtfType::Ucs2, is_le) => decode_ucs2(input, is_le, false),
         (UtfType::Utf8, _) => match alloc::str::from_utf8(input) {
             Ok(s) => Ok(s.to_string()),
             Err(_) => Err(ConvertError::InvalidInput),
@@ -2,7 +2,9 @@ use alloc::string::String;
 
 use widestring::{decode_utf16_lossy, decode_utf32_lossy};
 
-use super::{UtfEncoding, UtfType};
+use crate::options::DefaultByteOrder;
+
+use super::{decode_cesu8, decode_ucs2, UtfEncoding, UtfType};
 
 fn decode_utf16_lossy_inner(input: &[u8], mut bytes_to_num: impl FnMut([u8; 2]) -> u16) -> String {
     let mut input_iter = input.chunks_exact(2);
@@ -33,7 +35,7 @@ fn decode_utf32_lossy_inner(input: &[u8], mut bytes_to_num: impl FnMut([u8; 4])
 }
 
 pub(crate) fn decode_utf_lossy(mut input: &[u8], encoding: UtfEncoding) -> String {
-    let byte_order = encoding.consume_input_bom(&mut input);
+    let byte_order = encoding.consume_input_bom(&mut input, DefaultByteOrder::default());
     let is_le = byte_order.is_le(true);
     match (encoding.r#type, is_le) {
         (UtfType::Utf16, true) => decode_utf16_lossy_inner(input, u16::from_le_bytes),
@@ -41,5 +43,16 @@ pub(crate) fn decode_utf_lossy(mut input: &[u8], encoding: UtfEncoding) -> Strin
         (UtfType::Utf32, true) => decode_utf32_lossy_inner(input, u32::from_le_bytes),
         (UtfType::Utf32, false) => decode_utf32_lossy_inner(input, u32::from_be_bytes),
         (UtfType::Utf8, _) => String::from_utf8_lossy(input).into_owned(),
+        (UtfType::Cesu8 | UtfType::Mutf8, _) => {
+            let modified = encoding.r#type == UtfType::Mutf8;
+            match decode_cesu8(input, modified, true) {
+                Ok(decoded) => decoded.into_owned(),
+                Err(_) => unreachable!("lossy decoding failed"),
+            }
+        }
+        (UtfType::Ucs2, is_le) => match decode_ucs2(input, is_le, true) {
+            Ok(decoded) => decoded,
+            Err(_) => unreachable!("lossy decoding failed"),
+        },
     }
 }
@@ -0,0 +1,110 @@
+//! UCS-2, which is UTF-16 restricted to the BMP. Surrogates are invalid in either direction.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::ConvertError;
+
+pub(crate) fn decode_ucs2(input: &[u8], is_le: bool, lossy: bool) -> Result<String, ConvertError> {
+    let chunks = input.chunks_exact(2);
+    let has_remainder = !chunks.remainder().is_empty();
+    let mut output = String::with_capacity(input.len());
+    for chunk in chunks {
+        let bytes = [chunk[0], chunk[1]];
+        let unit = if is_le {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        };
+        match char::from_u32(u32::from(unit)) {
+            Some(ch) => output.push(ch),
+            None if lossy => output.push(char::REPLACEMENT_CHARACTER),
+            None => return Err(ConvertError::InvalidInput),
+        }
+    }
+    if has_remainder {
+        if !lossy {
+            return Err(ConvertError::InvalidInput);
+        }
+        output.push(char::REPLACEMENT_CHARACTER);
+    }
+    Ok(output)
+}
+
+/// Encodes `input` as UCS-2. Characters above `U+FFFF` are invalid, or replaced with `U+FFFD` if
+/// `lossy`.
+pub(crate) fn encode_ucs2(
+    input: &str,
+    is_le: bool,
+    add_bom: bool,
+    lossy: bool,
+) -> Result<Vec<u8>, ConvertError> {
+    let mut output = Vec::with_capacity(input.len() * 2 + 2);
+    if add_bom {
+        output.extend_from_slice(&if is_le { [0xFF, 0xFE] } else { [0xFE, 0xFF] });
+    }
+    for ch in input.chars() {
+        let unit = match u16::try_from(u32::from(ch)) {
+            Ok(unit) => unit,
+            Err(_) if lossy => 0xFFFD,
+            Err(_) => return Err(ConvertError::InvalidInput),
+        };
+        output.extend_from_slice(&if is_le {
+            unit.to_le_bytes()
+        } else {
+            unit.to_be_bytes()
+        });
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ucs2_roundtrip() {
+        let testcases = [
+            ("芙a", true, false, &b"\x99\x82a\0"[..]),
+            ("芙a", false, true, b"\xfe\xff\x82\x99\0a"),
+            ("", true, false, b""),
+        ];
+        for (idx, (str, is_le, add_bom, bytes)) in testcases.into_iter().enumerate() {
+            assert_eq!(
+                encode_ucs2(str, is_le, add_bom, false).unwrap(),
+                bytes,
+                "{idx}"
+            );
+            let bytes = if add_bom { &bytes[2..] } else { bytes };
+            assert_eq!(decode_ucs2(bytes, is_le, false).unwrap(), str, "{idx}");
+        }
+    }
+
+    #[test]
+    fn test_ucs2_invalid() {
+        assert_eq!(
+            encode_ucs2("a😀", true, false, false),
+            Err(ConvertError::InvalidInput)
+        );
+        assert_eq!(
+            encode_ucs2("a😀", true, false, true).unwrap(),
+            b"a\0\xfd\xff"
+        );
+
+        let testcases = [
+            (&b"\x3d\xd8\x00\xde"[..], "\u{fffd}\u{fffd}"),
+            (b"a\0b", "a\u{fffd}"),
+        ];
+        for (idx, (bytes, expected_lossy)) in testcases.into_iter().enumerate() {
+            assert_eq!(
+                decode_ucs2(bytes, true, false),
+                Err(ConvertError::InvalidInput),
+                "{idx}"
+            );
+            assert_eq!(
+                decode_ucs2(bytes, true, true).unwrap(),
+                expected_lossy,
+                "{idx}_lossy"
+            );
+        }
+    }
+}
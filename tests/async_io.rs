@@ -0,0 +1,153 @@
+#![cfg(any(feature = "futures-io", feature = "tokio"))]
+
+#[macro_use]
+mod harness;
+mod strings;
+
+use futures::executor::block_on;
+use iconv_native::async_io::{TranscodeReader, TranscodeWriter};
+use strings::*;
+
+#[cfg(feature = "futures-io")]
+mod trickle {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures::io::{AsyncRead, AsyncWrite};
+
+    /// Reads or writes one byte at a time, returning `Pending` before each byte.
+    pub struct Trickle<T> {
+        pub inner: T,
+        is_ready: bool,
+    }
+
+    impl<T> Trickle<T> {
+        pub fn new(inner: T) -> Self {
+            Self {
+                inner,
+                is_ready: false,
+            }
+        }
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+            self.is_ready = !self.is_ready;
+            if self.is_ready {
+                Poll::Ready(())
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    impl AsyncRead for Trickle<&[u8]> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            futures::ready!(self.poll_ready(cx));
+            let len = buf.len().min(1);
+            Pin::new(&mut self.inner).poll_read(cx, &mut buf[..len])
+        }
+    }
+
+    impl AsyncWrite for Trickle<Vec<u8>> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            futures::ready!(self.poll_ready(cx));
+            let len = buf.len().min(1);
+            Pin::new(&mut self.inner).poll_write(cx, &buf[..len])
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_close(cx)
+        }
+    }
+}
+
+#[cfg(feature = "futures-io")]
+use trickle::Trickle;
+
+#[cfg(feature = "futures-io")]
+with_harness! {
+    fn test_async_io_futures_read() {
+        use futures::io::AsyncReadExt;
+
+        let testcases = [
+            (TEST_GB18030, "gb18030", TEST_UTF16_LE_BOM, "utf-16"),
+            (TEST_UTF16_BE_BOM, "utf-16", TEST_UTF8, "utf-8"),
+            (b"\x1b$B$\"\x1b(Ba", "iso-2022-jp", "あa".as_bytes(), "utf-8"),
+        ];
+        for (input, from_encoding, expected, to_encoding) in testcases {
+            let inner = Trickle::new(input);
+            let mut reader = TranscodeReader::new(inner, from_encoding, to_encoding).unwrap();
+            let mut output = vec![];
+            block_on(reader.read_to_end(&mut output)).unwrap();
+            assert_eq!(output, expected, "{from_encoding} => {to_encoding}");
+        }
+    }
+
+    fn test_async_io_futures_read_invalid() {
+        use futures::io::AsyncReadExt;
+
+        let inner = Trickle::new(&b"ab\xe8\x8a"[..]);
+        let mut reader = TranscodeReader::new(inner, "utf-8", "utf-16le").unwrap();
+        let mut output = vec![];
+        let result = block_on(reader.read_to_end(&mut output));
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(output, b"a\0b\0", "valid prefix");
+
+        let mut reader = TranscodeReader::new_lossy(&b"ab\xff"[..], "utf-8", "ascii").unwrap();
+        let mut output = String::new();
+        block_on(reader.read_to_string(&mut output)).unwrap();
+        assert!(output.starts_with("ab"));
+    }
+
+    fn test_async_io_futures_write() {
+        use futures::io::AsyncWriteExt;
+
+        let inner = Trickle::new(vec![]);
+        let mut writer = TranscodeWriter::new(inner, "utf-8", "gb18030").unwrap();
+        for byte in TEST_UTF8 {
+            block_on(writer.write_all(&[*byte])).unwrap();
+        }
+        block_on(writer.close()).unwrap();
+        assert_eq!(writer.into_inner().inner, TEST_GB18030);
+
+        let mut writer = TranscodeWriter::new(vec![], "utf-8", "gb18030").unwrap();
+        block_on(writer.write_all(&TEST_UTF8[..4])).unwrap();
+        let result = block_on(writer.close());
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData, "incomplete");
+    }
+}
+
+#[cfg(feature = "tokio")]
+with_harness! {
+    fn test_async_io_tokio() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut reader = TranscodeReader::new(TEST_GB18030, "gb18030", "utf-8").unwrap();
+        let mut output = String::new();
+        block_on(reader.read_to_string(&mut output)).unwrap();
+        assert_eq!(output.as_bytes(), TEST_UTF8);
+
+        let mut writer = TranscodeWriter::new(vec![], "utf-8", "utf-16be").unwrap();
+        block_on(writer.write_all(&TEST_UTF8[..4])).unwrap();
+        block_on(writer.write_all(&TEST_UTF8[4..])).unwrap();
+        block_on(writer.shutdown()).unwrap();
+        assert_eq!(writer.into_inner(), TEST_UTF16_BE);
+
+        let mut reader = TranscodeReader::new(&b"\xff"[..], "utf-8", "utf-8").unwrap();
+        let result = block_on(reader.read_to_end(&mut vec![]));
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+}
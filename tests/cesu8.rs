@@ -0,0 +1,62 @@
+#[macro_use]
+mod harness;
+mod strings;
+
+use iconv_native::*;
+use strings::*;
+
+const TEST_UTF8_SUPPLEMENTARY: &[u8] = b"\xe8\x8a\x99\xf0\x9f\x98\x80\x00";
+const TEST_CESU8_SUPPLEMENTARY: &[u8] = b"\xe8\x8a\x99\xed\xa0\xbd\xed\xb8\x80\x00";
+const TEST_MUTF8_SUPPLEMENTARY: &[u8] = b"\xe8\x8a\x99\xed\xa0\xbd\xed\xb8\x80\xc0\x80";
+
+with_harness! {
+    fn test_cesu8_convert() {
+        let testcases = [
+            (TEST_UTF8_SUPPLEMENTARY, "utf-8", TEST_CESU8_SUPPLEMENTARY, "cesu-8"),
+            (TEST_UTF8_SUPPLEMENTARY, "utf-8", TEST_MUTF8_SUPPLEMENTARY, "mutf-8"),
+            (TEST_CESU8_SUPPLEMENTARY, "cesu-8", TEST_MUTF8_SUPPLEMENTARY, "modified-utf-8"),
+            (TEST_UTF16_BE, "utf-16be", TEST_UTF8, "cesu-8"),
+            (b"\xd8\x3d\xde\x00", "utf-16be", b"\xed\xa0\xbd\xed\xb8\x80", "mutf-8"),
+        ];
+        for (idx, (input, from_encoding, output, to_encoding)) in testcases.into_iter().enumerate() {
+            let result = convert(input, from_encoding, to_encoding).unwrap();
+            assert_eq!(result, output, "{idx}: {from_encoding} => {to_encoding}");
+            let result = convert(output, to_encoding, from_encoding).unwrap();
+            assert_eq!(result, input, "{idx}: {to_encoding} => {from_encoding}");
+
+            let result = convert_lossy(input, from_encoding, to_encoding).unwrap();
+            assert_eq!(result, output, "{idx}_lossy: {from_encoding} => {to_encoding}");
+        }
+    }
+
+    fn test_cesu8_decode_encode() {
+        let result = decode(TEST_MUTF8_SUPPLEMENTARY, "mutf-8").unwrap();
+        assert_eq!(result, "芙😀\0");
+        let result = encode("芙😀\0", "cesu-8").unwrap();
+        assert_eq!(result, TEST_CESU8_SUPPLEMENTARY);
+        let result = encode_lossy("芙😀\0", "mutf-8").unwrap();
+        assert_eq!(result, TEST_MUTF8_SUPPLEMENTARY);
+    }
+
+    fn test_cesu8_invalid_input() {
+        let testcases = [
+            (TEST_UTF8_SUPPLEMENTARY, "cesu-8"),
+            (TEST_CESU8_SUPPLEMENTARY, "mutf-8"),
+            (b"\xed\xa0\xbd", "cesu-8"),
+        ];
+        for (idx, (input, encoding)) in testcases.into_iter().enumerate() {
+            let result = decode(input, encoding);
+            assert_eq!(result, Err(ConvertError::InvalidInput), "{idx}: {encoding}");
+            let result = decode_lossy(input, encoding).unwrap();
+            assert!(result.contains('\u{fffd}'), "{idx}_lossy: {encoding}");
+        }
+    }
+
+    fn test_cesu8_bom() {
+        let result = decode(b"\xef\xbb\xbfa", "cesu-8").unwrap();
+        assert_eq!(result, "a");
+        let options = ConvertOptions::new().bom_policy(BomPolicy::AlwaysAdd);
+        let result = convert_with_options(b"a", "utf-8", "mutf-8", options).unwrap();
+        assert_eq!(result, b"\xef\xbb\xbfa");
+    }
+}
@@ -0,0 +1,284 @@
+#![cfg(feature = "cli")]
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+fn run(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_iconv-native"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Write from another thread, since the child may block on a full stdout.
+    let mut child_stdin = child.stdin.take().unwrap();
+    let stdin = stdin.to_vec();
+    let writer = thread::spawn(move || child_stdin.write_all(&stdin));
+    let output = child.wait_with_output().unwrap();
+    // The child may exit without reading stdin, such as for an unknown encoding.
+    if let Err(err) = writer.join().unwrap() {
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+    output
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli");
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+#[test]
+fn test_cli_convert_stdin() {
+    let output = run(&["-f", "utf-8", "-t", "utf-16le"], "芙宁娜".as_bytes());
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"\x99\x82\x81\x5b\x1c\x5a");
+}
+
+#[test]
+fn test_cli_invalid_input() {
+    let output = run(&["-f", "utf-8", "-t", "iso-8859-1"], "a€b".as_bytes());
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!output.stderr.is_empty());
+
+    let output = run(&["-s", "-f", "utf-8", "-t", "utf-16le"], b"a\xff");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stderr.is_empty(), "silent");
+}
+
+#[test]
+fn test_cli_fallback() {
+    let testcases = [
+        (
+            &["-c", "-f", "utf-8", "-t", "iso-8859-1"][..],
+            "a€b",
+            &b"ab"[..],
+        ),
+        (&["-f", "utf-8", "-t", "iso-8859-1//IGNORE"], "a€b", b"ab"),
+        (
+            &["-c", "-f", "utf-8", "-t", "utf-16le"],
+            "a\u{fffd}b",
+            b"a\0\xfd\xffb\0",
+        ),
+    ];
+    for (args, input, expected) in testcases {
+        let output = run(args, input.as_bytes());
+        assert_eq!(output.status.code(), Some(0), "{args:?}");
+        assert_eq!(output.stdout, expected, "{args:?}");
+    }
+    for to in ["ascii//TRANSLIT", "iso-8859-1//translit"] {
+        let output = run(&["-f", "utf-8", "-t", to], "a€b".as_bytes());
+        assert_eq!(output.status.code(), Some(0), "{to}");
+        assert!(output.stdout.len() > 2, "{to}");
+        assert!(output.stdout.starts_with(b"a"), "{to}");
+        assert!(output.stdout.ends_with(b"b"), "{to}");
+    }
+
+    // Invalid input is omitted, but U+FFFD in the input is kept.
+    let output = run(&["-c", "-f", "utf-8"], b"a\xef\xbf\xbd\xffb");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, "a\u{fffd}b".as_bytes());
+}
+
+#[test]
+fn test_cli_unknown_encoding() {
+    let output = run(&["-f", "invalid_encoding", "-t", "utf-8"], b"a");
+    assert_eq!(output.status.code(), Some(3));
+    let output = run(&["-c", "-f", "utf-8", "-t", "invalid_encoding"], b"a");
+    assert_eq!(output.status.code(), Some(3), "omit");
+}
+
+#[test]
+fn test_cli_usage() {
+    let output = run(&["--no-such-option"], b"");
+    assert_eq!(output.status.code(), Some(2));
+    let output = run(&["-f"], b"");
+    assert_eq!(output.status.code(), Some(2), "missing value");
+
+    let output = run(&["--help"], b"");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.starts_with(b"Usage:"));
+}
+
+#[test]
+fn test_cli_list() {
+    let output = run(&["-l"], b"");
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.lines().any(|line| line.trim() == "UTF-8"));
+    assert!(stdout.lines().any(|line| line.trim() == "GSM0338"));
+}
+
+#[test]
+fn test_cli_files() {
+    let first = temp_path("files_first.txt");
+    let second = temp_path("files_second.txt");
+    let output_path = temp_path("files_output.txt");
+    fs::write(&first, b"\xdc\xbd").unwrap();
+    fs::write(&second, b"\xc4\xfe\xc4\xc8").unwrap();
+    let output = run(
+        &[
+            "-f",
+            "gb18030",
+            "-t",
+            "utf-8",
+            "-o",
+            output_path.to_str().unwrap(),
+            first.to_str().unwrap(),
+            second.to_str().unwrap(),
+        ],
+        b"",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), "芙宁娜");
+
+    let missing = temp_path("files_missing.txt");
+    let output = run(
+        &[
+            missing.to_str().unwrap(),
+            first.to_str().unwrap(),
+            "-f",
+            "gb18030",
+        ],
+        b"",
+    );
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(
+        output.stdout,
+        "芙".as_bytes(),
+        "continues after missing file"
+    );
+}
+
+#[test]
+fn test_cli_recode() {
+    let dir = temp_path("recode");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    let sjis = dir.join("nested/sjis.txt");
+    let ascii = dir.join("ascii.txt");
+    let invalid = dir.join("invalid.txt");
+    let other = dir.join("other.dat");
+    fs::write(&sjis, b"\x82\xa0").unwrap();
+    fs::write(&ascii, b"plain").unwrap();
+    fs::write(&invalid, b"\x82").unwrap();
+    fs::write(&other, b"\x82\xa0").unwrap();
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let file = File::options().write(true).open(&sjis).unwrap();
+    file.set_modified(mtime).unwrap();
+    drop(file);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&sjis, fs::Permissions::from_mode(0o640)).unwrap();
+        std::os::unix::fs::symlink(&other, dir.join("link.txt")).unwrap();
+    }
+
+    let pattern = format!("{}/**/*.txt", dir.display());
+    let args = ["recode", "-f", "shift_jis", "-t", "utf-8", &pattern];
+    let output = run(&[&args[..], &["--dry-run"]].concat(), b"");
+    assert_eq!(output.status.code(), Some(1), "dry run");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("would convert: "), "{stdout}");
+    assert!(
+        stdout.ends_with("1 to convert, 1 unchanged, 1 skipped, 0 failed\n"),
+        "{stdout}"
+    );
+    assert_eq!(fs::read(&sjis).unwrap(), b"\x82\xa0", "dry run");
+
+    let output = run(&args, b"");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.ends_with("1 converted, 1 unchanged, 1 skipped, 0 failed\n"),
+        "{stdout}"
+    );
+    assert_eq!(fs::read_to_string(&sjis).unwrap(), "あ");
+    assert_eq!(fs::read(&invalid).unwrap(), b"\x82", "skipped");
+    assert_eq!(fs::read(&other).unwrap(), b"\x82\xa0", "not matched");
+    let metadata = fs::metadata(&sjis).unwrap();
+    assert_eq!(metadata.modified().unwrap(), mtime);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o640);
+        let link = fs::symlink_metadata(dir.join("link.txt")).unwrap();
+        assert!(link.file_type().is_symlink(), "symlink replaced");
+    }
+    assert_eq!(
+        fs::read_dir(dir.join("nested")).unwrap().count(),
+        1,
+        "temp file left"
+    );
+}
+
+#[test]
+fn test_cli_recode_usage() {
+    let output = run(&["recode", "*.txt"], b"");
+    assert_eq!(output.status.code(), Some(2), "missing encoding");
+    let output = run(&["recode", "-f", "utf-8"], b"");
+    assert_eq!(output.status.code(), Some(2), "missing pattern");
+    let output = run(&["recode", "-f", "utf-8", "[*.txt"], b"");
+    assert_eq!(output.status.code(), Some(2), "invalid pattern");
+    let output = run(&["recode", "-f", "invalid_encoding", "Cargo.toml"], b"");
+    assert_eq!(output.status.code(), Some(3), "unknown encoding");
+}
+
+#[test]
+fn test_cli_check() {
+    let valid = temp_path("check_valid.txt");
+    let invalid = temp_path("check_invalid.txt");
+    fs::write(&valid, "芙宁娜\n".as_bytes()).unwrap();
+    fs::write(&invalid, b"first\nab\xe5\xae\x81\xffc\n").unwrap();
+    let (valid, invalid) = (valid.to_str().unwrap(), invalid.to_str().unwrap());
+
+    let output = run(&["check", "-f", "utf-8", valid], b"");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, format!("{valid}: ok\n").as_bytes());
+
+    let output = run(&["check", "-q", "-f", "utf-8", valid, invalid], b"");
+    assert_eq!(output.status.code(), Some(1));
+    let expected = format!("{invalid}:2:4: invalid utf-8 sequence at byte offset 11\n");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+
+    let output = run(&["check", "-f", "invalid_encoding"], b"a");
+    assert_eq!(output.status.code(), Some(3), "unknown encoding");
+    let output = run(&["check"], b"a");
+    assert_eq!(output.status.code(), Some(2), "missing encoding");
+}
+
+#[test]
+fn test_cli_detect() {
+    let output = run(&["detect"], b"\xdc\xbd\xc4\xfe\xc4\xc8");
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("-: GBK ("), "{stdout}");
+
+    let output = run(&["detect"], b"\x1b$B$3$s$K$A$O\x1b(B");
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("-: ISO-2022-JP ("), "{stdout}");
+
+    let output = run(&["detect", "--candidates", "utf-8,utf-16le"], b"\xff");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"-: unknown\n");
+}
+
+#[test]
+fn test_cli_chunked_input() {
+    // Larger than a chunk, with sequences split across chunk boundaries.
+    let text = "日本語 a\n".repeat(20_000);
+    let testcases = ["gb18030", "utf-16", "iso-2022-jp", "utf-7"];
+    for encoding in testcases {
+        let input = iconv_native::encode(&text, encoding).unwrap();
+        let output = run(&["-f", encoding, "-t", "utf-8"], &input);
+        assert_eq!(output.status.code(), Some(0), "{encoding}");
+        assert!(output.stdout == text.as_bytes(), "{encoding}");
+    }
+}
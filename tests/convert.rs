@@ -2,6 +2,8 @@ mod strings;
 #[macro_use]
 mod harness;
 
+use std::borrow::Cow;
+
 use iconv_native::*;
 use strings::*;
 
@@ -21,6 +23,17 @@ fn test_convert_success() {
     assert_eq!(result_rev, input, "lossy rev");
 }
 
+// TextEncoder does not support legacy encodings
+#[test]
+fn test_convert_cow_legacy() {
+    let result = convert_cow(b"hello", "gb18030", "euc-kr").unwrap();
+    assert!(matches!(result, Cow::Borrowed(b"hello")));
+
+    let result = convert_cow(TEST_UTF8, "utf-8", "gb18030").unwrap();
+    assert!(matches!(result, Cow::Owned(_)));
+    assert_eq!(result, TEST_GB18030);
+}
+
 with_harness! {
     fn test_convert_roundtrip() {
         let testcases = [
@@ -67,15 +80,15 @@ with_harness! {
             (TEST_UTF8_BOM, &TEST_UTF16_DE_BOM_2[..], "utf-8", "utf-16"),
             (TEST_UTF8, TEST_UTF16_DE_BOM, "utf-8", "utf-16"),
             (TEST_UTF16_DE_BOM, TEST_UTF8, "utf-16", "utf-8"),
-            (TEST_UTF16_DE, TEST_UTF8, "utf-16", "utf-8"),
-            (TEST_UTF16_DE, TEST_UTF32_DE_BOM, "utf-16", "utf-32"),
+            (TEST_UTF16_LE, TEST_UTF8, "utf-16", "utf-8"),
+            (TEST_UTF16_LE, TEST_UTF32_DE_BOM, "utf-16", "utf-32"),
         ];
         let testcases_be_32 = [
             (TEST_UTF8_BOM, &TEST_UTF32_DE_BOM_2[..], "utf-8", "utf-32"),
             (TEST_UTF8, TEST_UTF32_DE_BOM, "utf-8", "utf-32"),
             (TEST_UTF32_DE_BOM, TEST_UTF8, "utf-32", "utf-8"),
-            (TEST_UTF32_DE, TEST_UTF8, "utf-32", "utf-8"),
-            (TEST_UTF32_DE, TEST_UTF16_DE_BOM, "utf-32", "utf-16"),
+            (TEST_UTF32_LE, TEST_UTF8, "utf-32", "utf-8"),
+            (TEST_UTF32_LE, TEST_UTF16_DE_BOM, "utf-32", "utf-16"),
         ];
         let testcases = [
             ("be_16", &testcases_be_16[..]),
@@ -100,6 +113,114 @@ with_harness! {
         }
     }
 
+    fn test_convert_bom_policy() {
+        let testcases = [
+            (TEST_UTF8_BOM, TEST_UTF16_LE, "utf-8", "utf-16le", BomPolicy::Strip),
+            (TEST_UTF16_BE_BOM, TEST_UTF32_LE, "utf-16", "utf-32", BomPolicy::Strip),
+            (TEST_UTF16_BE_BOM, TEST_UTF8_BOM, "utf-16", "utf-8", BomPolicy::Preserve),
+            (TEST_UTF16_BE, TEST_UTF8, "utf-16be", "utf-8", BomPolicy::Preserve),
+            (TEST_UTF8, TEST_UTF8_BOM, "utf-8", "utf-8", BomPolicy::AlwaysAdd),
+            (TEST_UTF8, TEST_UTF32_BE_BOM, "utf-8", "utf-32be", BomPolicy::AlwaysAdd),
+            (TEST_UTF8_BOM, TEST_UTF16_LE_BOM, "utf-8", "utf-16", BomPolicy::AddIfAmbiguous),
+            (TEST_UTF16_LE, TEST_UTF8, "utf-16", "utf-8", BomPolicy::AddIfAmbiguous),
+            (TEST_UTF32_BE_BOM, TEST_UTF16_BE, "utf-32", "utf-16be", BomPolicy::AddIfAmbiguous),
+            (TEST_UTF16_LE_BOM, TEST_UTF16_LE, "utf-16le", "utf-16le", BomPolicy::Strip),
+        ];
+        for (idx, (input, expected, from_encoding, to_encoding, bom_policy)) in
+            testcases.into_iter().enumerate()
+        {
+            let options = ConvertOptions::new().bom_policy(bom_policy);
+            let result = convert_with_options(input, from_encoding, to_encoding, options).unwrap();
+            assert_eq!(
+                result, expected,
+                "{idx}: {from_encoding} => {to_encoding} {bom_policy:?}"
+            );
+
+            let result =
+                convert_lossy_with_options(input, from_encoding, to_encoding, options).unwrap();
+            assert_eq!(
+                result, expected,
+                "{idx}_lossy: {from_encoding} => {to_encoding} {bom_policy:?}"
+            );
+        }
+    }
+
+    fn test_convert_bom_policy_empty_input() {
+        let options = ConvertOptions::new().bom_policy(BomPolicy::AlwaysAdd);
+        let result = convert_with_options(b"", "utf-8", "utf-16be", options);
+        assert_eq!(result, Ok(b"\xfe\xff".to_vec()));
+        let result = convert_with_options(b"", "utf-16", "utf-16", ConvertOptions::default());
+        assert_eq!(result, Ok(b"\xff\xfe".to_vec()));
+    }
+
+    fn test_convert_with_options_invalid() {
+        let options = ConvertOptions::default();
+        let result = convert_with_options(b"\xff 141919", "utf-8", "utf-16", options);
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = convert_with_options(TEST_UTF8, "invalid_encoding", "utf-8", options);
+        assert_eq!(result, Err(ConvertError::UnknownConversion));
+        let result = convert_lossy_with_options(TEST_UTF8, "utf-8", "invalid_encoding", options);
+        assert_eq!(result, Err(ConvertLossyError::UnknownConversion));
+    }
+
+    fn test_convert_default_byte_order() {
+        let testcases = [
+            (TEST_UTF16_LE, "utf-16", DefaultByteOrder::LittleEndian, TEST_UTF8),
+            (TEST_UTF16_BE, "utf-16", DefaultByteOrder::BigEndian, TEST_UTF8),
+            (TEST_UTF16_LE_BOM, "utf-16", DefaultByteOrder::BigEndian, TEST_UTF8),
+            (TEST_UTF32_BE, "utf-32", DefaultByteOrder::BigEndian, TEST_UTF8),
+            (TEST_UTF32_BE, "utf-32", DefaultByteOrder::Guess, TEST_UTF8),
+            (b"\0a\0b", "utf-16", DefaultByteOrder::Guess, b"ab"),
+            (b"a\0b\0", "utf-16", DefaultByteOrder::Guess, b"ab"),
+            (TEST_UTF16_LE, "utf-16", DefaultByteOrder::Guess, TEST_UTF8),
+            (TEST_UTF16_BE, "utf-16be", DefaultByteOrder::LittleEndian, TEST_UTF8),
+        ];
+        for (idx, (input, from_encoding, default_byte_order, expected)) in
+            testcases.into_iter().enumerate()
+        {
+            let options = ConvertOptions::new().default_byte_order(default_byte_order);
+            let result = convert_with_options(input, from_encoding, "utf-8", options).unwrap();
+            assert_eq!(result, expected, "{idx}: {from_encoding} {default_byte_order:?}");
+
+            let result =
+                convert_lossy_with_options(input, from_encoding, "utf-8", options).unwrap();
+            assert_eq!(result, expected, "{idx}_lossy: {from_encoding} {default_byte_order:?}");
+        }
+    }
+
+    fn test_convert_newline_policy() {
+        let testcases = [
+            (&b"a\r\nb\rc\n"[..], "utf-8", &b"a\0\n\0b\0\n\0c\0\n\0"[..], "utf-16le", NewlinePolicy::Lf),
+            (b"\0a\0\r\0b\0\x85", "utf-16be", b"a\r\nb\r\n", "utf-8", NewlinePolicy::CrLf),
+            (b"\0a\0\r\0\n", "utf-16be", b"\0a\0\r\0\n", "utf-16be", NewlinePolicy::Keep),
+            (b"a\r\n\r\n", "utf-8", b"a\n\n", "utf-8", NewlinePolicy::Lf),
+        ];
+        for (idx, (input, from_encoding, expected, to_encoding, newline_policy)) in
+            testcases.into_iter().enumerate()
+        {
+            let options = ConvertOptions::new().newline_policy(newline_policy);
+            let result = convert_with_options(input, from_encoding, to_encoding, options).unwrap();
+            assert_eq!(result, expected, "{idx}: {from_encoding} => {to_encoding}");
+
+            let result =
+                convert_lossy_with_options(input, from_encoding, to_encoding, options).unwrap();
+            assert_eq!(result, expected, "{idx}_lossy: {from_encoding} => {to_encoding}");
+        }
+    }
+
+    #[cfg(feature = "normalization")]
+    fn test_convert_normalization() {
+        let options = ConvertOptions::new()
+            .normalization(Some(NormalizationForm::Nfc))
+            .newline_policy(NewlinePolicy::CrLf);
+        let result = convert_with_options(b"e\0\x01\x03\n\0", "utf-16le", "utf-16be", options);
+        assert_eq!(result, Ok(b"\0\xe9\0\r\0\n".to_vec()));
+
+        let result =
+            convert_lossy_with_options(b"e\0\x01\x03\n\0", "utf-16le", "utf-16be", options);
+        assert_eq!(result, Ok(b"\0\xe9\0\r\0\n".to_vec()), "lossy");
+    }
+
     fn test_convert_same_encoding() {
         let input = TEST_UTF8;
 
@@ -110,6 +231,106 @@ with_harness! {
         assert_eq!(result, input, "lossy");
     }
 
+    fn test_convert_cow_borrowed() {
+        let testcases = [
+            (TEST_UTF8, "utf-8", "utf-8"),
+            (TEST_UTF8_BOM, "UTF8", "utf-8"),
+            (b"hello", "utf-8", "utf-8"),
+            (b"", "gb18030", "utf-8"),
+        ];
+        for (idx, (input, from_encoding, to_encoding)) in testcases.into_iter().enumerate() {
+            let result = convert_cow(input, from_encoding, to_encoding).unwrap();
+            assert!(
+                matches!(result, Cow::Borrowed(_)),
+                "{idx}: {input:?} {from_encoding} => {to_encoding}"
+            );
+            assert_eq!(result, input, "{idx}: {input:?} {from_encoding} => {to_encoding}");
+        }
+    }
+
+    fn test_convert_cow_owned() {
+        let testcases = [
+            (TEST_UTF8, TEST_UTF16_LE, "utf-8", "utf-16le"),
+            (TEST_UTF16_BE, TEST_UTF8, "utf-16be", "utf-8"),
+            (b"hi", b"\0\0\0h\0\0\0i", "utf-8", "utf-32be"),
+        ];
+        for (idx, (input, expected, from_encoding, to_encoding)) in
+            testcases.into_iter().enumerate()
+        {
+            let result = convert_cow(input, from_encoding, to_encoding).unwrap();
+            assert!(
+                matches!(result, Cow::Owned(_)),
+                "{idx}: {input:?} {from_encoding} => {to_encoding}"
+            );
+            assert_eq!(result, expected, "{idx}: {input:?} {from_encoding} => {to_encoding}");
+        }
+    }
+
+    fn test_convert_cow_error() {
+        let result = convert_cow(TEST_GB18030, "utf-8", "utf-8");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = convert_cow(b"hello", "utf-8", "invalid_encoding");
+        assert_eq!(result, Err(ConvertError::UnknownConversion));
+    }
+
+    fn test_convert_into() {
+        let testcases = [
+            (TEST_UTF8, TEST_UTF16_LE, "utf-8", "utf-16le"),
+            (TEST_UTF16_BE_BOM, TEST_UTF32_LE, "utf-16", "utf-32le"),
+            (b"", b"", "utf-8", "utf-16le"),
+        ];
+        for (idx, (input, expected, from_encoding, to_encoding)) in
+            testcases.into_iter().enumerate()
+        {
+            let mut output = [0; 64];
+            let (read, written) =
+                convert_into(input, from_encoding, to_encoding, &mut output).unwrap();
+            assert_eq!(read, input.len(), "{idx}: {input:?} {from_encoding} => {to_encoding}");
+            assert_eq!(
+                &output[..written],
+                expected,
+                "{idx}: {input:?} {from_encoding} => {to_encoding}"
+            );
+        }
+    }
+
+    fn test_convert_into_no_room() {
+        let result = convert_into(TEST_UTF8, "utf-8", "utf-16le", &mut []);
+        assert_eq!(result, Ok((0, 0)));
+    }
+
+    fn test_convert_into_error() {
+        let mut output = [0; 64];
+        let result = convert_into(TEST_GB18030, "utf-8", "utf-16le", &mut output);
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = convert_into(TEST_UTF8, "utf-8", "invalid_encoding", &mut output);
+        assert_eq!(result, Err(ConvertError::UnknownConversion));
+    }
+
+    fn test_max_output_len() {
+        let testcases = [
+            (TEST_UTF8, "utf-8", "utf-16le"),
+            (TEST_UTF8, "utf-8", "utf-32"),
+            (TEST_UTF16_BE_BOM, "utf-16", "utf-8"),
+            (TEST_UTF32_LE, "utf-32le", "utf-16"),
+        ];
+        for (idx, (input, from_encoding, to_encoding)) in testcases.into_iter().enumerate() {
+            let result = convert(input, from_encoding, to_encoding).unwrap();
+            let max_len = max_output_len(from_encoding, to_encoding, input.len()).unwrap();
+            assert!(result.len() <= max_len, "{idx}: {from_encoding} => {to_encoding}");
+        }
+        assert_eq!(max_output_len("invalid_encoding", "utf-8", 1), None);
+    }
+
+    fn test_convert_append() {
+        let mut output = b"prefix".to_vec();
+        convert_append(TEST_UTF16_LE, "utf-16le", "utf-8", &mut output).unwrap();
+        convert_append(TEST_UTF32_BE, "utf-32be", "utf-8", &mut output).unwrap();
+        let result = convert_append(TEST_GB18030, "utf-8", "utf-16le", &mut output);
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        assert_eq!(output, [b"prefix", TEST_UTF8, TEST_UTF8].concat());
+    }
+
     fn test_convert_empty_input() {
         let result = convert("", "utf-8", "utf-16le");
         let result_lossy = convert_lossy("", "utf-8", "utf-16le");
@@ -138,10 +359,33 @@ with_harness! {
         assert_eq!(result, Err(ConvertLossyError::UnknownConversion));
     }
 
+    // Before `DefaultByteOrder`, BOM-less input was treated as big-endian with iconv.
+    fn test_convert_big_endian() {
+        let testcases = [
+            (TEST_UTF16_BE, TEST_UTF8, "utf-16", "utf-8"),
+            (TEST_UTF16_BE, TEST_UTF32_LE_BOM, "utf-16", "utf-32"),
+            (TEST_UTF32_BE, TEST_UTF8, "utf-32", "utf-8"),
+            (TEST_UTF32_BE, TEST_UTF16_LE_BOM, "utf-32", "utf-16"),
+        ];
+        let options = ConvertOptions::new().default_byte_order(DefaultByteOrder::BigEndian);
+        for (idx, (input, expected, from_encoding, to_encoding)) in
+            testcases.into_iter().enumerate()
+        {
+            let result = convert_with_options(input, from_encoding, to_encoding, options).unwrap();
+            assert_eq!(result, expected, "{idx}: {from_encoding} => {to_encoding}");
+        }
+
+        let result = convert_with_options(&TEST_UTF16_BE[..3], "utf-16", "utf-8", options);
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result =
+            convert_lossy_with_options(&TEST_UTF16_BE[..3], "utf-16", "utf-8", options).unwrap();
+        assert!(result.starts_with(b"\xe8\x8a\x99"), "lossy");
+    }
+
     fn test_convert_invalid_input() {
         let testcases = [
             (&b"b\xffaa"[..], "utf-8", "utf-16", &b"a"[..]),
-            (&TEST_UTF16_DE[..3], "utf-16", "utf-8", b"\xe8\x8a\x99"),
+            (&TEST_UTF16_LE[..3], "utf-16", "utf-8", b"\xe8\x8a\x99"),
             (b"\0\xd8\x99\x82", "utf-16le", "utf-32", b"\x99\x82"),
             (b"\xff\xdc\xbd", "gb18030", "utf-8", b"\xe8\x8a\x99"),
         ];
@@ -163,6 +407,78 @@ with_harness! {
         }
     }
 
+    // Only iconv is able to report partial progress.
+    #[cfg(not(all(windows, feature = "win32")))]
+    #[cfg(not(all(target_arch = "wasm32", feature = "web-encoding")))]
+    fn test_convert_into_partial() {
+        let mut input = TEST_UTF8;
+        let mut output = vec![];
+        let mut buf = [0; 3];
+        while !input.is_empty() {
+            let (read, written) = convert_into(input, "utf-8", "utf-16be", &mut buf).unwrap();
+            assert_eq!((read, written), (3, 2));
+            output.extend_from_slice(&buf[..written]);
+            input = &input[read..];
+        }
+        assert_eq!(output, TEST_UTF16_BE);
+    }
+
+    fn test_convert_into_bom() {
+        let testcases = [
+            (TEST_UTF8, "utf-8", "utf-16"),
+            (TEST_UTF8, "utf-8", "utf-32"),
+            (TEST_UTF16_LE_BOM, "utf-16", "utf-8"),
+            (TEST_UTF8, "utf-8", "utf-16le"),
+        ];
+        for (idx, (mut input, from_encoding, to_encoding)) in testcases.into_iter().enumerate() {
+            let expected = convert(input, from_encoding, to_encoding).unwrap();
+            let mut output = vec![];
+            let mut buf = [0; 8];
+            while !input.is_empty() {
+                let (read, written) =
+                    convert_into(input, from_encoding, to_encoding, &mut buf).unwrap();
+                if (read, written) == (0, 0) {
+                    output.extend(convert(input, from_encoding, to_encoding).unwrap());
+                    break;
+                }
+                output.extend_from_slice(&buf[..written]);
+                input = &input[read..];
+            }
+            assert_eq!(output, expected, "{idx}: {from_encoding} => {to_encoding}");
+        }
+    }
+
+    fn test_convert_into_stateful() {
+        let testcases = [
+            ("日本語テキストabc".as_bytes(), "utf-8", "iso-2022-jp"),
+            (b"\x1b$BF|K\\8l%F%-%9%H\x1b(Babc", "iso-2022-jp", "utf-8"),
+            (b"+ZeVnLIqe-abc", "utf-7", "utf-8"),
+        ];
+        for (idx, (input, from_encoding, to_encoding)) in testcases.into_iter().enumerate() {
+            let expected = convert(input, from_encoding, to_encoding).unwrap();
+            let mut buf = [0; 6];
+            let result = convert_into(input, from_encoding, to_encoding, &mut buf);
+            assert_eq!(result, Ok((0, 0)), "{idx}: {from_encoding} => {to_encoding}");
+            let mut buf = vec![0; expected.len()];
+            let result = convert_into(input, from_encoding, to_encoding, &mut buf);
+            assert_eq!(result, Ok((input.len(), expected.len())), "{idx}");
+            assert_eq!(buf, expected, "{idx}: {from_encoding} => {to_encoding}");
+        }
+    }
+
+    // Only glibc decomposes characters when encoding to CP1255 and CP1258.
+    #[cfg(not(all(windows, feature = "win32")))]
+    #[cfg(not(all(target_arch = "wasm32", feature = "web-encoding")))]
+    fn test_max_output_len_decomposition() {
+        let testcases = [("\u{fb2c}", "windows-1255"), ("ÃẤ", "windows-1258")];
+        for (idx, (input, to_encoding)) in testcases.into_iter().enumerate() {
+            let input = encode(input, "utf-16le").unwrap();
+            let result = convert(&input, "utf-16le", to_encoding).unwrap();
+            let max_len = max_output_len("utf-16le", to_encoding, input.len()).unwrap();
+            assert!(result.len() <= max_len, "{idx}: {to_encoding}");
+        }
+    }
+
     // `WideCharToMultiByte` supports a `WC_ERR_INVALID_CHARS` flag, but it only works for UTF-8 and
     // GB18030. Really don't know how to let it fail.
     #[cfg(not(all(windows, feature = "win32")))]
@@ -2,21 +2,32 @@
 mod harness;
 mod strings;
 
+use std::borrow::Cow;
+
 use iconv_native::*;
 use strings::*;
 
 with_harness! {
+    fn test_decode_newline_ebcdic() {
+        let options = ConvertOptions::new().newline_policy(NewlinePolicy::CrLf);
+        let result = decode_with_options(b"\xc1\x15\xc2\x25\xc3\x0d", "ibm037", options).unwrap();
+        assert_eq!(result, "A\r\nB\r\nC\r\n");
+
+        let result = decode_lossy_with_options(b"\xc1\x15\xc2\x25\xc3\x0d", "ibm037", options).unwrap();
+        assert_eq!(result, "A\r\nB\r\nC\r\n", "lossy");
+    }
+
     fn test_decode_success() {
         let testcases = [
             (TEST_UTF8, "utf-8"),
             (TEST_UTF8_BOM, "utf-8"),
-            (TEST_UTF16_DE, "utf-16"),
+            (TEST_UTF16_LE, "utf-16"),
             (TEST_UTF16_BE_BOM, "utf-16"),
             (TEST_UTF16_LE, "utf-16le"),
             (TEST_UTF16_BE, "utf-16be"),
             (TEST_UTF16_LE_BOM, "utf-16le"),
             (TEST_UTF16_BE_BOM, "utf-16be"),
-            (TEST_UTF32_DE, "utf-32"),
+            (TEST_UTF32_LE, "utf-32"),
             (TEST_UTF32_BE_BOM, "utf-32"),
             (TEST_UTF32_LE, "utf-32le"),
             (TEST_UTF32_BE, "utf-32be"),
@@ -33,6 +44,19 @@ with_harness! {
         }
     }
 
+    // Before `DefaultByteOrder`, BOM-less input was treated as big-endian with iconv.
+    fn test_decode_big_endian() {
+        let testcases = [(TEST_UTF16_BE, "utf-16"), (TEST_UTF32_BE, "utf-32")];
+        let options = ConvertOptions::new().default_byte_order(DefaultByteOrder::BigEndian);
+        for (idx, (input, encoding)) in testcases.into_iter().enumerate() {
+            let result = decode_with_options(input, encoding, options).unwrap();
+            assert_eq!(result, "芙宁娜", "{idx}: {encoding}");
+
+            let result = decode_lossy_with_options(input, encoding, options).unwrap();
+            assert_eq!(result, "芙宁娜", "{idx}_lossy: {encoding}");
+        }
+    }
+
     fn test_decode_empty_input() {
         let result = decode("", "utf-16le");
         let result_lossy = decode_lossy("", "utf-16le");
@@ -56,4 +80,118 @@ with_harness! {
         let result = decode_lossy(TEST_GB18030, "invalid_encoding");
         assert_eq!(result, Err(ConvertLossyError::UnknownConversion));
     }
+
+    fn test_decode_cow_borrowed() {
+        let testcases = [
+            (TEST_UTF8, "utf-8", "芙宁娜"),
+            (TEST_UTF8_BOM, "utf-8", "芙宁娜"),
+            (b"hello", "gb18030", "hello"),
+            (b"hello", "windows-1252", "hello"),
+            (b"", "euc-jp", ""),
+        ];
+        for (idx, (input, encoding, expected)) in testcases.into_iter().enumerate() {
+            let result = decode_cow(input, encoding).unwrap();
+            assert!(matches!(result, Cow::Borrowed(_)), "{idx}: {input:?} {encoding}");
+            assert_eq!(result, expected, "{idx}: {input:?} {encoding}");
+        }
+    }
+
+    fn test_decode_cow_owned() {
+        let testcases = [
+            (TEST_GB18030, "gb18030"),
+            (TEST_UTF16_LE, "utf-16le"),
+            (TEST_UTF16_BE_BOM, "utf-16"),
+            (TEST_UTF32_LE, "utf-32le"),
+        ];
+        for (idx, (input, encoding)) in testcases.into_iter().enumerate() {
+            let result = decode_cow(input, encoding).unwrap();
+            assert!(matches!(result, Cow::Owned(_)), "{idx}: {input:?} {encoding}");
+            assert_eq!(result, "芙宁娜", "{idx}: {input:?} {encoding}");
+        }
+    }
+
+    fn test_decode_cow_error() {
+        let result = decode_cow(TEST_GB18030, "utf-8");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = decode_cow(b"hello", "invalid_encoding");
+        assert_eq!(result, Err(ConvertError::UnknownConversion));
+    }
+
+    fn test_decode_with_options() {
+        let lf = ConvertOptions::new().newline_policy(NewlinePolicy::Lf);
+        let testcases = [
+            (&b"a\r\nb\rc\xc2\x85"[..], "utf-8", lf, "a\nb\nc\n"),
+            (b"\xef\xbb\xbfa\r", "utf-8", lf, "a\n"),
+            (b"\0a\0\r\0b", "utf-16be", lf, "a\nb"),
+            (b"\xff\xfea\0\r\0\n\0", "utf-16", lf, "a\n"),
+            (b"a\0\0\0\x85\0\0\0", "utf-32le", lf, "a\n"),
+            (b"a\r\n", "utf-8", ConvertOptions::default(), "a\r\n"),
+            (
+                b"\xfe\xff\0a",
+                "utf-16",
+                ConvertOptions::new().bom_policy(BomPolicy::Preserve),
+                "\u{feff}a",
+            ),
+        ];
+        for (idx, (input, encoding, options, expected)) in testcases.into_iter().enumerate() {
+            let result = decode_with_options(input, encoding, options).unwrap();
+            assert_eq!(result, expected, "{idx}: {encoding}");
+
+            let result = decode_lossy_with_options(input, encoding, options).unwrap();
+            assert_eq!(result, expected, "{idx}_lossy: {encoding}");
+        }
+    }
+
+    fn test_decode_with_options_error() {
+        let options = ConvertOptions::new().newline_policy(NewlinePolicy::Lf);
+        let result = decode_with_options(b"a\r\xff", "utf-8", options);
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = decode_lossy_with_options(b"a\r\xff", "utf-8", options).unwrap();
+        assert!(result.starts_with("a\n"), "lossy");
+        let result = decode_with_options(b"a", "invalid_encoding", options);
+        assert_eq!(result, Err(ConvertError::UnknownConversion));
+    }
+
+    fn test_decode_with_options_long() {
+        // Sequences and line endings across the boundaries of the chunks decoded at a time.
+        let options = ConvertOptions::new().newline_policy(NewlinePolicy::Lf);
+        let text = "a".repeat(4095) + "\r\nb";
+        let input: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let result = decode_with_options(&input, "utf-16le", options).unwrap();
+        assert_eq!(result, "a".repeat(4095) + "\nb");
+
+        let mut input = b"a".repeat(8191);
+        input.extend_from_slice(b"\x82\xa0\r");
+        let result = decode_with_options(&input, "shift_jis", options).unwrap();
+        assert_eq!(result, "a".repeat(8191) + "\u{3042}\n");
+
+        input.extend_from_slice(b"\x82");
+        let result = decode_with_options(&input, "shift_jis", options);
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+    }
+
+    #[cfg(feature = "normalization")]
+    fn test_decode_normalization() {
+        let testcases = [
+            (&b"e\xcc\x81"[..], "utf-8", NormalizationForm::Nfc, "\u{e9}"),
+            (b"\xe9", "iso-8859-1", NormalizationForm::Nfd, "e\u{301}"),
+            (b"\x5c\xd5", "utf-16le", NormalizationForm::Nfd, "\u{1112}\u{1161}\u{11ab}"),
+            (b"\0\0\xff\x21", "utf-32be", NormalizationForm::Nfkc, "A"),
+        ];
+        for (idx, (input, encoding, form, expected)) in testcases.into_iter().enumerate() {
+            let options = ConvertOptions::new().normalization(Some(form));
+            let result = decode_with_options(input, encoding, options).unwrap();
+            assert_eq!(result, expected, "{idx}: {encoding}");
+
+            let result = decode_lossy_with_options(input, encoding, options).unwrap();
+            assert_eq!(result, expected, "{idx}_lossy: {encoding}");
+        }
+
+        // A combining sequence across the boundary of the chunks decoded at a time.
+        let text = "a".repeat(4095) + "e\u{301}";
+        let input: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let options = ConvertOptions::new().normalization(Some(NormalizationForm::Nfc));
+        let result = decode_with_options(&input, "utf-16le", options).unwrap();
+        assert_eq!(result, "a".repeat(4095) + "\u{e9}");
+    }
 }
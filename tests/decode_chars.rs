@@ -0,0 +1,124 @@
+#[macro_use]
+mod harness;
+mod strings;
+
+use iconv_native::*;
+use strings::*;
+
+fn collect_chars(input: &[u8], encoding: &str) -> String {
+    decode_chars(input, encoding)
+        .unwrap()
+        .map(|item| item.unwrap().1)
+        .collect()
+}
+
+with_harness! {
+    fn test_decode_chars() {
+        let testcases = [
+            (TEST_UTF8, "utf-8"),
+            (TEST_UTF8_BOM, "utf-8"),
+            (TEST_GB18030, "gb18030"),
+            (TEST_UTF16_LE_BOM, "utf-16"),
+            (TEST_UTF16_BE_BOM, "utf-16"),
+            (TEST_UTF16_LE_BOM, "utf-16le"),
+            (TEST_UTF32_BE, "utf-32be"),
+            (TEST_UTF32_BE_BOM, "utf-32"),
+        ];
+        let expected = std::str::from_utf8(TEST_UTF8).unwrap();
+        for (idx, (input, encoding)) in testcases.into_iter().enumerate() {
+            let result = collect_chars(input, encoding);
+            assert_eq!(result, expected, "{idx}: {encoding}");
+        }
+    }
+
+    fn test_decode_chars_stateful() {
+        let testcases = [
+            (&b"a\x1b$B$\"$$\x1b(Bb"[..], "iso-2022-jp"),
+            (b"a+ZYJnLIqe-b", "utf-7"),
+            (b"+2D3eAA-", "utf-7"),
+            (b"&ZYJnLIqe-", "utf-7-imap"),
+            (b"\x00\xc8\x06\xb5\x29\x6f\x52\x7f", "gsm0338-packed"),
+        ];
+        for (idx, (input, encoding)) in testcases.into_iter().enumerate() {
+            let expected = decode(input, encoding).unwrap();
+            let result = collect_chars(input, encoding);
+            assert_eq!(result, expected, "{idx}: {encoding}");
+        }
+    }
+
+    fn test_decode_chars_clone() {
+        let mut chars = decode_chars(b"\x1b$B$\"$$\x1b(Bb", "iso-2022-jp").unwrap();
+        assert_eq!(chars.next(), Some(Ok((0, 'あ'))));
+        // The clone continues in the shifted state.
+        let rest: Vec<_> = chars.clone().collect();
+        assert_eq!(rest, [Ok((5, 'い')), Ok((7, 'b'))]);
+        assert_eq!(chars.collect::<Vec<_>>(), rest);
+    }
+
+    fn test_decode_chars_positions() {
+        let chars: Vec<_> = decode_chars(b"\xff\xfea\x00=\xd8\x00\xdeb\x00", "utf-16")
+            .unwrap()
+            .collect();
+        assert_eq!(chars, [Ok((2, 'a')), Ok((4, '😀')), Ok((8, 'b'))]);
+
+        let chars: Vec<_> = decode_chars("a😀é".as_bytes(), "utf-8").unwrap().collect();
+        assert_eq!(chars, [Ok((0, 'a')), Ok((1, '😀')), Ok((5, 'é'))]);
+
+        let chars: Vec<_> = decode_chars(b"a\x82\xb3b", "shift_jis").unwrap().collect();
+        assert_eq!(chars, [Ok((0, 'a')), Ok((1, 'さ')), Ok((3, 'b'))]);
+    }
+
+    fn test_decode_chars_early_stop() {
+        // The invalid sequence at the end is never reached.
+        let mut chars = decode_chars(b"\xdc\xbd\xc4\xfe\xc4\xc8\xff", "gb18030").unwrap();
+        assert_eq!(chars.next(), Some(Ok((0, '芙'))));
+        assert_eq!(chars.position(), 2);
+    }
+
+    fn test_decode_chars_invalid() {
+        let mut chars = decode_chars(b"\xdc\xbd\xff\xc4\xc8", "gb18030").unwrap();
+        assert_eq!(chars.next(), Some(Ok((0, '芙'))));
+        assert_eq!(chars.next(), Some(Err(ConvertError::InvalidInput)));
+        assert_eq!(chars.next(), None);
+        assert_eq!(chars.position(), 2);
+
+        let mut chars = decode_chars(b"abc\xe8\x8a", "utf-8").unwrap();
+        assert_eq!(chars.nth(3), Some(Err(ConvertError::InvalidInput)));
+        assert_eq!(chars.position(), 3);
+
+        let input = b"abc\xe8\x8a\xffdef";
+        let result: String = decode_chars_lossy(input, "utf-8")
+            .unwrap()
+            .map(|item| item.unwrap().1)
+            .collect();
+        assert_eq!(result, decode_lossy(input, "utf-8").unwrap());
+    }
+
+    fn test_decode_chars_lossy_code_units() {
+        let testcases = [
+            (&b"a\x00\x00\xd8b\x00c\x00"[..], "utf-16le", 2),
+            (b"\xfe\xff\x00a\xdc\x00\x00b\x00c", "utf-16", 2),
+            (b"a\x00\x00\xd8b\x00c\x00", "ucs-2le", 2),
+            (b"a\x00\x00\x00\x00\x00\x11\x00b\x00\x00\x00c\x00\x00\x00", "utf-32le", 4),
+        ];
+        for (input, encoding, unit_len) in testcases {
+            let chars: Vec<_> = decode_chars_lossy(input, encoding)
+                .unwrap()
+                .map(|item| item.unwrap())
+                .collect();
+            let result: String = chars.iter().map(|(_, char)| char).collect();
+            assert_eq!(result, decode_lossy(input, encoding).unwrap(), "{encoding}");
+            assert!(result.ends_with("bc"), "{encoding}");
+            for (offset, char) in chars {
+                assert_eq!(offset % unit_len, 0, "{encoding}: {char:?} at {offset}");
+            }
+        }
+    }
+
+    fn test_decode_chars_unknown_encoding() {
+        let result = decode_chars(b"a", "invalid_encoding").unwrap_err();
+        assert_eq!(result, ConvertError::UnknownConversion);
+        let result = decode_chars_lossy(b"a", "invalid_encoding").unwrap_err();
+        assert_eq!(result, ConvertLossyError::UnknownConversion);
+    }
+}
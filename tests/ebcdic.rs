@@ -0,0 +1,57 @@
+#[macro_use]
+mod harness;
+mod strings;
+
+use iconv_native::*;
+
+// "Grüße, 5€!" in IBM01141, the German code page with the euro sign.
+const TEST_UTF8_GERMAN: &str = "Grüße, 5€!";
+const TEST_IBM1141: &[u8] = b"\xc7\x99\xd0\xa1\x85\x6b\x40\xf5\x9f\x4f";
+
+with_harness! {
+    fn test_ebcdic_convert() {
+        let testcases = [
+            (&b"Hello, World!"[..], "utf-8", &b"\xc8\x85\x93\x93\x96\x6b\x40\xe6\x96\x99\x93\x84\x5a"[..], "IBM037"),
+            (b"[1]", "us-ascii", b"\x4a\xf1\x5a", "cp500"),
+            (b"[1]", "us-ascii", b"\xad\xf1\xbd", "IBM-1047"),
+            (TEST_UTF8_GERMAN.as_bytes(), "utf-8", TEST_IBM1141, "IBM01141"),
+            (b"\xc1\x15", "ibm037", b"\xc1\x15", "ibm1140"),
+        ];
+        for (idx, (input, from_encoding, output, to_encoding)) in testcases.into_iter().enumerate() {
+            let result = convert(input, from_encoding, to_encoding).unwrap();
+            assert_eq!(result, output, "{idx}: {from_encoding} => {to_encoding}");
+            let result = convert(output, to_encoding, from_encoding).unwrap();
+            assert_eq!(result, input, "{idx}: {to_encoding} => {from_encoding}");
+
+            let result = convert_lossy(input, from_encoding, to_encoding).unwrap();
+            assert_eq!(result, output, "{idx}_lossy: {from_encoding} => {to_encoding}");
+        }
+    }
+
+    fn test_ebcdic_unrepresentable() {
+        let result = encode("5€", "ibm273");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = encode_lossy("5€", "ibm273").unwrap();
+        assert_eq!(result, b"\xf5\x3f");
+        let result = decode(TEST_IBM1141, "ibm273").unwrap();
+        assert_eq!(result, "Grüße, 5¤!");
+    }
+
+    fn test_ebcdic_newline() {
+        let input = b"\xc1\x15\xc2\x25";
+        let result = decode(input, "ibm1047").unwrap();
+        assert_eq!(result, "A\u{85}B\n");
+        let result = decode(input, "ibm1047,swaps").unwrap();
+        assert_eq!(result, "A\nB\u{85}");
+        let result = decode(input, "ibm1047,swaplfnl").unwrap();
+        assert_eq!(result, "A\nB\u{85}");
+
+        let options = ConvertOptions::new().ebcdic_newline(EbcdicNewline::Swapped);
+        let result = decode_with_options(input, "ibm1047", options).unwrap();
+        assert_eq!(result, "A\nB\u{85}");
+        let result = convert_with_options(b"A\nB", "utf-8", "cp1047", options).unwrap();
+        assert_eq!(result, b"\xc1\x15\xc2");
+        let result = convert_with_options(input, "ibm037", "ibm1047", options).unwrap();
+        assert_eq!(result, input);
+    }
+}
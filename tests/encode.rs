@@ -0,0 +1,84 @@
+#[macro_use]
+mod harness;
+mod strings;
+
+use iconv_native::*;
+use strings::*;
+
+// TextEncoder does not support legacy encodings
+#[test]
+fn test_encode_legacy() {
+    let result = encode("芙宁娜", "gb18030").unwrap();
+    assert_eq!(result, TEST_GB18030);
+
+    let result = encode_lossy("芙宁娜", "gb18030").unwrap();
+    assert_eq!(result, TEST_GB18030, "lossy");
+}
+
+with_harness! {
+    fn test_encode_success() {
+        let testcases = [
+            (TEST_UTF8, "utf-8"),
+            (TEST_UTF16_LE, "utf-16le"),
+            (TEST_UTF16_BE, "utf-16be"),
+            (TEST_UTF16_DE_BOM, "utf-16"),
+            (TEST_UTF32_LE, "utf-32le"),
+            (TEST_UTF32_BE, "utf-32be"),
+            (TEST_UTF32_DE_BOM, "utf-32"),
+        ];
+        for (idx, (expected, encoding)) in testcases.into_iter().enumerate() {
+            let result = encode("芙宁娜", encoding).unwrap();
+            assert_eq!(result, expected, "{idx}: {encoding}");
+
+            let result = encode_lossy("芙宁娜", encoding).unwrap();
+            assert_eq!(result, expected, "{idx}_lossy: {encoding}");
+        }
+    }
+
+    fn test_encode_bom() {
+        let testcases = [
+            (TEST_UTF8_BOM, "utf-8"),
+            (TEST_UTF16_LE_BOM, "utf-16le"),
+            (TEST_UTF16_DE_BOM_2, "utf-16"),
+            (TEST_UTF32_BE_BOM, "utf-32be"),
+        ];
+        for (idx, (expected, encoding)) in testcases.into_iter().enumerate() {
+            let result = encode("\u{feff}芙宁娜", encoding).unwrap();
+            assert_eq!(result, expected, "{idx}: {encoding}");
+        }
+    }
+
+    fn test_encode_decode_roundtrip() {
+        for encoding in ["utf-8", "utf-16", "utf-16be", "utf-32", "utf-32le"] {
+            let encoded = encode("芙宁娜", encoding).unwrap();
+            let result = decode(encoded, encoding).unwrap();
+            assert_eq!(result, "芙宁娜", "{encoding}");
+        }
+    }
+
+    fn test_encode_empty_input() {
+        let result = encode("", "utf-16le");
+        let result_lossy = encode_lossy("", "utf-16le");
+        assert_eq!(result, Ok(vec![]), "utf-16le");
+        assert_eq!(result_lossy, Ok(vec![]), "utf-16le lossy");
+    }
+
+    fn test_encode_invalid_encoding() {
+        let result = encode("芙宁娜", "invalid_encoding");
+        assert_eq!(result, Err(ConvertError::UnknownConversion));
+        let result = encode_lossy("芙宁娜", "invalid_encoding");
+        assert_eq!(result, Err(ConvertLossyError::UnknownConversion));
+    }
+
+    // `WideCharToMultiByte` supports a `WC_ERR_INVALID_CHARS` flag, but it only works for UTF-8 and
+    // GB18030. Really don't know how to let it fail.
+    #[cfg(not(all(windows, feature = "win32")))]
+    // A standard TextEncoder does not support legacy encodings.
+    #[cfg(not(all(target_arch = "wasm32", feature = "web-encoding")))]
+    fn test_encode_out_of_range() {
+        let result = encode("🤣b", "iso-8859-1");
+        let result_lossy = encode_lossy("🤣b", "iso-8859-1").unwrap();
+        assert_eq!(result.unwrap_err(), ConvertError::InvalidInput);
+        assert!(result_lossy.contains(&b'b'), "lossy");
+    }
+}
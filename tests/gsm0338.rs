@@ -0,0 +1,51 @@
+#[macro_use]
+mod harness;
+mod strings;
+
+use iconv_native::*;
+
+const TEST_GSM0338: &[u8] = b"\x00\x10\x1b\x28\x1b\x65\x1b\x29\x7f";
+const TEST_GSM0338_PACKED: &[u8] = b"\x00\xc8\x06\xb5\x29\x6f\x52\x7f";
+const TEST_UTF8_GSM0338: &str = "@Δ{€}à";
+
+with_harness! {
+    fn test_gsm0338_convert() {
+        let testcases = [
+            (TEST_UTF8_GSM0338.as_bytes(), "utf-8", TEST_GSM0338, "gsm0338"),
+            (TEST_UTF8_GSM0338.as_bytes(), "utf-8", TEST_GSM0338_PACKED, "GSM-03.38-PACKED"),
+            (TEST_GSM0338, "gsm-03.38", TEST_GSM0338_PACKED, "gsm0338-packed"),
+        ];
+        for (idx, (input, from_encoding, output, to_encoding)) in testcases.into_iter().enumerate() {
+            let result = convert(input, from_encoding, to_encoding).unwrap();
+            assert_eq!(result, output, "{idx}: {from_encoding} => {to_encoding}");
+            let result = convert(output, to_encoding, from_encoding).unwrap();
+            assert_eq!(result, input, "{idx}: {to_encoding} => {from_encoding}");
+
+            let result = convert_lossy(input, from_encoding, to_encoding).unwrap();
+            assert_eq!(result, output, "{idx}_lossy: {from_encoding} => {to_encoding}");
+        }
+    }
+
+    fn test_gsm0338_decode_encode() {
+        let result = decode(TEST_GSM0338_PACKED, "gsm0338-packed").unwrap();
+        assert_eq!(result, TEST_UTF8_GSM0338);
+        let result = encode(TEST_UTF8_GSM0338, "gsm0338").unwrap();
+        assert_eq!(result, TEST_GSM0338);
+        let result = count_gsm0338_septets(TEST_UTF8_GSM0338);
+        assert_eq!(result, Some(TEST_GSM0338.len()));
+    }
+
+    fn test_gsm0338_invalid_input() {
+        let result = encode("a中", "gsm0338");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = encode_lossy("a中", "gsm0338").unwrap();
+        assert_eq!(result, b"a?");
+
+        let result = decode(b"a\x80", "gsm0338");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = decode_lossy(b"a\x80", "gsm0338").unwrap();
+        assert_eq!(result, "a\u{fffd}");
+        let result = convert(b"a\x1b", "gsm0338", "utf-16le");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+    }
+}
@@ -0,0 +1,42 @@
+#[macro_use]
+mod harness;
+mod strings;
+
+use iconv_native::*;
+use strings::*;
+
+with_harness! {
+    fn test_idna_to_ascii() {
+        let gb18030_domain = [TEST_GB18030, b".example"].concat();
+        let utf16_domain = convert("Bücher。Example.", "utf-8", "utf-16le").unwrap();
+        let testcases = [
+            (&gb18030_domain[..], "gb18030", "xn--pzs55a217f.example"),
+            (&utf16_domain, "utf-16le", "xn--bcher-kva.example."),
+            (b"WWW.Example.com", "us-ascii", "www.example.com"),
+        ];
+        for (idx, (input, encoding, expected)) in testcases.into_iter().enumerate() {
+            let result = idna::to_ascii(input, encoding).unwrap();
+            assert_eq!(result, expected, "{idx}: {encoding}");
+        }
+    }
+
+    fn test_idna_from_ascii() {
+        let result = idna::from_ascii("XN--pzs55a217f.example", "utf-8").unwrap();
+        assert_eq!(result, "芙宁娜.example".as_bytes());
+        let result = idna::to_unicode("www.xn--bcher-kva.example").unwrap();
+        assert_eq!(result, "www.bücher.example");
+    }
+
+    fn test_idna_invalid() {
+        let result = idna::to_ascii("a..b", "utf-8");
+        assert_eq!(result, Err(IdnaError::InvalidLabel));
+        let result = idna::to_ascii("a".repeat(64), "utf-8");
+        assert_eq!(result, Err(IdnaError::InvalidLabel));
+        let result = idna::to_ascii(b"\xff.example", "utf-8");
+        assert_eq!(result, Err(IdnaError::Convert(ConvertError::InvalidInput)));
+        let result = idna::to_unicode("xn--bcher-kv.example");
+        assert_eq!(result, Err(IdnaError::InvalidPunycode));
+        let result = idna::from_ascii("xn--pzs55a217f.example", "us-ascii");
+        assert_eq!(result, Err(IdnaError::Convert(ConvertError::InvalidInput)));
+    }
+}
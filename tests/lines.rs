@@ -0,0 +1,74 @@
+#![cfg(feature = "std")]
+
+#[macro_use]
+mod harness;
+
+use std::io::{self, BufRead, BufReader, Read};
+
+use iconv_native::*;
+
+/// Reads one byte at a time, failing at the end of the input if `fail` is set.
+struct Trickle<'a> {
+    input: &'a [u8],
+    fail: bool,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.input.is_empty() && self.fail {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        let len = buf.len().min(self.input.len()).min(1);
+        buf[..len].copy_from_slice(&self.input[..len]);
+        self.input = &self.input[len..];
+        Ok(len)
+    }
+}
+
+fn lines(reader: impl BufRead, encoding: &str) -> Vec<Result<String, LinesError>> {
+    DecodedLines::new(reader, encoding).unwrap().collect()
+}
+
+with_harness! {
+    fn test_decoded_lines() {
+        let testcases = [
+            (&b"a\nb\r\n\nc"[..], "utf-8", &["a", "b", "", "c"][..]),
+            (b"a\nb\n", "utf-8", &["a", "b"]),
+            (b"", "utf-8", &[]),
+            (b"\xfe\xff\0a\x0a\x62\0\n\0b", "utf-16", &["a\u{a62}", "b"]),
+            (b"\xc1\x25\xc2\x0d\x25", "ibm1047", &["A", "B"]),
+            (b"\xc1\x15\xc2\x0d\x15\xc3", "ibm037", &["A", "B\r", "C"]),
+            (b"a\xc2\x85b\r\n", "utf-8", &["a", "b"]),
+            (b"\x1b$B$\"\n$$\x1b(B\n", "iso-2022-jp", &["あ", "い"]),
+            (b"\x82\xa0\n\x82\xa2", "shift_jis", &["あ", "い"]),
+        ];
+        for (input, encoding, expected) in testcases {
+            let reader = BufReader::new(Trickle { input, fail: false });
+            let result: Vec<_> = lines(reader, encoding).into_iter().map(Result::unwrap).collect();
+            assert_eq!(result, expected, "{encoding}");
+            let result: Vec<_> = lines(input, encoding).into_iter().map(Result::unwrap).collect();
+            assert_eq!(result, expected, "{encoding} at once");
+        }
+    }
+
+    fn test_decoded_lines_invalid() {
+        let result = lines(&b"a\nb\xffc\nd\n"[..], "utf-8");
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].as_ref().unwrap(), "a");
+        assert!(matches!(result[1], Err(LinesError::Convert(ConvertError::InvalidInput))));
+
+        let input = b"a\nb\xffc\n\xe8";
+        let reader = DecodedLines::new_lossy(&input[..], "utf-8").unwrap();
+        let result: Vec<_> = reader.map(Result::unwrap).collect();
+        let expected = decode_lossy(input, "utf-8").unwrap();
+        assert_eq!(result, expected.lines().collect::<Vec<_>>());
+
+        let reader = BufReader::new(Trickle { input: b"a\nbc\nd", fail: true });
+        let result = lines(reader, "utf-8");
+        assert_eq!(result.len(), 3, "{result:?}");
+        assert!(matches!(result[2], Err(LinesError::Io(_))));
+
+        let result = DecodedLines::new(&b""[..], "invalid_encoding").unwrap_err();
+        assert_eq!(result, ConvertError::UnknownConversion);
+    }
+}
@@ -0,0 +1,33 @@
+#![cfg(all(feature = "std", unix))]
+
+#[macro_use]
+mod harness;
+
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use iconv_native::*;
+
+with_harness! {
+    // Locales are set in a single test, since the environment is shared by all threads.
+    fn test_locale() {
+        std::env::set_var("LC_ALL", "C.UTF-8");
+        assert!(matches!(&*locale::codeset(), "UTF-8" | "utf8"));
+        assert_eq!(locale::decode_os_str(OsStr::new("さユり")), Ok("さユり".into()));
+        assert_eq!(locale::encode_path("さユり"), Ok(Path::new("さユり").into()));
+        let invalid = OsStr::from_bytes(b"\xa4\xb5.txt");
+        assert_eq!(locale::decode_path(Path::new(invalid)), Err(ConvertError::InvalidInput));
+        assert!(matches!(locale::os_str_to_utf8(invalid), Cow::Borrowed(_)));
+
+        std::env::set_var("LC_ALL", "C");
+        assert_eq!(locale::decode_os_str(OsStr::new("a.txt")), Ok("a.txt".into()));
+        assert_eq!(locale::encode_os_str("さ"), Err(ConvertError::InvalidInput));
+        let converted = locale::os_str_to_utf8(invalid);
+        assert_eq!(converted.as_bytes(), b"\xed\xb2\xa4\xed\xb2\xb5.txt");
+        assert_eq!(locale::os_str_from_utf8(&converted), Ok(invalid.into()));
+        let result = locale::os_str_from_utf8(OsStr::new("さ"));
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+    }
+}
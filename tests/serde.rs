@@ -0,0 +1,44 @@
+#![cfg(feature = "serde")]
+
+#[macro_use]
+mod harness;
+mod strings;
+
+use serde::{Deserialize, Serialize};
+
+use strings::*;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Record {
+    #[serde(with = "iconv_native::serde::gb18030")]
+    name: String,
+    #[serde(with = "iconv_native::serde::utf_16le")]
+    alias: String,
+}
+
+with_harness! {
+    fn test_serde_roundtrip() {
+        let record = Record {
+            name: "芙宁娜".into(),
+            alias: "芙宁娜".into(),
+        };
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["name"], serde_json::json!(TEST_GB18030));
+        assert_eq!(json["alias"], serde_json::json!(TEST_UTF16_LE));
+        let result: Record = serde_json::from_value(json).unwrap();
+        assert_eq!(result, record);
+    }
+
+    fn test_serde_invalid() {
+        let json = serde_json::json!({ "name": [0xff, 0xff], "alias": [] });
+        let result = serde_json::from_value::<Record>(json);
+        assert!(result.is_err());
+
+        let json = serde_json::json!({ "name": "not bytes", "alias": [] });
+        let result = serde_json::from_value::<Record>(json);
+        assert!(result.is_err());
+
+        let result = iconv_native::serde::cp437::serialize("😀", serde_json::value::Serializer);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,146 @@
+#[macro_use]
+mod harness;
+mod strings;
+
+use iconv_native::*;
+use strings::*;
+
+fn decode_bytewise(input: &[u8], encoding: &str) -> Result<String, (ConvertError, u64)> {
+    let mut decoder = Decoder::new(encoding).unwrap();
+    let mut output = String::new();
+    for byte in input {
+        decoder
+            .decode(&[*byte], &mut output)
+            .map_err(|err| (err, decoder.position()))?;
+    }
+    decoder
+        .finish(&mut output)
+        .map_err(|err| (err, decoder.position()))?;
+    Ok(output)
+}
+
+with_harness! {
+    fn test_stream_decode() {
+        let testcases = [
+            (TEST_UTF8, "utf-8"),
+            (TEST_UTF8_BOM, "utf-8"),
+            (TEST_GB18030, "gb18030"),
+            (TEST_UTF16_LE_BOM, "utf-16"),
+            (TEST_UTF16_BE_BOM, "utf-16"),
+            (TEST_UTF16_LE_BOM, "utf-16le"),
+            (TEST_UTF32_BE, "utf-32be"),
+            (TEST_UTF32_BE_BOM, "utf-32"),
+        ];
+        let expected = std::str::from_utf8(TEST_UTF8).unwrap();
+        for (idx, (input, encoding)) in testcases.into_iter().enumerate() {
+            let result = decode_bytewise(input, encoding).unwrap();
+            assert_eq!(result, expected, "{idx}: {encoding}");
+        }
+    }
+
+    fn test_stream_decode_stateful() {
+        let testcases = [
+            (&b"a\x1b$B$\"$$\x1b(Bb"[..], "iso-2022-jp"),
+            (b"+ZYJnLIqe-", "utf-7"),
+            (b"&ZYJnLIqe-", "utf-7-imap"),
+            (b"\x00\xc8\x06\xb5\x29\x6f\x52\x7f", "gsm0338-packed"),
+        ];
+        for (idx, (input, encoding)) in testcases.into_iter().enumerate() {
+            let expected = decode(input, encoding).unwrap();
+            let result = decode_bytewise(input, encoding).unwrap();
+            assert_eq!(result, expected, "{idx}: {encoding}");
+        }
+    }
+
+    fn test_stream_decode_invalid() {
+        let result = decode_bytewise(b"abc\xe8\x8a\xffdef", "utf-8");
+        assert_eq!(result, Err((ConvertError::InvalidInput, 3)));
+        let result = decode_bytewise(b"abc\xe8\x8a", "utf-8");
+        assert_eq!(result, Err((ConvertError::InvalidInput, 3)));
+
+        let mut decoder = Decoder::new_lossy("utf-8").unwrap();
+        let mut output = String::new();
+        decoder.decode(b"abc\xe8\x8a", &mut output).unwrap();
+        decoder.finish(&mut output).unwrap();
+        assert_eq!(output, decode_lossy(b"abc\xe8\x8a", "utf-8").unwrap());
+    }
+
+    fn test_stream_decode_many_invalid() {
+        // Invalid sequences throughout a long input, found by the backend or by searching.
+        let testcases = [(&b"\x82\xa0\xff"[..], "shift_jis", "\u{3042}"), (b"+MEI-\x80", "utf-7", "\u{3042}")];
+        for (unit, encoding, expected) in testcases {
+            let input = unit.repeat(1000);
+            let mut decoder = Decoder::new_lossy(encoding).unwrap();
+            let mut output = String::new();
+            decoder.decode(&input, &mut output).unwrap();
+            decoder.finish(&mut output).unwrap();
+            assert_eq!(output.matches(expected).count(), 1000, "{encoding}");
+
+            let mut input = b"a".repeat(10_000);
+            input.extend_from_slice(unit);
+            let valid_len = input.len() - 1;
+            let mut decoder = Decoder::new(encoding).unwrap();
+            let mut output = String::new();
+            let result = decoder
+                .decode(&input, &mut output)
+                .and_then(|()| decoder.finish(&mut output));
+            assert_eq!(result, Err(ConvertError::InvalidInput), "{encoding}");
+            assert_eq!(decoder.position(), valid_len as u64, "{encoding}");
+            assert_eq!(output, "a".repeat(10_000) + expected, "{encoding}");
+        }
+    }
+
+    fn test_stream_decode_lossy_code_units() {
+        // Decoding resumes on the code unit after a lone surrogate or an invalid unit.
+        let testcases = [
+            (&b"a\x00\x00\xd8b\x00c\x00"[..], "utf-16le"),
+            (b"\x00a\xdc\x00\x00b\x00c", "utf-16be"),
+            (b"a\x00\x00\xd8b\x00c\x00", "ucs-2le"),
+            (b"a\x00\x00\x00\x00\x00\x11\x00b\x00\x00\x00c\x00\x00\x00", "utf-32le"),
+            (b"\x00\x00\x00a\x00\x00\xd8\x00\x00\x00\x00b\x00\x00\x00c", "utf-32be"),
+        ];
+        for (input, encoding) in testcases {
+            let expected = decode_lossy(input, encoding).unwrap();
+            assert!(expected.starts_with('a') && expected.ends_with("bc"), "{encoding}");
+
+            let mut decoder = Decoder::new_lossy(encoding).unwrap();
+            let mut output = String::new();
+            decoder.decode(input, &mut output).unwrap();
+            decoder.finish(&mut output).unwrap();
+            assert_eq!(output, expected, "{encoding}");
+
+            let mut decoder = Decoder::new_lossy(encoding).unwrap();
+            let mut output = String::new();
+            for byte in input {
+                decoder.decode(&[*byte], &mut output).unwrap();
+            }
+            decoder.finish(&mut output).unwrap();
+            assert_eq!(output, expected, "{encoding} byte by byte");
+        }
+    }
+
+    fn test_stream_unknown_encoding() {
+        let result = Decoder::new("invalid_encoding").unwrap_err();
+        assert_eq!(result, ConvertError::UnknownConversion);
+        let result = Encoder::new("invalid_encoding").unwrap_err();
+        assert_eq!(result, ConvertError::UnknownConversion);
+        let result = Converter::new_lossy("utf-8", "invalid_encoding").unwrap_err();
+        assert_eq!(result, ConvertLossyError::UnknownConversion);
+    }
+
+    fn test_stream_convert() {
+        let mut converter = Converter::new("gb18030", "utf-16").unwrap();
+        let mut output = vec![];
+        for byte in TEST_GB18030 {
+            converter.convert(&[*byte], &mut output).unwrap();
+        }
+        converter.finish(&mut output).unwrap();
+        assert_eq!(output, TEST_UTF16_LE_BOM);
+
+        // The converter is reusable after finish.
+        output.clear();
+        converter.convert(TEST_GB18030, &mut output).unwrap();
+        converter.finish(&mut output).unwrap();
+        assert_eq!(output, TEST_UTF16_LE_BOM);
+    }
+}
@@ -20,7 +20,7 @@ pub const TEST_UTF32_BE_BOM: &[u8] = b"\0\0\xfe\xff\0\0\x82\x99\0\0\x5b\x81\0\0\
 pub const TEST_UTF32_BE_BOM_2: &[u8] =
     b"\0\0\xfe\xff\0\0\xfe\xff\0\0\x82\x99\0\0\x5b\x81\0\0\x5a\x1c";
 
-// DE: default endianness
+// DE: default endianness of output. BOM-less input is always treated as little-endian.
 cfg_if! {
     if #[cfg(any(
         all(windows, feature = "win32"),
@@ -33,17 +33,13 @@ cfg_if! {
             )
         )
     ))] {
-        pub const TEST_UTF16_DE: &[u8] = TEST_UTF16_LE;
         pub const TEST_UTF16_DE_BOM: &[u8] = TEST_UTF16_LE_BOM;
         pub const TEST_UTF16_DE_BOM_2: &[u8] = TEST_UTF16_LE_BOM_2;
-        pub const TEST_UTF32_DE: &[u8] = TEST_UTF32_LE;
         pub const TEST_UTF32_DE_BOM: &[u8] = TEST_UTF32_LE_BOM;
         pub const TEST_UTF32_DE_BOM_2: &[u8] = TEST_UTF32_LE_BOM_2;
     } else {
-        pub const TEST_UTF16_DE: &[u8] = TEST_UTF16_BE;
         pub const TEST_UTF16_DE_BOM: &[u8] = TEST_UTF16_BE_BOM;
         pub const TEST_UTF16_DE_BOM_2: &[u8] = TEST_UTF16_BE_BOM_2;
-        pub const TEST_UTF32_DE: &[u8] = TEST_UTF32_BE;
         pub const TEST_UTF32_DE_BOM: &[u8] = TEST_UTF32_BE_BOM;
         pub const TEST_UTF32_DE_BOM_2: &[u8] = TEST_UTF32_BE_BOM_2;
     }
@@ -0,0 +1,77 @@
+#![cfg(feature = "tokio-codec")]
+
+#[macro_use]
+mod harness;
+
+use bytes::BytesMut;
+use iconv_native::tokio_codec::{LinesCodec, LinesCodecError};
+use iconv_native::ConvertError;
+use tokio_util::codec::{Decoder, Encoder};
+
+fn decode_lines(codec: &mut LinesCodec, input: &[u8]) -> Result<Vec<String>, LinesCodecError> {
+    let mut lines = vec![];
+    let mut buf = BytesMut::new();
+    for byte in input {
+        buf.extend_from_slice(&[*byte]);
+        while let Some(line) = codec.decode(&mut buf)? {
+            lines.push(line);
+        }
+    }
+    while let Some(line) = codec.decode_eof(&mut buf)? {
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+with_harness! {
+    fn test_tokio_codec_decode() {
+        let testcases = [
+            (&b"a\x1b$B$\"\x1b(B\r\n\x1b$B$$\x1b(B\nb"[..], "iso-2022-jp", &["aあ", "い", "b"][..]),
+            (b"\x1b$B$\"\n$$\x1b(B\n", "iso-2022-jp", &["あ", "い"]),
+            (b"\xff\xfea\0\n\0\n\0\x0a\x4e\n\0", "utf-16", &["a", "", "上"]),
+            (b"\xc1\x25\xc2\x25", "ibm1047", &["A", "B"]),
+            (b"\xc1\x15\xc2\x15", "ibm037", &["A", "B"]),
+            (b"\x82t\x82\r\n", "cp437", &["été"]),
+            (b"", "cp437", &[]),
+        ];
+        for (input, encoding, expected) in testcases {
+            let mut codec = LinesCodec::new(encoding).unwrap();
+            let lines = decode_lines(&mut codec, input).unwrap();
+            assert_eq!(lines, expected, "{encoding}");
+        }
+    }
+
+    fn test_tokio_codec_decode_invalid() {
+        let mut codec = LinesCodec::new("utf-8").unwrap();
+        let result = decode_lines(&mut codec, b"a\nb\xff\n");
+        assert!(matches!(
+            result,
+            Err(LinesCodecError::Convert(ConvertError::InvalidInput))
+        ));
+
+        let mut codec = LinesCodec::new_lossy("utf-8").unwrap();
+        let lines = decode_lines(&mut codec, b"a\nb\xff\n").unwrap();
+        assert_eq!(lines, ["a", "b"]);
+
+        let mut codec = LinesCodec::new("utf-8").unwrap().max_length(3);
+        let lines = decode_lines(&mut codec, b"abc\r\nd").unwrap();
+        assert_eq!(lines, ["abc", "d"]);
+        let result = decode_lines(&mut codec, b"abcd\n");
+        assert!(matches!(result, Err(LinesCodecError::MaxLineLengthExceeded)));
+    }
+
+    fn test_tokio_codec_encode() {
+        let mut codec = LinesCodec::new("utf-16").unwrap();
+        let mut buf = BytesMut::new();
+        codec.encode("a", &mut buf).unwrap();
+        codec.encode(String::from("b"), &mut buf).unwrap();
+        assert_eq!(&buf[..], b"\xff\xfea\0\n\0b\0\n\0");
+
+        let mut codec = LinesCodec::new("iso-8859-1").unwrap();
+        let result = codec.encode("€", &mut buf);
+        assert!(matches!(
+            result,
+            Err(LinesCodecError::Convert(ConvertError::InvalidInput))
+        ));
+    }
+}
@@ -0,0 +1,59 @@
+#[macro_use]
+mod harness;
+mod strings;
+
+use iconv_native::*;
+use strings::*;
+
+with_harness! {
+    fn test_ucs2_convert() {
+        let testcases = [
+            (TEST_UTF8, "utf-8", TEST_UTF16_LE, "ucs-2le"),
+            (TEST_UTF8, "utf-8", TEST_UTF16_BE, "UCS-2BE"),
+            (TEST_UTF16_BE, "utf-16be", TEST_UTF16_LE, "ucs-2le"),
+            (TEST_UTF8, "utf-8", TEST_UTF16_LE_BOM, "ucs-2"),
+        ];
+        for (idx, (input, from_encoding, output, to_encoding)) in testcases.into_iter().enumerate() {
+            let result = convert(input, from_encoding, to_encoding).unwrap();
+            assert_eq!(result, output, "{idx}: {from_encoding} => {to_encoding}");
+            let result = convert(output, to_encoding, from_encoding).unwrap();
+            assert_eq!(result, input, "{idx}: {to_encoding} => {from_encoding}");
+
+            let result = convert_lossy(input, from_encoding, to_encoding).unwrap();
+            assert_eq!(result, output, "{idx}_lossy: {from_encoding} => {to_encoding}");
+        }
+    }
+
+    fn test_ucs2_decode() {
+        let testcases = [
+            (TEST_UTF16_LE, "ucs-2"),
+            (TEST_UTF16_LE_BOM, "ucs-2"),
+            (TEST_UTF16_BE_BOM, "ucs-2"),
+            (TEST_UTF16_BE, "ucs-2be"),
+        ];
+        for (idx, (input, encoding)) in testcases.into_iter().enumerate() {
+            let result = decode(input, encoding).unwrap();
+            assert_eq!(result, "芙宁娜", "{idx}: {encoding}");
+        }
+    }
+
+    fn test_ucs2_supplementary() {
+        let result = encode("a😀", "ucs-2le");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = encode_lossy("a😀", "ucs-2le").unwrap();
+        assert_eq!(result, b"a\0\xfd\xff");
+        let result = convert("a😀".as_bytes(), "utf-8", "ucs-2be");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = convert_lossy("a😀".as_bytes(), "utf-8", "ucs-2be").unwrap();
+        assert_eq!(result, b"\0a\xff\xfd");
+
+        // A surrogate pair is valid UTF-16 but not UCS-2.
+        let input = b"\x3d\xd8\x00\xde";
+        let result = decode(input, "ucs-2le");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = decode_lossy(input, "ucs-2le").unwrap();
+        assert_eq!(result, "\u{fffd}\u{fffd}");
+        let result = convert(input, "ucs-2le", "utf-16le");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+    }
+}
@@ -0,0 +1,55 @@
+#[macro_use]
+mod harness;
+mod strings;
+
+use iconv_native::*;
+use strings::*;
+
+const TEST_UTF7: &[u8] = b"+gplbgVoc-";
+const TEST_IMAP_UTF7: &[u8] = b"INBOX/&gplbgVoc-";
+
+with_harness! {
+    fn test_utf7_convert() {
+        let testcases = [
+            (TEST_UTF8, "utf-8", TEST_UTF7, "utf-7"),
+            (TEST_UTF16_LE, "utf-16le", TEST_UTF7, "UTF7"),
+            ("INBOX/芙宁娜".as_bytes(), "utf-8", TEST_IMAP_UTF7, "utf-7-imap"),
+            (TEST_UTF7, "utf-7", b"&gplbgVoc-", "imap-utf-7"),
+        ];
+        for (idx, (input, from_encoding, output, to_encoding)) in testcases.into_iter().enumerate() {
+            let result = convert(input, from_encoding, to_encoding).unwrap();
+            assert_eq!(result, output, "{idx}: {from_encoding} => {to_encoding}");
+            let result = convert(output, to_encoding, from_encoding).unwrap();
+            assert_eq!(result, input, "{idx}: {to_encoding} => {from_encoding}");
+
+            let result = convert_lossy(input, from_encoding, to_encoding).unwrap();
+            assert_eq!(result, output, "{idx}_lossy: {from_encoding} => {to_encoding}");
+        }
+    }
+
+    fn test_utf7_decode_encode() {
+        let result = decode(b"Hi Mom -+Jjo--!", "utf-7").unwrap();
+        assert_eq!(result, "Hi Mom -☺-!");
+        let result = decode(TEST_IMAP_UTF7, "utf-7-imap").unwrap();
+        assert_eq!(result, "INBOX/芙宁娜");
+        let result = encode("Tom & Jerry", "utf-7-imap").unwrap();
+        assert_eq!(result, b"Tom &- Jerry");
+        let result = encode_lossy("a+b", "utf-7").unwrap();
+        assert_eq!(result, b"a+-b");
+    }
+
+    fn test_utf7_invalid_input() {
+        let testcases = [
+            (&b"+gplbgVo-"[..], "utf-7"),
+            (b"a\xffb", "utf-7"),
+            (b"&gplbgVoc", "utf-7-imap"),
+            (b"a\tb", "utf-7-imap"),
+        ];
+        for (idx, (input, encoding)) in testcases.into_iter().enumerate() {
+            let result = decode(input, encoding);
+            assert_eq!(result, Err(ConvertError::InvalidInput), "{idx}: {encoding}");
+            let result = decode_lossy(input, encoding).unwrap();
+            assert!(result.contains('\u{fffd}'), "{idx}_lossy: {encoding}");
+        }
+    }
+}
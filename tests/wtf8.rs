@@ -0,0 +1,79 @@
+#[macro_use]
+mod harness;
+mod strings;
+
+use iconv_native::*;
+use strings::*;
+
+const TEST_UTF16_LE_LONE: &[u8] = b"\x99\x82\x00\xd8\x81\x5b";
+const TEST_UTF16_BE_LONE: &[u8] = b"\x82\x99\xd8\x00\x5b\x81";
+const TEST_WTF8_LONE: &[u8] = b"\xe8\x8a\x99\xed\xa0\x80\xe5\xae\x81";
+
+with_harness! {
+    fn test_wtf8_roundtrip() {
+        let testcases = [
+            (TEST_UTF16_LE_LONE, "utf-16le", TEST_WTF8_LONE),
+            (TEST_UTF16_BE_LONE, "utf-16be", TEST_WTF8_LONE),
+            (TEST_UTF16_LE, "utf-16le", TEST_UTF8),
+            (b"\x00\xdc\x00\xd8", "utf-16le", b"\xed\xb0\x80\xed\xa0\x80"),
+        ];
+        for (idx, (utf16, encoding, wtf8)) in testcases.into_iter().enumerate() {
+            let result = convert(utf16, encoding, "wtf-8").unwrap();
+            assert_eq!(result, wtf8, "{idx}: {encoding} => wtf-8");
+            let result = convert(wtf8, "wtf-8", encoding).unwrap();
+            assert_eq!(result, utf16, "{idx}: wtf-8 => {encoding}");
+
+            let result = convert_lossy(utf16, encoding, "wtf-8").unwrap();
+            assert_eq!(result, wtf8, "{idx}_lossy: {encoding} => wtf-8");
+            let result = convert_lossy(wtf8, "wtf-8", encoding).unwrap();
+            assert_eq!(result, utf16, "{idx}_lossy: wtf-8 => {encoding}");
+        }
+    }
+
+    fn test_wtf8_bom() {
+        let result = convert(TEST_UTF16_BE_BOM, "utf-16", "wtf-8").unwrap();
+        assert_eq!(result, TEST_UTF8);
+        let result = convert(TEST_UTF8, "wtf-8", "utf-16").unwrap();
+        assert_eq!(result, TEST_UTF16_LE_BOM);
+    }
+
+    fn test_wtf8_other_encodings() {
+        let result = convert(TEST_UTF8, "wtf-8", "utf-32be").unwrap();
+        assert_eq!(result, TEST_UTF32_BE);
+        let result = convert(TEST_UTF32_LE, "utf-32le", "wtf-8").unwrap();
+        assert_eq!(result, TEST_UTF8);
+        let result = convert(TEST_WTF8_LONE, "wtf-8", "utf-8");
+        assert_eq!(result, Err(ConvertError::InvalidInput));
+        let result = convert_lossy(TEST_WTF8_LONE, "wtf-8", "utf-8").unwrap();
+        assert_eq!(result, "芙\u{fffd}宁".as_bytes());
+    }
+
+    fn test_wtf8_decode_encode() {
+        assert_eq!(decode(TEST_UTF8, "wtf-8").unwrap(), "芙宁娜");
+        assert_eq!(decode(TEST_WTF8_LONE, "wtf-8"), Err(ConvertError::InvalidInput));
+        assert_eq!(decode_lossy(TEST_WTF8_LONE, "wtf-8").unwrap(), "芙\u{fffd}宁");
+        assert_eq!(encode("芙宁娜", "wtf-8").unwrap(), TEST_UTF8);
+    }
+
+    fn test_wtf8_invalid_input() {
+        let testcases = [
+            (&b"\xed\xa0\xbd\xed\xb8\x80"[..], "utf-16le", &b"\x3d\xd8\xfd\xff"[..]),
+            (b"\xff", "utf-16be", b"\xff\xfd"),
+        ];
+        for (idx, (input, to_encoding, expected_lossy)) in testcases.into_iter().enumerate() {
+            let result = convert(input, "wtf-8", to_encoding);
+            assert_eq!(result, Err(ConvertError::InvalidInput), "{idx}");
+            let result = convert_lossy(input, "wtf-8", to_encoding).unwrap();
+            assert_eq!(result, expected_lossy, "{idx}_lossy");
+        }
+        let result = convert(b"\x00\xd8\x00", "utf-16le", "wtf-8");
+        assert_eq!(result, Err(ConvertError::InvalidInput), "odd length");
+    }
+
+    fn test_wtf8_utf16_units() {
+        let units = [0xd83d, 0xde00, 0xdc00, 0x61];
+        let wtf8 = decode_utf16_to_wtf8(units);
+        assert_eq!(wtf8, b"\xf0\x9f\x98\x80\xed\xb0\x80a");
+        assert_eq!(encode_wtf8_to_utf16(&wtf8).unwrap(), units);
+    }
+}